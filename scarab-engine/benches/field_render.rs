@@ -0,0 +1,135 @@
+#![feature(test)]
+extern crate test;
+
+use graphics::{Context, DrawState, Graphics, ImageSize};
+use scarab_engine::{
+    gameobject::field::{Cell, CellColorView, Field, FieldColorView, FieldMeshView},
+    rendering::Camera,
+    types::{
+        physbox::{HasBox, PhysBox},
+        NO_SOLIDITY, SOLID,
+    },
+};
+use test::{black_box, Bencher};
+
+struct NullTexture;
+
+impl ImageSize for NullTexture {
+    fn get_size(&self) -> (u32, u32) {
+        (0, 0)
+    }
+}
+
+/// A backend that only sums up the vertices it's given, so the benchmarks measure the
+/// work done before anything reaches the GPU
+#[derive(Default)]
+struct CountingGraphics {
+    vertices: usize,
+    checksum: f32,
+}
+
+impl CountingGraphics {
+    fn receive(&mut self, vertices: &[[f32; 2]]) {
+        self.vertices += vertices.len();
+        self.checksum += vertices.iter().map(|[x, y]| x + y).sum::<f32>();
+    }
+}
+
+impl Graphics for CountingGraphics {
+    type Texture = NullTexture;
+
+    fn clear_color(&mut self, _color: [f32; 4]) {}
+
+    fn clear_stencil(&mut self, _value: u8) {}
+
+    fn tri_list<F>(&mut self, _draw_state: &DrawState, _color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        f(&mut |vertices| self.receive(vertices));
+    }
+
+    fn tri_list_c<F>(&mut self, _draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        f(&mut |vertices, _| self.receive(vertices));
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        _draw_state: &DrawState,
+        _color: &[f32; 4],
+        _texture: &NullTexture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        f(&mut |vertices, _| self.receive(vertices));
+    }
+
+    fn tri_list_uv_c<F>(&mut self, _draw_state: &DrawState, _texture: &NullTexture, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        f(&mut |vertices, _, _| self.receive(vertices));
+    }
+}
+
+/// A 100x100 grid of alternating solid and air cells, all in view
+fn grid_field() -> (Field, Camera, FieldColorView) {
+    let mut cells = Vec::new();
+    for y in 0..100 {
+        for x in 0..100 {
+            let solidity = if (x + y) % 2 == 0 { SOLID } else { NO_SOLIDITY };
+            let physbox = PhysBox::new([x as f64 * 4.0, y as f64 * 4.0, 4.0, 4.0]).unwrap();
+            cells.push(Cell::new(solidity, physbox));
+        }
+    }
+    let field = Field::new(cells).unwrap();
+    let camera = Camera::new(
+        PhysBox::new([0.0, 0.0, 400.0, 400.0]).unwrap(),
+        [800.0, 800.0],
+    );
+    let colors = FieldColorView {
+        solid_view: CellColorView {
+            color: [0.0, 0.0, 0.0, 1.0],
+        },
+        air_view: CellColorView {
+            color: [1.0, 1.0, 1.0, 1.0],
+        },
+        default_view: CellColorView {
+            color: [0.5, 0.5, 0.5, 1.0],
+        },
+    };
+    (field, camera, colors)
+}
+
+#[bench]
+fn per_cell_field_render(b: &mut Bencher) {
+    let (field, camera, colors) = grid_field();
+    let ctx = Context::new_abs(800.0, 800.0);
+    let mut g = CountingGraphics::default();
+    // The same as rendering each cell with its CellColorView
+    b.iter(|| {
+        for cell in field.cells() {
+            if let Some((transform, rect)) = camera.box_renderables(cell.get_box(), ctx) {
+                graphics::rectangle(colors.color_for_cell(cell), rect, transform, &mut g);
+            }
+        }
+        black_box((g.vertices, g.checksum))
+    });
+}
+
+#[bench]
+fn cached_mesh_field_render(b: &mut Bencher) {
+    let (field, camera, colors) = grid_field();
+    let ctx = Context::new_abs(800.0, 800.0);
+    let mut g = CountingGraphics::default();
+    let mut view = FieldMeshView::new(colors);
+    view.prepare(&field);
+    b.iter(|| {
+        view.draw(&field, &camera, ctx, &mut g);
+        black_box((g.vertices, g.checksum))
+    });
+}
@@ -1,7 +1,102 @@
+use std::time::Instant;
+
+use glutin_window::GlutinWindow;
 use piston::{
-    CloseArgs, CloseEvent, Event, EventSettings, Events, Input, RenderArgs, RenderEvent,
-    ResizeArgs, ResizeEvent, UpdateArgs, UpdateEvent, Window,
+    BuildFromWindowSettings, CloseArgs, CloseEvent, Event, EventSettings, Events, Input,
+    RenderArgs, RenderEvent, ResizeArgs, ResizeEvent, UpdateArgs, UpdateEvent, Window,
+    WindowSettings,
 };
+use serde::{Deserialize, Serialize};
+
+use crate::{rendering::Camera, timestep::FixedTimestep, ScarabError, ScarabResult};
+
+/// The highest MSAA sample count that will be requested
+const MAX_SAMPLES: u8 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How the window's GL context anti-aliases edges
+pub enum AntiAliasing {
+    /// No anti-aliasing, keeps pixel art crisp
+    Off,
+    /// Multisample anti-aliasing with the given number of samples per pixel
+    Msaa(u8),
+}
+
+impl AntiAliasing {
+    /// The default for pixel art, where smoothing would blur the pixels
+    pub const PIXEL_ART: Self = Self::Off;
+    /// The default for smooth art, where diagonal edges would otherwise alias
+    pub const SMOOTH: Self = Self::Msaa(4);
+
+    /// The sample count requested on context creation. GL only accepts powers of two,
+    /// so other counts are rounded down to the nearest one
+    pub fn samples(&self) -> u8 {
+        match *self {
+            Self::Off | Self::Msaa(0) => 0,
+            Self::Msaa(n) => {
+                let n = n.min(MAX_SAMPLES);
+                1 << (u8::BITS - 1 - n.leading_zeros())
+            }
+        }
+    }
+
+    /// The sample counts to try in order, from the requested count down to no anti-aliasing
+    pub fn fallback_samples(&self) -> Vec<u8> {
+        let mut samples = vec![self.samples()];
+        while let Some(&last) = samples.last() {
+            match last {
+                0 => break,
+                1 => samples.push(0),
+                n => samples.push(n / 2),
+            }
+        }
+        samples
+    }
+}
+
+impl Default for AntiAliasing {
+    fn default() -> Self {
+        Self::PIXEL_ART
+    }
+}
+
+/// A window that can report how many samples its GL context actually got
+pub trait SampledWindow {
+    /// The MSAA sample count of the window's framebuffer, 0 without multisampling
+    fn samples(&self) -> u8;
+}
+
+impl SampledWindow for GlutinWindow {
+    fn samples(&self) -> u8 {
+        self.ctx
+            .get_pixel_format()
+            .multisampling
+            .map(|s| s.min(u8::MAX as u16) as u8)
+            .unwrap_or(0)
+    }
+}
+
+/// Builds a window with the given anti-aliasing. If the hardware doesn't support the requested
+/// sample count, the nearest lower count that it does support is used instead
+pub fn build_window<W: BuildFromWindowSettings + SampledWindow>(
+    settings: WindowSettings,
+    anti_aliasing: AntiAliasing,
+) -> ScarabResult<W> {
+    let mut last_err = None;
+    for samples in anti_aliasing.fallback_samples() {
+        match settings.clone().samples(samples).build::<W>() {
+            // Some backends quietly drop multisampling instead of failing, so check what was
+            // actually created before settling for a lower count
+            Ok(window) if samples == 0 || window.samples() >= samples => return Ok(window),
+            Ok(_) => {}
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(ScarabError::RawString(format!(
+        "Could not build window: {}",
+        last_err.map(|e| e.to_string()).unwrap_or_default()
+    )))
+}
 
 /// A trait to simplify some of the boilerplate in running an app
 pub trait App<W: Window> {
@@ -11,7 +106,9 @@ pub trait App<W: Window> {
     /// Runs the render loop
     fn render(&mut self, args: &RenderArgs);
 
-    /// Runs the fixed time update loop
+    /// Runs the fixed time update loop. To pause the simulation while overlays (i.e. a pause
+    /// menu) keep updating, forward this to a [SceneStack](crate::scene_stack::SceneStack) with
+    /// the overlay on top of the frozen simulation
     fn update(&mut self, args: &UpdateArgs);
 
     /// Controls the window resize event
@@ -28,9 +125,41 @@ pub trait App<W: Window> {
         Events::new(EventSettings::new())
     }
 
+    /// The app's own fixed timestep. Override to return `Some` to have [App::run] drive updates
+    /// from the real time elapsed between renders instead of piston's update events.
+    /// The timestep's [FixedTimestep::alpha] is then up to date whenever [App::render] is called
+    fn fixed_timestep(&mut self) -> Option<&mut FixedTimestep> {
+        None
+    }
+
+    /// The camera the app renders through. Override to return `Some` to have [App::run] keep its
+    /// [Camera::render_alpha] in step with the [App::fixed_timestep]
+    fn camera(&mut self) -> Option<&mut Camera> {
+        None
+    }
+
+    /// Advances the [App::fixed_timestep] by `dt` seconds of real time, updating as many times
+    /// as the timestep allows. The camera is then given the leftover fraction of a step to
+    /// render with
+    fn advance(&mut self, dt: f64) {
+        if let Some(timestep) = self.fixed_timestep() {
+            let step = timestep.step();
+            for _ in 0..timestep.advance(dt) {
+                self.update(&UpdateArgs { dt: step });
+            }
+        }
+
+        if let Some(alpha) = self.fixed_timestep().map(|t| t.alpha()) {
+            if let Some(camera) = self.camera() {
+                camera.set_render_alpha(alpha);
+            }
+        }
+    }
+
     /// Run the app until it is exited
     fn run(mut self: Box<Self>) {
         let mut events = self.events();
+        let mut last_render = Instant::now();
         while let Some(e) = events.next(self.window()) {
             if let Some(args) = e.close_args() {
                 self.close(&args);
@@ -38,11 +167,21 @@ pub trait App<W: Window> {
             }
 
             if let Some(args) = e.render_args() {
+                let now = Instant::now();
+                let dt = (now - last_render).as_secs_f64();
+                last_render = now;
+
+                if self.fixed_timestep().is_some() {
+                    self.advance(dt);
+                }
+
                 self.render(&args);
             }
 
             if let Some(args) = e.update_args() {
-                self.update(&args);
+                if self.fixed_timestep().is_none() {
+                    self.update(&args);
+                }
             }
 
             if let Some(args) = e.resize_args() {
@@ -58,3 +197,196 @@ pub trait App<W: Window> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, error::Error, rc::Rc};
+
+    use piston::NoWindow;
+    use shapes::Point;
+
+    use super::*;
+    use crate::{
+        gameobject::{entity::registry::test::TestEntity, field::FieldColorView},
+        scene::{test::test_scene, Scene},
+        scene_stack::{LayerFlags, SceneStack, StackedScene},
+        types::physbox::{HasBox, PhysBox},
+    };
+
+    thread_local! {
+        static REQUESTED: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Records the requested sample counts and only supports up to 4 samples
+    struct TestWindow {
+        samples: u8,
+    }
+
+    impl BuildFromWindowSettings for TestWindow {
+        fn build_from_window_settings(settings: &WindowSettings) -> Result<Self, Box<dyn Error>> {
+            let samples = settings.get_samples();
+            REQUESTED.with(|r| r.borrow_mut().push(samples));
+            if samples > 4 {
+                Err("unsupported sample count".into())
+            } else {
+                Ok(Self { samples })
+            }
+        }
+    }
+
+    impl SampledWindow for TestWindow {
+        fn samples(&self) -> u8 {
+            self.samples
+        }
+    }
+
+    fn build(anti_aliasing: AntiAliasing) -> (u8, Vec<u8>) {
+        REQUESTED.with(|r| r.borrow_mut().clear());
+        let window: TestWindow =
+            build_window(WindowSettings::new("test", [10, 10]), anti_aliasing).unwrap();
+        (window.samples, REQUESTED.with(|r| r.take()))
+    }
+
+    #[test]
+    fn configured_samples_requested_with_fallback() {
+        assert_eq!(build(AntiAliasing::SMOOTH), (4, vec![4]));
+        assert_eq!(build(AntiAliasing::PIXEL_ART), (0, vec![0]));
+        // 6 isn't a power of two and 4 is the most this "hardware" supports
+        assert_eq!(build(AntiAliasing::Msaa(6)), (4, vec![4]));
+        assert_eq!(build(AntiAliasing::Msaa(32)), (4, vec![16, 8, 4]));
+    }
+
+    /// The simulation, moving its entities
+    struct Gameplay(Rc<RefCell<Scene<TestEntity, FieldColorView>>>);
+
+    impl StackedScene<()> for Gameplay {
+        fn update(&mut self, args: &UpdateArgs) -> ScarabResult<()> {
+            self.0.borrow_mut().tick_entities(args.dt)
+        }
+
+        fn render(&mut self, _args: &RenderArgs, _render_with: &mut ()) -> ScarabResult<()> {
+            Ok(())
+        }
+    }
+
+    /// A pause menu with a pulsing label
+    struct PauseMenu {
+        pulse: Rc<RefCell<f64>>,
+    }
+
+    impl StackedScene<()> for PauseMenu {
+        fn update(&mut self, args: &UpdateArgs) -> ScarabResult<()> {
+            *self.pulse.borrow_mut() += args.dt;
+            Ok(())
+        }
+
+        fn render(&mut self, _args: &RenderArgs, _render_with: &mut ()) -> ScarabResult<()> {
+            Ok(())
+        }
+    }
+
+    struct TestApp {
+        window: NoWindow,
+        stack: SceneStack<()>,
+        timestep: Option<FixedTimestep>,
+        camera: Camera,
+        updates: usize,
+    }
+
+    impl App<NoWindow> for TestApp {
+        fn close(self: Box<Self>, _args: &CloseArgs) {}
+
+        fn render(&mut self, args: &RenderArgs) {
+            self.stack.render(args, &mut ()).unwrap();
+        }
+
+        fn update(&mut self, args: &UpdateArgs) {
+            self.stack.update(args).unwrap();
+            self.updates += 1;
+        }
+
+        fn resize(&mut self, _args: &ResizeArgs) {}
+
+        fn window(&mut self) -> &mut NoWindow {
+            &mut self.window
+        }
+
+        fn input_event(&mut self, input: Input) {
+            self.stack.input_event(&input);
+        }
+
+        fn fixed_timestep(&mut self) -> Option<&mut FixedTimestep> {
+            self.timestep.as_mut()
+        }
+
+        fn camera(&mut self) -> Option<&mut Camera> {
+            Some(&mut self.camera)
+        }
+    }
+
+    fn test_app() -> TestApp {
+        TestApp {
+            window: NoWindow::new(&WindowSettings::new("test", [10, 10])),
+            stack: SceneStack::new(),
+            timestep: None,
+            updates: 0,
+            camera: Camera::new(PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap(), [10.0, 10.0]),
+        }
+    }
+
+    #[test]
+    fn pause_menu_updates_while_simulation_is_paused() {
+        let mut entity = TestEntity::with_box([10.0, 10.0, 5.0, 5.0]);
+        entity.0.set_max_velocity(100.0).unwrap();
+        entity.0.set_velocity([10.0, 0.0].into());
+        let scene = Rc::new(RefCell::new(test_scene()));
+        let id = scene.borrow_mut().register_entity(entity).unwrap();
+        let pulse = Rc::new(RefCell::new(0.0));
+        let mut app = test_app();
+        app.stack
+            .push(Box::new(Gameplay(scene.clone())), LayerFlags::default());
+        let pos = || -> Point {
+            *scene
+                .borrow()
+                .entities()
+                .get_by_id(id)
+                .unwrap()
+                .get_box()
+                .pos()
+        };
+
+        app.update(&UpdateArgs { dt: 0.5 });
+        assert_eq!(pos(), [15.0, 10.0].into());
+
+        // Pausing pushes the menu over the simulation, which stays frozen underneath it
+        app.stack.push(
+            Box::new(PauseMenu {
+                pulse: pulse.clone(),
+            }),
+            LayerFlags::default(),
+        );
+        for _ in 0..3 {
+            app.update(&UpdateArgs { dt: 0.5 });
+        }
+        assert_eq!(pos(), [15.0, 10.0].into());
+        assert_eq!(*pulse.borrow(), 1.5);
+
+        app.stack.pop();
+        app.update(&UpdateArgs { dt: 0.5 });
+        assert_eq!(pos(), [20.0, 10.0].into());
+    }
+
+    #[test]
+    fn fixed_steps_hand_render_alpha_to_camera() {
+        let mut app = test_app();
+        app.timestep = Some(FixedTimestep::new(0.1).unwrap());
+
+        app.advance(0.25);
+        assert_eq!(app.updates, 2);
+        assert!((app.camera.render_alpha() - 0.5).abs() < 1e-9);
+
+        // The leftover carries, so the next frame lands a quarter of the way into a step
+        app.advance(0.075);
+        assert!((app.camera.render_alpha() - 0.25).abs() < 1e-9);
+    }
+}
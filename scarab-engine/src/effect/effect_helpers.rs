@@ -1,16 +1,18 @@
+use std::collections::HashMap;
+
 use graphics::types::Scalar;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    gameobject::entity::registry::RegisteredEntity,
+    gameobject::entity::{crowd_control::CrowdControl, registry::RegisteredEntity},
     types::{
         physbox::{HasBox, PhysBox},
-        HasHealth,
+        HasUuid, Uuid,
     },
     ScarabResult,
 };
 
-use super::{Effect, EffectTarget, PendingEffect};
+use super::{Effect, EffectSource, EffectTarget, PendingEffect};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
 /// Expresses the readiness of an effect
@@ -80,12 +82,24 @@ impl TryAction {
 /// Represents an attack that does a raw amount of damage on an entity
 pub struct BasicAttack {
     damage: Scalar,
+    /// The upward speed targets are launched at (see [Entity::launch](crate::gameobject::entity::Entity::launch))
+    #[serde(default)]
+    launch: Option<Scalar>,
 }
 
 impl BasicAttack {
     /// Sets the raw damage for this attack
     pub fn new(damage: Scalar) -> Self {
-        Self { damage }
+        Self {
+            damage,
+            launch: None,
+        }
+    }
+
+    /// Makes the attack knock its targets up into the air at `speed`
+    pub fn with_launch(mut self, speed: Scalar) -> Self {
+        self.launch = Some(speed);
+        self
     }
 
     /// Transforms self into a pending effect so it can be applied on the next tick
@@ -104,7 +118,34 @@ impl BasicAttack {
 
 impl<E: RegisteredEntity> Effect<E> for BasicAttack {
     fn apply_effect(&mut self, target: &mut E) -> ScarabResult<bool> {
-        target.get_health_mut().raw_damage(self.damage);
+        let entity = target.inner_entity_mut();
+        if entity.take_damage(self.damage) {
+            if let Some(speed) = self.launch {
+                entity.launch(speed);
+            }
+        }
+        Ok(false)
+    }
+
+    fn update_src(&mut self, _src: &mut E) -> ScarabResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+/// Stuns, roots or silences the entities it targets
+pub struct ApplyCrowdControl {
+    /// The state to apply
+    pub kind: CrowdControl,
+    /// How long the state lasts in seconds
+    pub duration: f64,
+}
+
+impl<E: RegisteredEntity> Effect<E> for ApplyCrowdControl {
+    fn apply_effect(&mut self, target: &mut E) -> ScarabResult<bool> {
+        target
+            .inner_entity_mut()
+            .apply_crowd_control(self.kind, self.duration);
         Ok(false)
     }
 
@@ -113,6 +154,81 @@ impl<E: RegisteredEntity> Effect<E> for BasicAttack {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An attack that stays in place and can hit each target repeatedly (i.e. a spinning blade or a fire aura).
+/// After hitting a target it can't hit that target again until `hit_interval` seconds have passed,
+/// but it's free to hit any other target immediately
+pub struct PersistentHazard {
+    damage: Scalar,
+    hit_interval: f64,
+    /// The remaining lifetime of the hazard, it never expires when None
+    remaining: Option<f64>,
+    /// How long until the hazard can hit each recently hit target again
+    target_cooldowns: HashMap<Uuid, Cooldown>,
+}
+
+impl PersistentHazard {
+    /// A hazard doing `damage` to each target at most once every `hit_interval` seconds.
+    /// Lasts for `duration` seconds, or forever if None
+    pub fn new(damage: Scalar, hit_interval: f64, duration: Option<f64>) -> Self {
+        Self {
+            damage,
+            hit_interval,
+            remaining: duration,
+            target_cooldowns: HashMap::new(),
+        }
+    }
+
+    /// Whether the hazard is currently unable to hit the target with the given uuid
+    pub fn is_cooling_for(&self, target: Uuid) -> bool {
+        self.target_cooldowns.contains_key(&target)
+    }
+
+    /// Transforms self into a pending effect covering `target_area`
+    pub fn into_pending_effect<E: RegisteredEntity>(
+        self,
+        source: Option<EffectSource>,
+        target_area: PhysBox,
+    ) -> PendingEffect<E> {
+        PendingEffect {
+            source,
+            target: Box::new(target_area),
+            effect: Box::new(self),
+        }
+    }
+}
+
+impl<E: RegisteredEntity> Effect<E> for PersistentHazard {
+    fn apply_effect(&mut self, target: &mut E) -> ScarabResult<bool> {
+        let uuid = target.uuid();
+        if !self.is_cooling_for(uuid) && target.inner_entity_mut().take_damage(self.damage) {
+            self.target_cooldowns
+                .insert(uuid, Cooldown::Cooling(self.hit_interval));
+        }
+        Ok(true)
+    }
+
+    fn update_src(&mut self, _src: &mut E) -> ScarabResult<()> {
+        Ok(())
+    }
+
+    fn advance(&mut self, dt: f64) -> bool {
+        for cooldown in self.target_cooldowns.values_mut() {
+            cooldown.cool(dt);
+        }
+        self.target_cooldowns
+            .retain(|_, cooldown| *cooldown != Cooldown::Ready);
+
+        match self.remaining.as_mut() {
+            Some(remaining) => {
+                *remaining -= dt;
+                *remaining > 0.0
+            }
+            None => true,
+        }
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 /// A sample effect for targeting a physbox and moving towards its position
 pub struct FollowBox {
@@ -127,7 +243,7 @@ impl<E: RegisteredEntity> Effect<E> for FollowBox {
     }
 
     fn update_src(&mut self, src: &mut E) -> ScarabResult<()> {
-        if let Some(target) = self.target {
+        if let Some(target) = self.target.filter(|_| src.inner_entity().can_move()) {
             let velocity = (*target.pos() - *src.get_box().pos()).into();
             src.inner_entity_mut().set_velocity(velocity);
         }
@@ -156,6 +272,10 @@ impl<E: RegisteredEntity> EffectTarget<E> for TargetFirstPlayer {
 mod test {
     use super::*;
 
+    use crate::{
+        gameobject::entity::registry::test::TestEntity, scene::test::test_scene, types::HasHealth,
+    };
+
     #[test]
     fn try_action_sets_doing_when_ready() {
         let mut try_action = TryAction {
@@ -247,4 +367,90 @@ mod test {
         cooldown.cool(5.0);
         assert_eq!(cooldown, Cooldown::Ready);
     }
+
+    #[test]
+    fn persistent_hazard_hits_each_target_once_per_interval() {
+        let mut scene = test_scene();
+        let inside = scene
+            .register_entity(TestEntity::with_box([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let hazard = PersistentHazard::new(1.0, 1.0, None);
+        scene.add_pending_effect(
+            hazard.into_pending_effect(None, PhysBox::new([0.0, 0.0, 50.0, 50.0]).unwrap()),
+        );
+
+        let health_of = |scene: &crate::scene::Scene<_, _>, id| {
+            let entity: &TestEntity = scene.entities().get_by_id(id).unwrap();
+            entity.get_health().current()
+        };
+
+        // Hit on the first tick, then every 4 ticks of 0.25s
+        let mut inside_health = vec![];
+        for _ in 0..9 {
+            scene.tick_entities(0.25).unwrap();
+            inside_health.push(health_of(&scene, inside));
+        }
+        assert_eq!(
+            inside_health,
+            vec![9.0, 9.0, 9.0, 9.0, 8.0, 8.0, 8.0, 8.0, 7.0]
+        );
+
+        // A new target is hit right away even though the first is still cooling
+        let entered = scene
+            .register_entity(TestEntity::with_box([20.0, 20.0, 5.0, 5.0]))
+            .unwrap();
+        scene.tick_entities(0.25).unwrap();
+        assert_eq!(health_of(&scene, entered), 9.0);
+        assert_eq!(health_of(&scene, inside), 7.0);
+    }
+
+    #[test]
+    fn knock_up_rises_falls_and_lands() {
+        use crate::{
+            gameobject::field::{Cell, Field},
+            scene::GameTickArgs,
+            types::{NO_SOLIDITY, SOLID},
+        };
+
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 40.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 40.0, 50.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+        let mut pending_effects: Vec<PendingEffect<TestEntity>> = Vec::new();
+        let args = GameTickArgs {
+            field: &field,
+            pending_effects: &mut pending_effects,
+            dt: 0.05,
+        };
+        let mut target = TestEntity::with_box([10.0, 36.0, 4.0, 4.0]);
+        target.0.set_gravity(Some(100.0));
+
+        let mut attack = BasicAttack::new(1.0).with_launch(50.0);
+        attack.apply_effect(&mut target).unwrap();
+        assert!(target.0.is_airborne());
+        assert_eq!(target.0.get_velocity().y, -50.0);
+
+        let mut heights = vec![];
+        while target.0.is_airborne() && heights.len() < 100 {
+            target.0.game_tick(&args).unwrap();
+            heights.push(target.0.get_box().top_y());
+        }
+        let peak = heights.iter().copied().fold(f64::INFINITY, f64::min);
+        // Rose by about v^2 / 2g before falling back down
+        assert!(peak < 36.0 - 10.0);
+        assert!(heights[0] > peak);
+        assert_eq!(target.0.get_box().top_y(), 36.0);
+        assert!(!target.0.is_airborne());
+        assert_eq!(target.0.get_velocity().y, 0.0);
+        assert_eq!(target.get_health().current(), 9.0);
+    }
+
+    #[test]
+    fn persistent_hazard_expires() {
+        let mut hazard = PersistentHazard::new(1.0, 1.0, Some(0.5));
+
+        assert!(Effect::<TestEntity>::advance(&mut hazard, 0.25));
+        assert!(!Effect::<TestEntity>::advance(&mut hazard, 0.25));
+    }
 }
@@ -68,6 +68,12 @@ pub trait Effect<E>: Debug {
     /// Apply any necessary updates to the source of the effect
     /// This could be animation states, draining energy or any other necessary effect
     fn update_src(&mut self, src: &mut E) -> ScarabResult<()>;
+
+    /// Advances any timers kept by the effect by `dt`. Called once per tick before the effect is applied.
+    /// Returns whether the effect should stay pending even if it doesn't apply to any targets
+    fn advance(&mut self, _dt: f64) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
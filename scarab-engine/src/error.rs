@@ -4,6 +4,8 @@ use shapes::Point;
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::save::SaveFormat;
+
 /// A genric result for Scarab Engine operations
 pub type ScarabResult<T> = Result<T, ScarabError>;
 
@@ -22,6 +24,9 @@ pub enum ScarabError {
     #[error("Attempted to register an entity with a pre-existing UUID: {0}")]
     /// Registering a new entity failed
     EntityRegistration(Uuid),
+    #[error("No prefab registered with the name {0}")]
+    /// Spawning a prefab that isn't registered to the scene
+    UnknownPrefab(String),
     #[error(transparent)]
     /// I/O Errors
     IoError(#[from] std::io::Error),
@@ -31,6 +36,12 @@ pub enum ScarabError {
     #[error(transparent)]
     /// Error related to rendering/graphics,
     RenderingError(#[from] RenderError),
+    #[error(transparent)]
+    /// Errors loading and validating spawn definitions
+    SpawnError(#[from] SpawnError),
+    #[error(transparent)]
+    /// Errors compressing and decompressing save data
+    SaveError(#[from] SaveError),
 }
 
 /// A generic result type for physics operations
@@ -48,6 +59,57 @@ pub enum PhysicsError {
     #[error("Maximum velocity must be positive")]
     /// Occurs when an invalid maximum velocity is set
     MaxVelocity,
+    #[error("Contact skin must be positive")]
+    /// Occurs when an invalid contact skin is set
+    ContactSkin,
+    #[error("Maximum penetration correction must be positive")]
+    /// Occurs when an invalid maximum penetration correction is set
+    PenetrationCorrection,
+    #[error("Visibility radius must be positive")]
+    /// Occurs when an invalid visibility radius is set
+    VisibilityRadius,
+    #[error("Overlap push strength must be in (0, 1] with at least one iteration")]
+    /// Occurs when invalid entity overlap push settings are set
+    OverlapPush,
+    #[error("Continuous collision threshold must be positive")]
+    /// Occurs when an invalid continuous collision threshold is set
+    ContinuousThreshold,
+    #[error("Restitution must be in [0, 1]")]
+    /// Occurs when an invalid restitution is set
+    Restitution,
+    #[error("Interaction range must be non-negative")]
+    /// Occurs when an entity is made interactable with an invalid range
+    InteractRange,
+    #[error("Mass must be positive")]
+    /// Occurs when an invalid mass is set
+    Mass,
+    #[error("Throw impulse must be positive")]
+    /// Occurs when an entity is made throwable with an invalid impulse
+    ThrowImpulse,
+    #[error("Shield max must be positive, with a non-negative regen rate and delay and no more than the max left")]
+    /// Occurs when an invalid shield is created or loaded
+    Shield,
+    #[error("Fixed timestep steps must be positive and finite")]
+    /// Occurs when a fixed timestep is created or loaded with an invalid step
+    TimestepStep,
+    #[error("Drag must be non-negative")]
+    /// Occurs when an invalid air drag is set
+    Drag,
+    #[error("Riding tolerance must be non-negative")]
+    /// Occurs when platform riding is set with an invalid tolerance
+    RidingTolerance,
+    #[error("Dash duration must be positive, with a non-negative distance and cooldown")]
+    /// Occurs when an invalid dash is created or loaded
+    Dash,
+    #[error("Grid of {0} tiles can't be split into rows of {1}")]
+    /// Occurs when a grid's tiles don't fill a whole number of rows
+    GridShape(usize, usize),
+    #[error("Field cells don't line up with the tile grid")]
+    /// Occurs when converting a field to a grid that its cells don't exactly cover
+    NotAGrid,
+    #[error("No clear position within {1} of {0:?} to spawn at")]
+    /// Occurs when spawning an entity and it doesn't fit anywhere near where it was spawned
+    NoClearPosition(Point, f64),
     #[error("Could not find field cell at position {0:?}")]
     /// Occurs when there is no cell on the field at the given point
     NoFieldCell(Point),
@@ -73,9 +135,13 @@ pub enum RenderError {
     /// 'String' is the specific error message
     #[error("Could not load texture {0}: {1}")]
     CouldNotLoadTexture(PathBuf, String),
+    /// Occurs when an offscreen render target can't be created
+    /// 'String' is the specific error message
+    #[error("Could not create render target: {0}")]
+    RenderTarget(String),
 }
 
-#[derive(Debug, Error, PartialEq)]
+#[derive(Debug, Clone, Error, PartialEq)]
 /// An error specific to sprite animations
 pub enum AnimationError {
     /// Occurs when creating a sprite animation and the number of frames requested
@@ -85,4 +151,39 @@ pub enum AnimationError {
     /// An ASM doesn't have an animation for the animation state
     #[error("No animation loaded for state {0}")]
     NoAnimationForState(String),
+    /// A frame effect names a socket that the viewed object doesn't have
+    #[error("No socket {0} to spawn {1} at for state {2}")]
+    NoSocketForEffect(String, String, String),
+}
+
+#[derive(Debug, Error, PartialEq)]
+/// An error loading or validating spawn definitions
+pub enum SpawnError {
+    #[error("Could not parse spawn definitions from {0}: {1}")]
+    /// Occurs when a spawn definitions file isn't in the expected format.
+    /// 'String' is the specific error message
+    Parse(PathBuf, String),
+    #[error("Spawn definition {0} is built from the unknown prefab {1}")]
+    /// Occurs when a spawn definition's prefab isn't registered
+    UnknownPrefab(String, String),
+    #[error("Spawn definition {0} has the unknown behavior {1}, expected one of [{2}]")]
+    /// Occurs when a spawn definition's behavior isn't one of the game's
+    UnknownBehavior(String, String, String),
+    #[error("Spawn definition {0} has the unknown animation {1}, expected one of [{2}]")]
+    /// Occurs when a spawn definition's animation isn't one of the game's
+    UnknownAnimation(String, String, String),
+}
+
+#[derive(Debug, Error, PartialEq)]
+/// An error compressing or decompressing save data
+pub enum SaveError {
+    #[error("{0:?} saves need the engine's feature of the same name")]
+    /// Occurs when saving or loading in a format the engine was built without
+    Unsupported(SaveFormat),
+    #[error("Could not compress the save as {0:?}: {1}")]
+    /// Occurs when the compressor fails. 'String' is the specific error message
+    Compression(SaveFormat, String),
+    #[error("Could not decompress the {0:?} save: {1}")]
+    /// Occurs when the save data is corrupt. 'String' is the specific error message
+    Decompression(SaveFormat, String),
 }
@@ -0,0 +1,739 @@
+use std::{collections::HashMap, f64::consts::TAU};
+
+use graphics::types::Scalar;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+use super::{registry::EntityId, Entity};
+use crate::{
+    gameobject::field::Field,
+    scene::HealthChange,
+    types::{
+        physbox::{HasBox, PhysBox},
+        DEFAULT_EPSILON,
+    },
+};
+
+/// How many random points a wander tries before giving up on moving this time
+const MAX_WANDER_ATTEMPTS: usize = 16;
+
+fn has_arrived(entity: &Entity, target: Point, arrive_radius: Scalar) -> bool {
+    let pos = *entity.get_box().pos();
+    f64::hypot(target.x - pos.x, target.y - pos.y) <= arrive_radius.max(DEFAULT_EPSILON)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// What a patrol does after reaching its last waypoint
+pub enum PatrolMode {
+    /// Heads straight back to the first waypoint
+    Loop,
+    /// Visits the waypoints again in reverse order
+    PingPong,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Steers an entity between waypoints, one after another.
+///
+/// The entity follows a path through the field to each waypoint (see [PathFollower]),
+/// so it finds its way around any walls between them
+pub struct Patrol {
+    waypoints: Vec<Point>,
+    mode: PatrolMode,
+    current: usize,
+    forward: bool,
+    /// How close the entity's position has to get to a waypoint to have reached it
+    pub arrive_radius: Scalar,
+    /// The path to the current waypoint, found on the next steer
+    #[serde(skip)]
+    route: Option<PathFollower>,
+}
+
+impl Patrol {
+    /// Creates a patrol visiting `waypoints` in order, starting with the first
+    pub fn new(waypoints: Vec<Point>, mode: PatrolMode) -> Self {
+        Self {
+            waypoints,
+            mode,
+            current: 0,
+            forward: true,
+            arrive_radius: DEFAULT_EPSILON,
+            route: None,
+        }
+    }
+
+    /// The waypoint the entity is heading towards, `None` without any waypoints
+    pub fn current_waypoint(&self) -> Option<Point> {
+        self.waypoints.get(self.current).copied()
+    }
+
+    fn advance(&mut self) {
+        let last = self.waypoints.len().saturating_sub(1);
+        if last == 0 {
+            return;
+        }
+        self.current = match self.mode {
+            PatrolMode::Loop => (self.current + 1) % self.waypoints.len(),
+            PatrolMode::PingPong => {
+                if self.forward && self.current == last {
+                    self.forward = false;
+                } else if !self.forward && self.current == 0 {
+                    self.forward = true;
+                }
+                if self.forward {
+                    self.current + 1
+                } else {
+                    self.current - 1
+                }
+            }
+        };
+    }
+
+    /// Moves on to the next waypoint if the entity has reached the current one, then steers
+    /// the entity along the path towards it over `dt`. Should be called before the entity moves
+    pub fn steer(&mut self, entity: &mut Entity, field: &Field, dt: Scalar) {
+        let waypoint = match self.current_waypoint() {
+            Some(waypoint) => waypoint,
+            None => return,
+        };
+        let waypoint = if has_arrived(entity, waypoint, self.arrive_radius) {
+            self.advance();
+            self.waypoints[self.current]
+        } else {
+            waypoint
+        };
+
+        // Paths are followed by the entity's center
+        let size = *entity.get_box().size();
+        let goal = Point::from([waypoint.x + size.w / 2.0, waypoint.y + size.h / 2.0]);
+        let route = self
+            .route
+            .get_or_insert_with(|| PathFollower::new(goal, 0.0, 0.0));
+        route.set_goal(goal);
+        route.arrive_radius = self.arrive_radius;
+        route.steer(entity, field, dt);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Steers an entity to random points near its home, pausing at each one before picking the next.
+///
+/// Only points the entity can stand at and can see in a straight line are picked,
+/// so it never steers into a wall
+pub struct Wander {
+    /// The center of the area that's wandered around
+    pub home: Point,
+    /// How far from home the wandered to points can be
+    pub radius: Scalar,
+    /// How long (in seconds) the entity waits at each point
+    pub pause: f64,
+    /// How close the entity's position has to get to a point to have reached it
+    pub arrive_radius: Scalar,
+    target: Option<Point>,
+    pause_remaining: f64,
+}
+
+impl Wander {
+    /// Creates a wander around `home` that picks its first point on the next steer
+    pub fn new(home: Point, radius: Scalar, pause: f64) -> Self {
+        Self {
+            home,
+            radius,
+            pause,
+            arrive_radius: DEFAULT_EPSILON,
+            target: None,
+            pause_remaining: 0.0,
+        }
+    }
+
+    /// The point the entity is heading towards, `None` while pausing
+    pub fn target(&self) -> Option<Point> {
+        self.target
+    }
+
+    /// Whether the entity is waiting before moving on to its next point
+    pub fn is_pausing(&self) -> bool {
+        self.target.is_none() && self.pause_remaining > 0.0
+    }
+
+    fn pick_target<R: Rng>(&self, entity: &Entity, field: &Field, rng: &mut R) -> Option<Point> {
+        let from = *entity.get_box().pos();
+        let size = entity.get_box().size();
+        let layers = entity.get_collision_layers();
+        (0..MAX_WANDER_ATTEMPTS).find_map(|_| {
+            let angle = rng.gen_range(0.0..TAU);
+            let distance = self.radius * rng.gen::<f64>().sqrt();
+            let point: Point = [
+                self.home.x + distance * angle.cos(),
+                self.home.y + distance * angle.sin(),
+            ]
+            .into();
+            let destination = PhysBox::new([point.x, point.y, size.w, size.h]).ok()?;
+            let standable = field.cell_at_pos(point).is_some()
+                && !field
+                    .cells_overlapping_box(&destination)
+                    .any(|c| c.is_solid_to(layers));
+            (standable && field.has_line_of_sight(from, point)).then_some(point)
+        })
+    }
+
+    /// Counts down the pause, picks a new point once it's over, and steers the entity towards
+    /// its point over `dt`. The entity is stopped while pausing. Should be called before the
+    /// entity moves
+    pub fn steer<R: Rng>(&mut self, entity: &mut Entity, field: &Field, rng: &mut R, dt: Scalar) {
+        if let Some(target) = self.target {
+            if has_arrived(entity, target, self.arrive_radius) {
+                self.target = None;
+                self.pause_remaining = self.pause;
+            }
+        }
+        if self.target.is_none() {
+            self.pause_remaining -= dt;
+            if self.pause_remaining > 0.0 {
+                entity.set_velocity([0.0, 0.0].into());
+                return;
+            }
+            self.target = self.pick_target(entity, field, rng);
+        }
+
+        match self.target {
+            Some(target) => entity.arrive_at(target, dt),
+            None => entity.set_velocity([0.0, 0.0].into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Steers an entity's center along a path through the field (see [Field::find_path]) to a goal,
+/// one waypoint after another.
+///
+/// A new path is found when something on the field blocks the current one, when the goal
+/// moves too far from where the path was found to, and every `repath_interval` seconds
+pub struct PathFollower {
+    goal: Point,
+    path: Vec<Point>,
+    found: bool,
+    /// The goal and field revision that the current path was found for
+    found_for: Option<(Point, u64)>,
+    since_repath: f64,
+    /// How far the goal can move before a new path is found to it
+    pub goal_tolerance: Scalar,
+    /// How often (in seconds) a new path is found, even if nothing has changed.
+    /// Never if it isn't positive
+    pub repath_interval: f64,
+    /// How close the entity's center has to get to a waypoint to have reached it
+    pub arrive_radius: Scalar,
+}
+
+impl PathFollower {
+    /// Creates a follower heading to `goal` that finds its path on the next steer
+    pub fn new(goal: Point, goal_tolerance: Scalar, repath_interval: f64) -> Self {
+        Self {
+            goal,
+            path: vec![],
+            found: false,
+            found_for: None,
+            since_repath: 0.0,
+            goal_tolerance,
+            repath_interval,
+            arrive_radius: DEFAULT_EPSILON,
+        }
+    }
+
+    /// Where the entity's center is heading
+    pub fn goal(&self) -> Point {
+        self.goal
+    }
+
+    /// Moves the goal, i.e. to keep chasing a moving target
+    pub fn set_goal(&mut self, goal: Point) {
+        self.goal = goal;
+    }
+
+    /// The waypoints left on the current path, ending at the goal
+    pub fn path(&self) -> &[Point] {
+        &self.path
+    }
+
+    /// Whether the entity has reached the end of its path
+    pub fn is_finished(&self) -> bool {
+        self.found && self.path.is_empty()
+    }
+
+    /// Whether the last search couldn't find any path to the goal
+    pub fn is_stuck(&self) -> bool {
+        self.found_for.is_some() && !self.found
+    }
+
+    /// Whether the rest of the path has become blocked for the entity's box
+    fn is_blocked(&self, entity: &Entity, field: &Field) -> bool {
+        let layers = entity.get_collision_layers();
+        let mut physbox = *entity.get_box();
+        for &waypoint in &self.path {
+            let from = physbox.center();
+            let to_waypoint = [waypoint.x - from.x, waypoint.y - from.y];
+            if field
+                .sweep_box_blocked_by(&physbox, to_waypoint, |c| c.is_solid_to(layers))
+                .is_some()
+            {
+                return true;
+            }
+            physbox.set_pos(*physbox.pos() + to_waypoint);
+        }
+        false
+    }
+
+    fn needs_repath(&self, entity: &Entity, field: &Field) -> bool {
+        let (goal, revision) = match self.found_for {
+            Some(found_for) => found_for,
+            None => return true,
+        };
+        let goal_moved =
+            f64::hypot(self.goal.x - goal.x, self.goal.y - goal.y) > self.goal_tolerance;
+        let interval_passed =
+            self.repath_interval > 0.0 && self.since_repath >= self.repath_interval;
+        // Only a change to the field can block (or clear) the path
+        let field_changed =
+            revision != field.revision() && (!self.found || self.is_blocked(entity, field));
+        goal_moved || interval_passed || field_changed
+    }
+
+    fn repath(&mut self, entity: &Entity, field: &Field) {
+        let path = field.find_path(
+            entity.get_box().center(),
+            self.goal,
+            *entity.get_box().size(),
+            entity.get_collision_layers(),
+        );
+        self.found = path.is_some();
+        self.path = path.unwrap_or_default();
+        self.found_for = Some((self.goal, field.revision()));
+        self.since_repath = 0.0;
+    }
+
+    /// Finds a new path if needed, moves on past the waypoints the entity has reached, and
+    /// steers the entity towards the next one over `dt`. The entity is stopped once it's at
+    /// the goal or when there's no way there. Should be called before the entity moves
+    pub fn steer(&mut self, entity: &mut Entity, field: &Field, dt: Scalar) {
+        self.since_repath += dt;
+        if self.needs_repath(entity, field) {
+            self.repath(entity, field);
+        }
+        let center = entity.get_box().center();
+        while let Some(&waypoint) = self.path.first() {
+            let distance = f64::hypot(waypoint.x - center.x, waypoint.y - center.y);
+            if distance > self.arrive_radius.max(DEFAULT_EPSILON) {
+                break;
+            }
+            self.path.remove(0);
+        }
+
+        let size = *entity.get_box().size();
+        match self.path.first() {
+            Some(&waypoint) => entity.arrive_at(
+                [waypoint.x - size.w / 2.0, waypoint.y - size.h / 2.0].into(),
+                dt,
+            ),
+            None => entity.set_velocity([0.0, 0.0].into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// How much an AI entity wants to target each of the entities around it. Threat builds up from
+/// the damage they deal to it and from how long they stay close to it, and decays over time
+pub struct ThreatTable {
+    threat: HashMap<EntityId, f64>,
+    /// The threat gained per point of damage dealt
+    pub damage_threat: f64,
+    /// The threat gained per second that an entity stays within `proximity_radius`
+    pub proximity_threat: f64,
+    /// How close an entity has to be to gain proximity threat
+    pub proximity_radius: Scalar,
+    /// The threat lost per second by every entity in the table
+    pub decay: f64,
+}
+
+impl ThreatTable {
+    /// Creates an empty threat table
+    pub fn new(
+        damage_threat: f64,
+        proximity_threat: f64,
+        proximity_radius: Scalar,
+        decay: f64,
+    ) -> Self {
+        Self {
+            threat: HashMap::new(),
+            damage_threat,
+            proximity_threat,
+            proximity_radius,
+            decay,
+        }
+    }
+
+    /// The current threat of `entity`, 0 if it isn't in the table
+    pub fn threat(&self, entity: EntityId) -> f64 {
+        self.threat.get(&entity).copied().unwrap_or(0.0)
+    }
+
+    /// Adds `amount` of threat to `entity`
+    pub fn add_threat(&mut self, entity: EntityId, amount: f64) {
+        *self.threat.entry(entity).or_insert(0.0) += amount;
+    }
+
+    /// Removes `entity` from the table, i.e. when it dies
+    pub fn remove(&mut self, entity: EntityId) {
+        self.threat.remove(&entity);
+    }
+
+    /// Adds threat for the damage done to `owner`, the entity the table belongs to,
+    /// by the sources of the `changes`. Healing and damage without a source are ignored
+    pub fn record_health_changes<'a, I: IntoIterator<Item = &'a HealthChange>>(
+        &mut self,
+        owner: EntityId,
+        changes: I,
+    ) {
+        for change in changes {
+            match change.source {
+                Some(source) if change.target == owner && change.amount < 0.0 => {
+                    self.add_threat(source, -change.amount * self.damage_threat)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Decays every entity's threat over `dt`, dropping those that reach 0, then adds proximity
+    /// threat to the `candidates` (with their positions) close enough to `owner_pos`
+    pub fn tick<I: IntoIterator<Item = (EntityId, Point)>>(
+        &mut self,
+        dt: f64,
+        owner_pos: Point,
+        candidates: I,
+    ) {
+        let decay = self.decay * dt;
+        self.threat.retain(|_, threat| {
+            *threat -= decay;
+            *threat > 0.0
+        });
+
+        for (entity, pos) in candidates {
+            if f64::hypot(pos.x - owner_pos.x, pos.y - owner_pos.y) <= self.proximity_radius {
+                self.add_threat(entity, self.proximity_threat * dt);
+            }
+        }
+    }
+
+    /// The entity with the most threat, which the owner should target.
+    /// Ties go to the entity registered first
+    pub fn highest_threat(&self) -> Option<EntityId> {
+        self.threat
+            .iter()
+            .max_by(|(a_id, a), (b_id, b)| a.total_cmp(b).then(b_id.cmp(a_id)))
+            .map(|(id, _)| *id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        gameobject::{
+            entity::registry::{test::TestEntity, EntityRegistry},
+            field::Cell,
+        },
+        types::{CollisionLayers, NO_SOLIDITY, SOLID},
+    };
+
+    fn open_field() -> Field {
+        Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+        )])
+        .unwrap()
+    }
+
+    fn entity_at(pos: [f64; 2]) -> Entity {
+        let mut entity = Entity::new().unwrap();
+        entity.physbox = PhysBox::new([pos[0], pos[1], 2.0, 2.0]).unwrap();
+        entity.set_max_velocity(10.0).unwrap();
+        entity
+    }
+
+    fn visited_waypoints(mode: PatrolMode, ticks: usize) -> Vec<Point> {
+        let field = open_field();
+        let mut entity = entity_at([10.0, 10.0]);
+        let mut patrol = Patrol::new(
+            vec![
+                [10.0, 10.0].into(),
+                [30.0, 10.0].into(),
+                [30.0, 30.0].into(),
+            ],
+            mode,
+        );
+
+        let mut visited = vec![];
+        for _ in 0..ticks {
+            let before = patrol.current_waypoint();
+            patrol.steer(&mut entity, &field, 0.5);
+            if patrol.current_waypoint() != before {
+                visited.push(before.unwrap());
+            }
+            entity.try_move(&field, 0.5).unwrap();
+        }
+        visited
+    }
+
+    #[test]
+    fn patrol_finds_its_way_around_wall_between_waypoints() {
+        let wall = PhysBox::new([50.0, 0.0, 10.0, 60.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+            Cell::new(SOLID, wall),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 60.0, 10.0, 40.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([60.0, 0.0, 40.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+        let mut entity = entity_at([20.0, 20.0]);
+        entity.set_max_velocity(100.0).unwrap();
+        let mut patrol = Patrol::new(
+            vec![[20.0, 20.0].into(), [70.0, 20.0].into()],
+            PatrolMode::Loop,
+        );
+
+        let mut visited = vec![];
+        for _ in 0..60 {
+            let before = patrol.current_waypoint();
+            patrol.steer(&mut entity, &field, 0.1);
+            if patrol.current_waypoint() != before {
+                visited.push(before.unwrap());
+            }
+            entity.try_move(&field, 0.1).unwrap();
+            assert!(!entity.get_box().has_overlap(&wall));
+        }
+
+        // Reached the far side of the wall and came back round again
+        let there: Point = [70.0, 20.0].into();
+        let back: Point = [20.0, 20.0].into();
+        assert_eq!(&visited[..3], &[back, there, back]);
+    }
+
+    #[test]
+    fn threat_follows_damage_and_decays_to_closer_target() {
+        let mut registry = EntityRegistry::default();
+        let enemy = registry
+            .register(TestEntity::with_box([50.0, 50.0, 2.0, 2.0]))
+            .unwrap();
+        let brawler = registry
+            .register(TestEntity::with_box([55.0, 50.0, 2.0, 2.0]))
+            .unwrap();
+        let sniper = registry
+            .register(TestEntity::with_box([95.0, 50.0, 2.0, 2.0]))
+            .unwrap();
+        let hit = |source, amount| HealthChange {
+            target: enemy,
+            source: Some(source),
+            amount,
+        };
+
+        let mut table = ThreatTable::new(1.0, 2.0, 10.0, 1.0);
+        table.record_health_changes(enemy, &[hit(brawler, -2.0), hit(sniper, -5.0)]);
+        // Healing, other targets and sourceless damage don't count
+        table.record_health_changes(
+            enemy,
+            &[
+                hit(brawler, 3.0),
+                HealthChange {
+                    target: sniper,
+                    source: Some(brawler),
+                    amount: -10.0,
+                },
+                HealthChange {
+                    target: enemy,
+                    source: None,
+                    amount: -10.0,
+                },
+            ],
+        );
+        assert_eq!(table.threat(brawler), 2.0);
+        assert_eq!(table.threat(sniper), 5.0);
+        assert_eq!(table.highest_threat(), Some(sniper));
+
+        // The sniper stops attacking from range while the brawler stays close
+        let positions = |registry: &EntityRegistry<TestEntity>| {
+            registry
+                .iter_with_ids()
+                .filter(|(id, _)| *id != enemy)
+                .map(|(id, e)| (id, *e.get_box().pos()))
+                .collect::<Vec<_>>()
+        };
+        let mut ticks = 0;
+        while table.highest_threat() == Some(sniper) {
+            table.tick(0.5, [50.0, 50.0].into(), positions(&registry));
+            ticks += 1;
+        }
+        assert_eq!(table.highest_threat(), Some(brawler));
+        assert_eq!(ticks, 3);
+
+        table.tick(10.0, [50.0, 50.0].into(), []);
+        assert_eq!(table.threat(sniper), 0.0);
+        assert_eq!(table.highest_threat(), None);
+    }
+
+    #[test]
+    fn patrol_visits_waypoints_in_order() {
+        // Each straight leg takes 4 ticks and the diagonal one back takes 6
+        assert_eq!(
+            visited_waypoints(PatrolMode::Loop, 16),
+            vec![
+                [10.0, 10.0].into(),
+                [30.0, 10.0].into(),
+                [30.0, 30.0].into(),
+                [10.0, 10.0].into(),
+            ]
+        );
+        assert_eq!(
+            visited_waypoints(PatrolMode::PingPong, 20),
+            vec![
+                [10.0, 10.0].into(),
+                [30.0, 10.0].into(),
+                [30.0, 30.0].into(),
+                [30.0, 10.0].into(),
+                [10.0, 10.0].into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_follower_reaches_goal_and_repaths_around_new_wall() {
+        // Three rows of five open tiles, travelling along the middle row
+        let mut field = Field::from_grid(
+            [0.0, 0.0].into(),
+            [10.0, 10.0].into(),
+            5,
+            &[NO_SOLIDITY; 15],
+        )
+        .unwrap();
+        let goal: Point = [45.0, 15.0].into();
+        let travel = |field: &Field, follower: &mut PathFollower, entity: &mut Entity| {
+            let mut ticks = 0;
+            while !follower.is_finished() && ticks < 40 {
+                follower.steer(entity, field, 0.5);
+                entity.try_move(field, 0.5).unwrap();
+                ticks += 1;
+            }
+            ticks
+        };
+
+        let mut entity = entity_at([4.0, 14.0]);
+        let mut follower = PathFollower::new(goal, 1.0, 0.0);
+        follower.steer(&mut entity, &field, 0.5);
+        assert_eq!(
+            follower.path(),
+            &[
+                [15.0, 15.0].into(),
+                [25.0, 15.0].into(),
+                [35.0, 15.0].into(),
+                goal
+            ]
+        );
+        travel(&field, &mut follower, &mut entity);
+        assert!(follower.is_finished());
+        assert_eq!(entity.get_box().center(), goal);
+
+        // A wall goes up across the route partway along it
+        let mut entity = entity_at([4.0, 14.0]);
+        let mut follower = PathFollower::new(goal, 1.0, 0.0);
+        for _ in 0..3 {
+            follower.steer(&mut entity, &field, 0.5);
+            entity.try_move(&field, 0.5).unwrap();
+        }
+        let wall = PhysBox::new([30.0, 10.0, 10.0, 10.0]).unwrap();
+        let id = field.cell_at_pos(wall.center()).unwrap().id();
+        field.remove_cell(id).unwrap();
+        field.add_cell(Cell::new(SOLID, wall)).unwrap();
+
+        follower.steer(&mut entity, &field, 0.5);
+        assert!(!follower.is_stuck());
+        assert!(follower.path().iter().all(|p| !wall.contains_pos(*p)));
+        entity.try_move(&field, 0.5).unwrap();
+        assert!(travel(&field, &mut follower, &mut entity) < 40);
+        assert_eq!(entity.get_box().center(), goal);
+    }
+
+    #[test]
+    fn large_entity_paths_around_gap_too_narrow_for_it() {
+        let narrow_gap = PhysBox::new([40.0, 40.0, 10.0, 6.0]).unwrap();
+        let wide_gap = PhysBox::new([40.0, 80.0, 10.0, 20.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 40.0, 100.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([40.0, 0.0, 10.0, 40.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, narrow_gap),
+            Cell::new(SOLID, PhysBox::new([40.0, 46.0, 10.0, 34.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, wide_gap),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 0.0, 50.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+        let start: Point = [20.0, 85.0].into();
+        let goal: Point = [70.0, 85.0].into();
+        let layers = CollisionLayers::default();
+
+        // Something small enough squeezes through the shorter way
+        let path = field.find_path(start, goal, [4.0, 4.0].into(), layers);
+        assert_eq!(path, Some(vec![narrow_gap.center(), goal]));
+        let path = field.find_path(start, goal, [8.0, 8.0].into(), layers);
+        assert_eq!(path, Some(vec![wide_gap.center(), goal]));
+
+        let mut entity = Entity::new().unwrap();
+        entity.physbox = PhysBox::new([16.0, 81.0, 8.0, 8.0]).unwrap();
+        entity.set_max_velocity(100.0).unwrap();
+        let mut follower = PathFollower::new(goal, 1.0, 0.0);
+        let mut ticks = 0;
+        while !follower.is_finished() && ticks < 40 {
+            follower.steer(&mut entity, &field, 0.1);
+            entity.try_move(&field, 0.1).unwrap();
+            ticks += 1;
+        }
+        assert!(follower.is_finished());
+        let center = entity.get_box().center();
+        assert!(f64::hypot(center.x - goal.x, center.y - goal.y) < 1e-9);
+    }
+
+    #[test]
+    fn seeded_wander_is_reproducible_and_avoids_walls() {
+        let wall = PhysBox::new([50.0, 0.0, 10.0, 100.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+            Cell::new(SOLID, wall),
+            Cell::new(NO_SOLIDITY, PhysBox::new([60.0, 0.0, 40.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+        let wander_targets = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut entity = entity_at([40.0, 50.0]);
+            let mut wander = Wander::new([45.0, 50.0].into(), 20.0, 1.0);
+
+            let mut targets = vec![];
+            for _ in 0..100 {
+                wander.steer(&mut entity, &field, &mut rng, 0.5);
+                if let Some(target) = wander.target() {
+                    if targets.last() != Some(&target) {
+                        targets.push(target);
+                    }
+                }
+                entity.try_move(&field, 0.5).unwrap();
+            }
+            targets
+        };
+
+        let targets = wander_targets(3);
+        assert!(targets.len() > 3);
+        assert_eq!(targets, wander_targets(3));
+        assert_ne!(targets, wander_targets(4));
+        // Everything picked is on the near side of the wall
+        assert!(targets.iter().all(|t| t.x + 2.0 <= wall.left_x()));
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A state that limits what an entity can do while it lasts
+pub enum CrowdControl {
+    /// Can't move or act at all
+    Stun,
+    /// Can't move, but can still act
+    Root,
+    /// Can't use abilities, but can still move and act
+    Silence,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The time (in seconds) remaining on each of an entity's crowd control states
+pub struct CrowdControlStates {
+    stun: f64,
+    root: f64,
+    silence: f64,
+}
+
+impl CrowdControlStates {
+    fn remaining_mut(&mut self, kind: CrowdControl) -> &mut f64 {
+        match kind {
+            CrowdControl::Stun => &mut self.stun,
+            CrowdControl::Root => &mut self.root,
+            CrowdControl::Silence => &mut self.silence,
+        }
+    }
+
+    /// Applies the state for `duration` seconds. A state that's already active
+    /// keeps whichever duration is longer rather than stacking
+    pub fn apply(&mut self, kind: CrowdControl, duration: f64) {
+        let remaining = self.remaining_mut(kind);
+        *remaining = remaining.max(duration);
+    }
+
+    /// Ends the state early
+    pub fn clear(&mut self, kind: CrowdControl) {
+        *self.remaining_mut(kind) = 0.0;
+    }
+
+    /// The seconds left on the state, 0 if it isn't active
+    pub fn remaining(&self, kind: CrowdControl) -> f64 {
+        match kind {
+            CrowdControl::Stun => self.stun,
+            CrowdControl::Root => self.root,
+            CrowdControl::Silence => self.silence,
+        }
+    }
+
+    /// Whether the state is active
+    pub fn is_active(&self, kind: CrowdControl) -> bool {
+        self.remaining(kind) > 0.0
+    }
+
+    /// Counts down every state by `dt`
+    pub fn tick(&mut self, dt: f64) {
+        for remaining in [&mut self.stun, &mut self.root, &mut self.silence] {
+            *remaining = (*remaining - dt).max(0.0);
+        }
+    }
+
+    /// Neither stunned nor rooted
+    pub fn can_move(&self) -> bool {
+        !self.is_active(CrowdControl::Stun) && !self.is_active(CrowdControl::Root)
+    }
+
+    /// Not stunned, i.e. can attack
+    pub fn can_act(&self) -> bool {
+        !self.is_active(CrowdControl::Stun)
+    }
+
+    /// Neither stunned nor silenced
+    pub fn can_use_abilities(&self) -> bool {
+        !self.is_active(CrowdControl::Stun) && !self.is_active(CrowdControl::Silence)
+    }
+}
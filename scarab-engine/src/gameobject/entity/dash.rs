@@ -0,0 +1,162 @@
+use graphics::types::{Scalar, Vec2d};
+use serde::{Deserialize, Serialize};
+
+use crate::{types::Velocity, PhysicsError, PhysicsResult};
+
+/// How close to the end of a dash (or its cooldown) counts as finished, so floating point
+/// error in the remaining time doesn't add an extra tick
+const DASH_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct ActiveDash {
+    velocity: Velocity,
+    remaining: f64,
+    /// What the entity was moving at before dashing, restored when the dash ends
+    resume_velocity: Velocity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SavedDash")]
+/// A short burst of fast movement in a direction, with a cooldown between dashes.
+/// Entities are invulnerable for the whole dash (see [Entity::try_dash](super::Entity::try_dash))
+pub struct Dash {
+    distance: Scalar,
+    duration: f64,
+    cooldown: f64,
+    #[serde(default)]
+    active: Option<ActiveDash>,
+    #[serde(default)]
+    cooldown_remaining: f64,
+}
+
+#[derive(Deserialize)]
+/// A dash as it's loaded, checked the same way as [Dash::new] so a zero duration can't give an
+/// infinite dash speed
+struct SavedDash {
+    distance: Scalar,
+    duration: f64,
+    cooldown: f64,
+    #[serde(default)]
+    active: Option<ActiveDash>,
+    #[serde(default)]
+    cooldown_remaining: f64,
+}
+
+impl TryFrom<SavedDash> for Dash {
+    type Error = PhysicsError;
+
+    fn try_from(saved: SavedDash) -> PhysicsResult<Self> {
+        Ok(Self {
+            active: saved.active,
+            cooldown_remaining: saved.cooldown_remaining,
+            ..Self::new(saved.distance, saved.duration, saved.cooldown)?
+        })
+    }
+}
+
+impl Dash {
+    /// A dash covering `distance` over `duration` seconds, that can be used again `cooldown`
+    /// seconds after it starts. The duration must be positive and the others non-negative
+    pub fn new(distance: Scalar, duration: f64, cooldown: f64) -> PhysicsResult<Self> {
+        if duration <= 0.0 || distance < 0.0 || cooldown < 0.0 {
+            return Err(PhysicsError::Dash);
+        }
+        Ok(Self {
+            distance,
+            duration,
+            cooldown,
+            active: None,
+            cooldown_remaining: 0.0,
+        })
+    }
+
+    /// How far a dash moves when it isn't stopped early
+    pub fn distance(&self) -> Scalar {
+        self.distance
+    }
+
+    /// How long a dash lasts in seconds
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// Whether a dash is in progress
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// The seconds until the dash can be used again
+    pub fn cooldown_remaining(&self) -> f64 {
+        self.cooldown_remaining
+    }
+
+    /// Whether a dash can be started
+    pub fn is_ready(&self) -> bool {
+        !self.is_active() && self.cooldown_remaining <= DASH_EPSILON
+    }
+
+    /// Starts dashing in `direction` if the dash is ready and the direction isn't zero,
+    /// returning the velocity to dash at
+    pub(super) fn start(
+        &mut self,
+        direction: Vec2d,
+        resume_velocity: Velocity,
+    ) -> Option<Velocity> {
+        let length = f64::hypot(direction[0], direction[1]);
+        if !self.is_ready() || length == 0.0 {
+            return None;
+        }
+        let speed = self.distance / self.duration;
+        let velocity: Velocity =
+            [direction[0] / length * speed, direction[1] / length * speed].into();
+        self.active = Some(ActiveDash {
+            velocity,
+            remaining: self.duration,
+            resume_velocity,
+        });
+        self.cooldown_remaining = self.cooldown;
+        Some(velocity)
+    }
+
+    /// The time to move for this tick of the dash, at most `dt`. `None` when not dashing
+    pub(super) fn step(&self, dt: f64) -> Option<f64> {
+        self.active.map(|a| a.remaining.min(dt))
+    }
+
+    /// Counts down the cooldown and any dash in progress by `dt`. Returns the velocity to resume
+    /// at if the dash ended
+    pub(super) fn tick(&mut self, dt: f64) -> Option<Velocity> {
+        self.cooldown_remaining = (self.cooldown_remaining - dt).max(0.0);
+        let active = self.active.as_mut()?;
+        active.remaining -= dt;
+        if active.remaining <= DASH_EPSILON {
+            self.stop()
+        } else {
+            None
+        }
+    }
+
+    /// Ends the dash in progress early, returning the velocity to resume at
+    pub(super) fn stop(&mut self) -> Option<Velocity> {
+        self.active.take().map(|a| a.resume_velocity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn loading_a_dash_checks_it_like_new() {
+        let dash = Dash::new(20.0, 0.2, 1.0).unwrap();
+        let saved = rmp_serde::to_vec(&dash).unwrap();
+        assert_eq!(rmp_serde::from_slice::<Dash>(&saved).unwrap(), dash);
+
+        let instant = Dash {
+            duration: 0.0,
+            ..dash
+        };
+        let saved = rmp_serde::to_vec(&instant).unwrap();
+        assert!(rmp_serde::from_slice::<Dash>(&saved).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Emits an event every time its entity travels a set distance (i.e. footsteps or ripples),
+/// so faster entities emit them more often
+pub struct DistanceEmitter {
+    /// The distance to travel between events
+    pub distance: Scalar,
+    /// The distance traveled since the last event
+    #[serde(skip)]
+    traveled: Scalar,
+    /// Events that haven't been taken yet
+    #[serde(skip)]
+    pending: usize,
+}
+
+impl DistanceEmitter {
+    /// An emitter with one event every `distance` traveled
+    pub fn new(distance: Scalar) -> Self {
+        Self {
+            distance,
+            traveled: 0.0,
+            pending: 0,
+        }
+    }
+
+    /// Records that the entity traveled `distance`, emitting any events it's due
+    pub fn travel(&mut self, distance: Scalar) {
+        if self.distance <= 0.0 {
+            return;
+        }
+        self.traveled += distance;
+        while self.traveled >= self.distance {
+            self.traveled -= self.distance;
+            self.pending += 1;
+        }
+    }
+
+    /// Returns the number of events emitted since the last call, clearing them
+    pub fn take_events(&mut self) -> usize {
+        core::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn emits_once_per_distance() {
+        let mut emitter = DistanceEmitter::new(5.0);
+
+        emitter.travel(4.0);
+        assert_eq!(emitter.take_events(), 0);
+
+        emitter.travel(12.0);
+        assert_eq!(emitter.take_events(), 3);
+        assert_eq!(emitter.take_events(), 0);
+    }
+}
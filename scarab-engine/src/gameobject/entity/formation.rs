@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+use super::{
+    behavior::PathFollower,
+    registry::{EntityId, EntityRegistry, RegisteredEntity},
+};
+use crate::{gameobject::field::Field, types::physbox::HasBox};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A member's place in a formation
+pub struct FormationSlot {
+    /// The entity filling the slot
+    pub member: EntityId,
+    /// The position of the slot relative to the leader's position
+    pub offset: Point,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Moves a group of entities together by steering each member towards its slot
+/// relative to the leader. The leader is moved by whatever normally controls it.
+///
+/// Members are steered straight at their slot while nothing is in the way. One that a wall
+/// cuts off from its slot follows a path around it (see [PathFollower]) until it's clear
+pub struct Formation {
+    leader: EntityId,
+    slots: Vec<FormationSlot>,
+    /// The paths of the members that are finding their way around walls to their slots
+    #[serde(skip)]
+    detours: HashMap<EntityId, PathFollower>,
+}
+
+impl Formation {
+    /// Creates an empty formation following `leader`
+    pub fn new(leader: EntityId) -> Self {
+        Self {
+            leader,
+            slots: Vec::new(),
+            detours: HashMap::new(),
+        }
+    }
+
+    /// The entity that the formation follows
+    pub fn leader(&self) -> EntityId {
+        self.leader
+    }
+
+    /// Changes the entity that the formation follows
+    pub fn set_leader(&mut self, leader: EntityId) {
+        self.leader = leader;
+    }
+
+    /// Adds `member` to the formation at `offset` from the leader.
+    /// Moves the member's slot if it's already in the formation
+    pub fn add_member(&mut self, member: EntityId, offset: Point) {
+        match self.slots.iter_mut().find(|s| s.member == member) {
+            Some(slot) => slot.offset = offset,
+            None => self.slots.push(FormationSlot { member, offset }),
+        }
+    }
+
+    /// Removes `member` from the formation, returning its slot if it had one
+    pub fn remove_member(&mut self, member: EntityId) -> Option<FormationSlot> {
+        let i = self.slots.iter().position(|s| s.member == member)?;
+        self.detours.remove(&member);
+        Some(self.slots.remove(i))
+    }
+
+    /// All of the formation's slots
+    pub fn slots(&self) -> &[FormationSlot] {
+        &self.slots
+    }
+
+    /// Where `member` should be when the leader is at `leader_pos`
+    pub fn slot_pos(&self, leader_pos: Point, member: EntityId) -> Option<Point> {
+        self.slots
+            .iter()
+            .find(|s| s.member == member)
+            .map(|s| leader_pos + s.offset)
+    }
+
+    /// Steers every member towards where its slot will be after the leader's next movement of `dt`.
+    /// Should be called after the leader's velocity is set for the tick, but before entities move.
+    /// Members that aren't in the registry are skipped, and nothing is steered without a leader
+    pub fn steer_members<E: RegisteredEntity>(
+        &mut self,
+        registry: &mut EntityRegistry<E>,
+        field: &Field,
+        dt: Scalar,
+    ) {
+        let leader_pos = match registry.get_by_id(self.leader) {
+            Some(leader) => {
+                let leader = leader.inner_entity();
+                *leader.get_box().pos() + leader.get_velocity() * dt
+            }
+            None => return,
+        };
+
+        for slot in &self.slots {
+            let member = match registry.get_by_id_mut(slot.member) {
+                Some(member) => member.inner_entity_mut(),
+                None => continue,
+            };
+            let target = leader_pos + slot.offset;
+            let physbox = *member.get_box();
+            let to_target = [target.x - physbox.pos().x, target.y - physbox.pos().y];
+            let layers = member.get_collision_layers();
+            if field
+                .sweep_box_blocked_by(&physbox, to_target, |c| c.is_solid_to(layers))
+                .is_none()
+            {
+                self.detours.remove(&slot.member);
+                member.arrive_at(target, dt);
+                continue;
+            }
+
+            let size = *physbox.size();
+            let slot_center = Point::from([target.x + size.w / 2.0, target.y + size.h / 2.0]);
+            // The slot moves with the leader, so only a change bigger than the member finds
+            // a new path
+            let detour = self
+                .detours
+                .entry(slot.member)
+                .or_insert_with(|| PathFollower::new(slot_center, size.w.max(size.h), 0.0));
+            detour.set_goal(slot_center);
+            detour.steer(member, field, dt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{
+        gameobject::{
+            entity::registry::test::TestEntity,
+            field::{Cell, Field},
+        },
+        types::{physbox::PhysBox, ApproxEq, NO_SOLIDITY, SOLID},
+    };
+
+    #[test]
+    fn members_keep_offsets_while_leader_moves_straight() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 200.0, 200.0]).unwrap(),
+        )])
+        .unwrap();
+
+        let mut registry = EntityRegistry::default();
+        let leader = registry
+            .register(TestEntity::with_box([50.0, 100.0, 4.0, 4.0]))
+            .unwrap();
+        let left = registry
+            .register(TestEntity::with_box([40.0, 90.0, 4.0, 4.0]))
+            .unwrap();
+        let right = registry
+            .register(TestEntity::with_box([40.0, 110.0, 4.0, 4.0]))
+            .unwrap();
+
+        let mut formation = Formation::new(leader);
+        formation.add_member(left, [-10.0, -10.0].into());
+        formation.add_member(right, [-10.0, 10.0].into());
+
+        for e in registry.iter_mut() {
+            e.0.set_max_velocity(100.0).unwrap();
+        }
+        let leader_entity = registry.get_by_id_mut(leader).unwrap();
+        leader_entity.0.set_velocity([30.0, 0.0].into());
+
+        let dt = 0.1;
+        for _ in 0..20 {
+            formation.steer_members(&mut registry, &field, dt);
+            for e in registry.iter_mut() {
+                e.0.try_move(&field, dt).unwrap();
+            }
+
+            let leader_pos = *registry.get_by_id(leader).unwrap().get_box().pos();
+            for slot in formation.slots() {
+                let member_pos = *registry.get_by_id(slot.member).unwrap().get_box().pos();
+                assert!(member_pos.approx_eq(&(leader_pos + slot.offset), 1e-9));
+            }
+        }
+
+        let leader_pos = *registry.get_by_id(leader).unwrap().get_box().pos();
+        assert!(leader_pos.approx_eq(&[110.0, 100.0].into(), 1e-9));
+    }
+
+    #[test]
+    fn member_cut_off_by_wall_finds_its_way_to_slot() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([50.0, 0.0, 10.0, 60.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 60.0, 10.0, 40.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([60.0, 0.0, 40.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let mut registry = EntityRegistry::default();
+        let leader = registry
+            .register(TestEntity::with_box([80.0, 20.0, 4.0, 4.0]))
+            .unwrap();
+        // On the other side of the wall from its slot
+        let member = registry
+            .register(TestEntity::with_box([20.0, 20.0, 4.0, 4.0]))
+            .unwrap();
+        for e in registry.iter_mut() {
+            e.0.set_max_velocity(100.0).unwrap();
+        }
+        let mut formation = Formation::new(leader);
+        formation.add_member(member, [-10.0, 0.0].into());
+
+        let dt = 0.1;
+        let mut went_under = false;
+        for _ in 0..60 {
+            formation.steer_members(&mut registry, &field, dt);
+            for e in registry.iter_mut() {
+                e.0.try_move(&field, dt).unwrap();
+            }
+            went_under |= registry.get_by_id(member).unwrap().get_box().top_y() >= 60.0;
+        }
+
+        let member_pos = *registry.get_by_id(member).unwrap().get_box().pos();
+        assert!(went_under);
+        assert!(member_pos.approx_eq(&[70.0, 20.0].into(), 1e-9));
+    }
+}
@@ -0,0 +1,128 @@
+use graphics::types::Scalar;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A possible outcome of rolling a [DropTable]
+pub struct DropEntry {
+    /// The tag of the item to spawn, also the name of the pickup prefab a scene spawns for it
+    pub item: String,
+    /// The relative likelihood of this entry being picked compared to the others in the table
+    pub weight: Scalar,
+    /// The inclusive range of how many of the item drop
+    pub quantity: (u32, u32),
+    /// The probability in [0, 1] of the item actually dropping once this entry is picked
+    pub chance: Scalar,
+}
+
+impl DropEntry {
+    /// An entry that always drops one of `item` when picked
+    pub fn new<T: Into<String>>(item: T, weight: Scalar) -> Self {
+        Self {
+            item: item.into(),
+            weight,
+            quantity: (1, 1),
+            chance: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An item that was rolled from a [DropTable]
+pub struct LootDrop {
+    /// The tag of the item to spawn
+    pub item: String,
+    /// How many of the item to spawn
+    pub quantity: u32,
+    /// Where to spawn the item (i.e. the center of the entity that dropped it)
+    pub pos: Point,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// A weighted table of the loot an entity drops when it dies
+pub struct DropTable {
+    /// The possible drops
+    pub entries: Vec<DropEntry>,
+    /// The relative likelihood of dropping nothing at all
+    pub no_drop_weight: Scalar,
+}
+
+impl DropTable {
+    /// Picks an entry by weight, then checks its chance and quantity.
+    /// Returns the item and quantity or None if nothing drops
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> Option<(&str, u32)> {
+        let total = self
+            .entries
+            .iter()
+            .map(|e| e.weight.max(0.0))
+            .sum::<Scalar>()
+            + self.no_drop_weight.max(0.0);
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut remaining = rng.gen_range(0.0..total);
+        let entry = self.entries.iter().find(|e| {
+            remaining -= e.weight.max(0.0);
+            remaining < 0.0
+        })?;
+
+        if rng.gen::<Scalar>() >= entry.chance {
+            return None;
+        }
+        let (min, max) = entry.quantity;
+        let quantity = rng.gen_range(min.min(max)..=max.max(min));
+        if quantity == 0 {
+            None
+        } else {
+            Some((entry.item.as_str(), quantity))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn drops_approximate_weights() {
+        let mut never = DropEntry::new("never", 1.0);
+        never.chance = 0.0;
+        let table = DropTable {
+            entries: vec![
+                DropEntry::new("coin", 3.0),
+                DropEntry::new("potion", 1.0),
+                never,
+            ],
+            no_drop_weight: 1.0,
+        };
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let rolls = 20_000;
+        let mut counts: HashMap<Option<String>, usize> = HashMap::new();
+        for _ in 0..rolls {
+            let item = table.roll(&mut rng).map(|(item, _)| item.to_string());
+            *counts.entry(item).or_default() += 1;
+        }
+
+        let fraction = |item: Option<&str>| {
+            counts.get(&item.map(String::from)).copied().unwrap_or(0) as f64 / rolls as f64
+        };
+        // "never" is picked a sixth of the time but then never drops
+        assert!((fraction(Some("coin")) - 0.5).abs() < 0.02);
+        assert!((fraction(Some("potion")) - 1.0 / 6.0).abs() < 0.02);
+        assert!((fraction(None) - 2.0 / 6.0).abs() < 0.02);
+        assert_eq!(fraction(Some("never")), 0.0);
+    }
+
+    #[test]
+    fn empty_table_drops_nothing() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(DropTable::default().roll(&mut rng), None);
+    }
+}
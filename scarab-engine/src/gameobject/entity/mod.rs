@@ -1,26 +1,176 @@
+use std::collections::HashMap;
+
 use graphics::{
-    types::{Color, Scalar},
+    types::{Color, Scalar, Vec2d},
     Context,
 };
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
-use serde::{Deserialize, Serialize};
+use rand::Rng;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use shapes::Point;
 use uuid::Uuid;
 
-use super::field::{Cell, Field};
+use self::{
+    crowd_control::{CrowdControl, CrowdControlStates},
+    dash::Dash,
+    emitter::DistanceEmitter,
+    loot::{DropTable, LootDrop},
+    registry::EntityId,
+    shield::Shield,
+};
+use super::field::{Cell, CellId, Field};
 use crate::{
     error::RenderResult,
-    rendering::{registry::TextureRegistry, Camera, View},
+    rendering::{
+        registry::TextureRegistry,
+        sprite::hit_effects::{HitEffects, HitEffectsConfig},
+        Camera, View,
+    },
     scene::GameTickArgs,
     types::{
         physbox::{HasBox, PhysBox},
-        HasSolidity, Health, Solidity, Velocity, SOLID,
+        ApproxEq, Axis, BoxEdge, CollisionLayers, HasSolidity, Health, SharedHealthId, Solidity,
+        Velocity, DEFAULT_EPSILON, SOLID,
     },
     HasBox, HasBoxMut, HasHealth, HasSolidity, HasUuid, PhysicsError, PhysicsResult, ScarabResult,
 };
 
+/// AI behaviors: patrolling, wandering and picking targets by threat
+pub mod behavior;
+/// Stuns, roots and silences that limit what entities can do
+pub mod crowd_control;
+/// Short bursts of movement with invulnerability
+pub mod dash;
+/// Events emitted as entities move
+pub mod emitter;
+/// Moving groups of entities together
+pub mod formation;
+/// Weighted loot dropped by entities when they die
+pub mod loot;
 /// Handles the registration of entities (loading and unloading)
 pub mod registry;
+/// Regenerating shields that absorb damage before health
+pub mod shield;
+/// Data-driven variants of prefabs, i.e. enemy spawn tables loaded from a file
+pub mod spawn_table;
+
+/// How far an entity may overlap a cell before the overlap counts as a collision.
+/// Keeps entities resting flush against a row of cells from catching on the seams
+/// between them because of floating point error
+pub const DEFAULT_CONTACT_SKIN: Scalar = 1e-6;
+
+fn default_contact_skin() -> Scalar {
+    DEFAULT_CONTACT_SKIN
+}
+
+fn check_contact_skin(contact_skin: Scalar) -> PhysicsResult<Scalar> {
+    if contact_skin < 0.0 {
+        Err(PhysicsError::ContactSkin)
+    } else {
+        Ok(contact_skin)
+    }
+}
+
+/// Loads a contact skin with the same check as [Entity::set_contact_skin]
+fn deserialize_contact_skin<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Scalar, D::Error> {
+    check_contact_skin(Scalar::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+/// `value` if it's positive or `None`, otherwise `error`
+fn check_positive(value: Option<Scalar>, error: PhysicsError) -> PhysicsResult<Option<Scalar>> {
+    match value {
+        Some(v) if v <= 0.0 => Err(error),
+        _ => Ok(value),
+    }
+}
+
+/// Loads a max penetration correction with the same check as
+/// [Entity::set_max_penetration_correction]
+fn deserialize_max_penetration_correction<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Scalar>, D::Error> {
+    check_positive(
+        Option::deserialize(deserializer)?,
+        PhysicsError::PenetrationCorrection,
+    )
+    .map_err(D::Error::custom)
+}
+
+/// Loads a visibility radius with the same check as [Entity::set_visibility_radius]
+fn deserialize_visibility_radius<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Scalar>, D::Error> {
+    check_positive(
+        Option::deserialize(deserializer)?,
+        PhysicsError::VisibilityRadius,
+    )
+    .map_err(D::Error::custom)
+}
+
+/// Loads a continuous threshold with the same check as [Entity::set_continuous_threshold]
+fn deserialize_continuous_threshold<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Scalar>, D::Error> {
+    check_positive(
+        Option::deserialize(deserializer)?,
+        PhysicsError::ContinuousThreshold,
+    )
+    .map_err(D::Error::custom)
+}
+
+fn check_field_restitution(restitution: Option<Scalar>) -> PhysicsResult<Option<Scalar>> {
+    match restitution {
+        Some(r) if !(0.0..=1.0).contains(&r) => Err(PhysicsError::Restitution),
+        _ => Ok(restitution),
+    }
+}
+
+/// Loads a field restitution with the same check as [Entity::set_field_restitution]
+fn deserialize_field_restitution<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Scalar>, D::Error> {
+    check_field_restitution(Option::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+/// Loads a mass with the same check as [Entity::set_mass]
+fn deserialize_mass<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Scalar>, D::Error> {
+    check_positive(Option::deserialize(deserializer)?, PhysicsError::Mass).map_err(D::Error::custom)
+}
+
+/// Loads a throw impulse with the same check as [Entity::set_throwable]
+fn deserialize_throw_impulse<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Scalar>, D::Error> {
+    check_positive(
+        Option::deserialize(deserializer)?,
+        PhysicsError::ThrowImpulse,
+    )
+    .map_err(D::Error::custom)
+}
+
+fn check_interact_range(range: Scalar) -> PhysicsResult<Scalar> {
+    if range < 0.0 {
+        Err(PhysicsError::InteractRange)
+    } else {
+        Ok(range)
+    }
+}
+
+/// Loads an interaction range with the same check as [Entity::set_interactable]
+fn deserialize_interact_range<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Scalar, D::Error> {
+    check_interact_range(Scalar::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+fn default_targetable() -> bool {
+    true
+}
 
 /// A trait for game objects that wrap/own an entity
 pub trait HasEntity {
@@ -44,6 +194,166 @@ pub struct Entity {
     solidity: Solidity,
     #[has_uuid]
     uuid: Uuid,
+    /// Game-defined labels for finding groups of entities
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Overlaps with cells smaller than this are ignored during collision resolution
+    #[serde(
+        default = "default_contact_skin",
+        deserialize_with = "deserialize_contact_skin"
+    )]
+    contact_skin: Scalar,
+    /// What the entity drops when it dies
+    #[serde(default)]
+    drop_table: Option<DropTable>,
+    /// Emits footstep events as the entity moves
+    #[serde(default)]
+    step_emitter: Option<DistanceEmitter>,
+    /// The remaining time that one-way cells are ignored for, while dropping through them
+    #[serde(default)]
+    drop_through: Option<f64>,
+    /// Whether the entity is pushed out of solid cells it's stuck inside
+    #[serde(default)]
+    resolves_penetration: bool,
+    /// The furthest the entity is pushed out of a solid cell it's stuck inside in one tick.
+    /// `None` pushes it all the way out at once
+    #[serde(default, deserialize_with = "deserialize_max_penetration_correction")]
+    max_penetration_correction: Option<Scalar>,
+    /// How the entity is kept within the camera's view, if at all
+    #[serde(default)]
+    camera_constraint: Option<CameraConstraint>,
+    /// The collision layers the entity is on, for which cells it collides with
+    #[serde(default)]
+    collision_layers: CollisionLayers,
+    /// How far around the entity is visible through the fog of war, if it reveals anything
+    #[serde(default, deserialize_with = "deserialize_visibility_radius")]
+    visibility_radius: Option<Scalar>,
+    /// The time remaining on each of the entity's crowd control states
+    #[serde(default)]
+    crowd_control: CrowdControlStates,
+    /// The downward acceleration the entity falls with while airborne, `None` floats
+    #[serde(default)]
+    gravity: Option<Scalar>,
+    /// Whether the entity has been launched into the air and hasn't landed yet
+    #[serde(default)]
+    airborne: bool,
+    /// Moves longer than this in one tick are swept against the field so they can't tunnel
+    /// through thin cells. `None` always uses the cheaper discrete resolution
+    #[serde(default, deserialize_with = "deserialize_continuous_threshold")]
+    continuous_threshold: Option<Scalar>,
+    /// The fraction of its speed the entity keeps when it bounces off of a field cell.
+    /// `None` stops it against the cell without bouncing
+    #[serde(default, deserialize_with = "deserialize_field_restitution")]
+    field_restitution: Option<Scalar>,
+    #[serde(skip)]
+    collision_paths: CollisionPathCounts,
+    /// The time remaining that the entity can't be damaged for
+    #[serde(default)]
+    invulnerable: f64,
+    /// Whether the entity can't be damaged until this is turned off, however long that is
+    #[serde(default)]
+    always_invulnerable: bool,
+    /// Whether targeting, like lock on or aim assist, can pick the entity
+    #[serde(default = "default_targetable")]
+    targetable: bool,
+    /// The entity's dash ability, if it has one
+    #[serde(default)]
+    dash: Option<Dash>,
+    /// The health pool that damage to the entity goes to instead of its own health, if any
+    #[serde(default)]
+    shared_health: Option<SharedHealthId>,
+    /// How the entity's sprite flashes when hit and dissolves when it dies, if at all
+    #[serde(default)]
+    hit_effects: Option<HitEffects>,
+    /// Whether the entity moves like a projectile, stopping at and reporting the first cell it hits
+    #[serde(default)]
+    projectile: bool,
+    /// The cells hit as a projectile since they were last taken
+    #[serde(skip)]
+    wall_impacts: Vec<WallImpact>,
+    /// What happens when another entity interacts with this one, if it can be interacted with
+    #[serde(default)]
+    interactable: Option<Interactable>,
+    /// Whether the entity is trying to interact with whatever's nearest on the next tick
+    #[serde(skip)]
+    interact_requested: bool,
+    /// How heavy the entity is when it's pushed by other entities, `None` can't be pushed
+    #[serde(default, deserialize_with = "deserialize_mass")]
+    mass: Option<Scalar>,
+    /// The impulse the entity is thrown with after being grabbed, if it can be
+    #[serde(default, deserialize_with = "deserialize_throw_impulse")]
+    throw_impulse: Option<Scalar>,
+    /// The entity this one is attached to and moves with, if any
+    #[serde(skip)]
+    attachment: Option<Attachment>,
+    /// The shield that absorbs damage before the entity's health, if it has one
+    #[serde(default)]
+    shield: Option<Shield>,
+    /// The air drag slowing the entity while it floats without gravity, if it's floating
+    #[serde(skip)]
+    floating: Option<Scalar>,
+    /// Named points on the entity that effects are attached to, as offsets from its box's position
+    #[serde(default)]
+    sockets: HashMap<String, Vec2d>,
+    /// Whether entities standing on top of this one are carried along as it moves
+    #[serde(default)]
+    carries_riders: bool,
+    /// The platform the entity is standing on and riding, if any
+    #[serde(skip)]
+    standing_on: Option<EntityId>,
+    /// The layer the entity is drawn on, higher layers are drawn over lower ones
+    #[serde(default)]
+    z_layer: i32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// How many moves an entity has resolved against the field with each collision path
+/// (see [Entity::set_continuous_threshold])
+pub struct CollisionPathCounts {
+    /// Moves resolved by checking the cells the destination overlaps
+    pub discrete: u64,
+    /// Moves resolved by sweeping the entity's box along its displacement
+    pub swept: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A projectile hitting a field cell (see [Entity::set_projectile])
+pub struct WallImpact {
+    /// Where the projectile touched the surface of the cell
+    pub point: Point,
+    /// The normal of the surface that was hit, pointing away from the cell
+    pub normal: [Scalar; 2],
+    /// The cell that was hit
+    pub cell: CellId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An entity being carried along by another (see [Scene::attach](crate::scene::Scene::attach))
+pub struct Attachment {
+    /// The entity being followed
+    pub parent: EntityId,
+    /// Where this entity's box is kept relative to the parent's
+    pub offset: Vec2d,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Something other entities can interact with, like a door, chest or NPC
+/// (see [Entity::set_interactable])
+pub struct Interactable {
+    /// How far the gap between the entities' boxes can be for them to interact
+    #[serde(deserialize_with = "deserialize_interact_range")]
+    pub range: Scalar,
+    /// The game-defined action that the interaction performs
+    pub action: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How an entity is kept within the camera's view (see [Entity::constrain_to_view])
+pub enum CameraConstraint {
+    /// The entity stops at the edges of the view
+    Clamp,
+    /// The entity stops at the edges of the view and its velocity reflects off of them
+    Bounce,
 }
 
 impl Entity {
@@ -56,6 +366,41 @@ impl Entity {
             health: Health::new(10.0),
             solidity: SOLID,
             uuid: Uuid::new_v4(),
+            tags: Vec::new(),
+            contact_skin: DEFAULT_CONTACT_SKIN,
+            drop_table: None,
+            step_emitter: None,
+            drop_through: None,
+            resolves_penetration: false,
+            max_penetration_correction: None,
+            camera_constraint: None,
+            collision_layers: CollisionLayers::default(),
+            visibility_radius: None,
+            crowd_control: CrowdControlStates::default(),
+            gravity: None,
+            airborne: false,
+            continuous_threshold: None,
+            field_restitution: None,
+            collision_paths: CollisionPathCounts::default(),
+            invulnerable: 0.0,
+            always_invulnerable: false,
+            targetable: true,
+            dash: None,
+            shared_health: None,
+            hit_effects: None,
+            projectile: false,
+            wall_impacts: Vec::new(),
+            interactable: None,
+            interact_requested: false,
+            mass: None,
+            throw_impulse: None,
+            attachment: None,
+            shield: None,
+            floating: None,
+            sockets: HashMap::new(),
+            carries_riders: false,
+            standing_on: None,
+            z_layer: 0,
         })
     }
 
@@ -64,6 +409,31 @@ impl Entity {
         self.uuid
     }
 
+    /// Labels the entity with `tag`. Does nothing if the entity already has it
+    pub fn add_tag<T: Into<String>>(&mut self, tag: T) {
+        let tag = tag.into();
+        if !self.has_tag(&tag) {
+            self.tags.push(tag);
+        }
+    }
+
+    /// Removes `tag` from the entity, returning whether it had the tag
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let len = self.tags.len();
+        self.tags.retain(|t| t != tag);
+        len != self.tags.len()
+    }
+
+    /// Whether the entity is labeled with `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// All of the entity's tags
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
     /// Sets the entity's velocity, limited by its maximum velocity
     pub fn set_velocity(&mut self, velocity: Velocity) {
         self.velocity = if velocity.magnitude_sq() <= self.max_velocity * self.max_velocity {
@@ -73,6 +443,16 @@ impl Entity {
         }
     }
 
+    /// Steers the entity so that its position reaches `target` after moving for `dt`,
+    /// limited by its maximum velocity
+    pub fn arrive_at(&mut self, target: Point, dt: Scalar) {
+        if dt <= 0.0 {
+            return;
+        }
+        let to_target: Velocity = (target - *self.physbox.pos()).into();
+        self.set_velocity(to_target * (1.0 / dt));
+    }
+
     /// Gets the entity's current velocity
     pub fn get_velocity(&self) -> Velocity {
         self.velocity
@@ -88,6 +468,631 @@ impl Entity {
         Ok(())
     }
 
+    /// Whether the entity's velocity is close enough to zero that it won't move
+    pub fn is_at_rest(&self) -> bool {
+        self.velocity.approx_eq(&[0.0, 0.0].into(), DEFAULT_EPSILON)
+    }
+
+    /// Sets how far the entity may overlap cells without colliding with them.
+    /// Must be greater than or equal to 0
+    pub fn set_contact_skin(&mut self, contact_skin: Scalar) -> PhysicsResult<()> {
+        self.contact_skin = check_contact_skin(contact_skin)?;
+
+        Ok(())
+    }
+
+    /// Gets how far the entity may overlap cells without colliding with them
+    pub fn get_contact_skin(&self) -> Scalar {
+        self.contact_skin
+    }
+
+    /// Sets what the entity drops when it dies, `None` drops nothing
+    pub fn set_drop_table(&mut self, drop_table: Option<DropTable>) {
+        self.drop_table = drop_table;
+    }
+
+    /// What the entity drops when it dies
+    pub fn get_drop_table(&self) -> Option<&DropTable> {
+        self.drop_table.as_ref()
+    }
+
+    /// Rolls the entity's drop table, placing the drop at the entity's center
+    pub fn roll_loot<R: Rng>(&self, rng: &mut R) -> Option<LootDrop> {
+        let (item, quantity) = self.drop_table.as_ref()?.roll(rng)?;
+        Some(LootDrop {
+            item: item.to_string(),
+            quantity,
+            pos: self.physbox.center(),
+        })
+    }
+
+    /// Makes the entity move like a projectile. Every move is swept against the field, however
+    /// slow, so it can't skip over thin cells, and it stops at the surface of the first cell it
+    /// hits (or bounces off of it with [Entity::set_field_restitution]), recording a [WallImpact]
+    pub fn set_projectile(&mut self, projectile: bool) {
+        self.projectile = projectile;
+    }
+
+    /// Whether the entity moves like a projectile
+    pub fn is_projectile(&self) -> bool {
+        self.projectile
+    }
+
+    /// Lets solid entities push this one out of their way, moving it more the lighter it is
+    /// compared to them. Entities without a mass are immovable to those with one, and with an
+    /// [OverlapPush](crate::scene::OverlapPush) two without a mass split the push evenly.
+    /// Must be positive, `None` isn't pushed
+    pub fn set_mass(&mut self, mass: Option<Scalar>) -> PhysicsResult<()> {
+        self.mass = check_positive(mass, PhysicsError::Mass)?;
+
+        Ok(())
+    }
+
+    /// How heavy the entity is when it's pushed
+    pub fn get_mass(&self) -> Option<Scalar> {
+        self.mass
+    }
+
+    /// Changes the entity's velocity by `impulse` divided by its mass (1 without one).
+    /// Like [Entity::launch] this isn't limited by the maximum velocity
+    pub fn apply_impulse(&mut self, [x, y]: Vec2d) {
+        let mass = self.mass.unwrap_or(1.0);
+        self.velocity.x += x / mass;
+        self.velocity.y += y / mass;
+    }
+
+    /// Lets the entity be grabbed and thrown with `impulse` (see
+    /// [Scene::grab](crate::scene::Scene::grab)). Must be positive, `None` can't be grabbed
+    pub fn set_throwable(&mut self, impulse: Option<Scalar>) -> PhysicsResult<()> {
+        self.throw_impulse = check_positive(impulse, PhysicsError::ThrowImpulse)?;
+
+        Ok(())
+    }
+
+    /// The impulse the entity is thrown with, if it can be grabbed
+    pub fn get_throw_impulse(&self) -> Option<Scalar> {
+        self.throw_impulse
+    }
+
+    /// The entity this one is attached to, if any
+    pub fn get_attachment(&self) -> Option<&Attachment> {
+        self.attachment.as_ref()
+    }
+
+    pub(crate) fn set_attachment(&mut self, attachment: Option<Attachment>) {
+        self.attachment = attachment;
+    }
+
+    /// Takes the cells hit as a projectile since the last call, in the order they were hit.
+    /// Entities in a scene have theirs collected every tick, see
+    /// [Scene::take_wall_impacts](crate::scene::Scene::take_wall_impacts)
+    pub fn take_wall_impacts(&mut self) -> Vec<WallImpact> {
+        std::mem::take(&mut self.wall_impacts)
+    }
+
+    /// Lets other entities interact with this one within the interactable's range, emitting an
+    /// [Interaction](crate::scene::Interaction) when they do. `None` can't be interacted with
+    pub fn set_interactable(&mut self, interactable: Option<Interactable>) -> PhysicsResult<()> {
+        if let Some(interactable) = interactable.as_ref() {
+            check_interact_range(interactable.range)?;
+        }
+        self.interactable = interactable;
+
+        Ok(())
+    }
+
+    /// What happens when another entity interacts with this one
+    pub fn get_interactable(&self) -> Option<&Interactable> {
+        self.interactable.as_ref()
+    }
+
+    /// Interacts with the nearest interactable in range on the next tick, if there is one
+    /// (see [Scene::nearest_interactable](crate::scene::Scene::nearest_interactable))
+    pub fn request_interaction(&mut self) {
+        self.interact_requested = true;
+    }
+
+    /// Whether the entity requested an interaction since the last call, clearing it
+    pub fn take_interaction_request(&mut self) -> bool {
+        std::mem::take(&mut self.interact_requested)
+    }
+
+    /// Sets the emitter for events as the entity moves (i.e. footsteps). `None` emits nothing
+    pub fn set_step_emitter(&mut self, step_emitter: Option<DistanceEmitter>) {
+        self.step_emitter = step_emitter;
+    }
+
+    /// Returns the number of step events emitted since the last call, clearing them
+    pub fn take_step_events(&mut self) -> usize {
+        self.step_emitter
+            .as_mut()
+            .map(DistanceEmitter::take_events)
+            .unwrap_or(0)
+    }
+
+    /// Stops one-way cells (see [Solidity::is_one_way]) from blocking the entity for `duration` seconds
+    /// so it can drop through a platform it's standing on. Collision is only re-enabled once
+    /// the time is up and the entity isn't inside any one-way cell
+    pub fn drop_through_one_way(&mut self, duration: f64) {
+        self.drop_through = Some(duration);
+    }
+
+    /// Whether one-way cells are currently ignored by the entity
+    pub fn is_dropping_through(&self) -> bool {
+        self.drop_through.is_some()
+    }
+
+    fn tick_drop_through(&mut self, field: &Field, dt: f64) {
+        if let Some(remaining) = self.drop_through.as_mut() {
+            *remaining -= dt;
+            if *remaining <= 0.0
+                && !field
+                    .cells_overlapping_box(&self.physbox)
+                    .any(|c| c.get_solidity().is_one_way())
+            {
+                self.drop_through = None;
+            }
+        }
+    }
+
+    /// Sets whether the entity is pushed out of a solid cell it's stuck inside (i.e. spawned
+    /// inside of a wall) before it moves. Off by default
+    pub fn set_resolves_penetration(&mut self, resolves_penetration: bool) {
+        self.resolves_penetration = resolves_penetration;
+    }
+
+    /// Whether the entity is pushed out of solid cells it's stuck inside
+    pub fn resolves_penetration(&self) -> bool {
+        self.resolves_penetration
+    }
+
+    /// Sets the furthest the entity is pushed out of a solid cell it's stuck inside
+    /// (see [Entity::set_resolves_penetration]) per tick, so deep overlaps are eased out over a few ticks
+    /// instead of popping. `None` pushes it out all at once. Must be greater than 0
+    pub fn set_max_penetration_correction(
+        &mut self,
+        max_correction: Option<Scalar>,
+    ) -> PhysicsResult<()> {
+        self.max_penetration_correction =
+            check_positive(max_correction, PhysicsError::PenetrationCorrection)?;
+
+        Ok(())
+    }
+
+    /// Gets the furthest the entity is pushed out of a solid cell per tick
+    pub fn get_max_penetration_correction(&self) -> Option<Scalar> {
+        self.max_penetration_correction
+    }
+
+    /// Pushes the entity out of the solid cell it overlaps the most, by at most the
+    /// maximum penetration correction. Prefers the shortest way out that doesn't lead into
+    /// another solid cell. Returns whether the entity was stuck inside a cell
+    fn resolve_penetration(&mut self, field: &Field) -> bool {
+        let skin_box = self.physbox.inset(self.contact_skin);
+        let overlaps_solid = |physbox: &PhysBox| {
+            field
+                .cells_overlapping_box(physbox)
+                .any(|c| c.get_solidity() == &SOLID && c.is_solid_to(self.collision_layers))
+        };
+        let deepest = field
+            .cells_overlapping_box(&skin_box)
+            .filter(|c| c.get_solidity() == &SOLID && c.is_solid_to(self.collision_layers))
+            .map(|c| {
+                let cell_box = c.get_box();
+                let overlap_w = self.physbox.right_x().min(cell_box.right_x())
+                    - self.physbox.left_x().max(cell_box.left_x());
+                let overlap_h = self.physbox.bottom_y().min(cell_box.bottom_y())
+                    - self.physbox.top_y().max(cell_box.top_y());
+                (cell_box, overlap_w * overlap_h)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        let cell_box = match deepest {
+            Some((cell_box, _)) => cell_box,
+            None => return false,
+        };
+
+        let mut exits = [
+            [cell_box.left_x() - self.physbox.right_x(), 0.0],
+            [cell_box.right_x() - self.physbox.left_x(), 0.0],
+            [0.0, cell_box.top_y() - self.physbox.bottom_y()],
+            [0.0, cell_box.bottom_y() - self.physbox.top_y()],
+        ];
+        exits.sort_by(|a, b| (a[0].abs() + a[1].abs()).total_cmp(&(b[0].abs() + b[1].abs())));
+        let exit = exits
+            .iter()
+            .find(|exit| {
+                let mut resolved = self.physbox;
+                resolved.set_pos(*resolved.pos() + **exit);
+                !overlaps_solid(&resolved.inset(self.contact_skin))
+            })
+            .unwrap_or(&exits[0]);
+
+        let distance = exit[0].abs() + exit[1].abs();
+        let scale = self
+            .max_penetration_correction
+            .map(|max| (max / distance).min(1.0))
+            .unwrap_or(1.0);
+        let new_pos = *self.physbox.pos() + [exit[0] * scale, exit[1] * scale];
+        self.physbox.set_pos(new_pos);
+        true
+    }
+
+    /// Sets the collision layers the entity is on. The entity only collides with cells
+    /// that are [solid to](Cell::is_solid_to) one of them
+    pub fn set_collision_layers(&mut self, collision_layers: CollisionLayers) {
+        self.collision_layers = collision_layers;
+    }
+
+    /// The collision layers the entity is on
+    pub fn get_collision_layers(&self) -> CollisionLayers {
+        self.collision_layers
+    }
+
+    /// Sets how far around the entity's center is visible through the
+    /// [fog of war](crate::rendering::fog::FogOfWar). `None` reveals nothing. Must be greater than 0
+    pub fn set_visibility_radius(&mut self, radius: Option<Scalar>) -> PhysicsResult<()> {
+        self.visibility_radius = check_positive(radius, PhysicsError::VisibilityRadius)?;
+
+        Ok(())
+    }
+
+    /// How far around the entity is visible through the fog of war
+    pub fn get_visibility_radius(&self) -> Option<Scalar> {
+        self.visibility_radius
+    }
+
+    /// Stuns, roots or silences the entity for `duration` seconds, or keeps the remaining
+    /// duration if it's already longer. Counted down by [Entity::game_tick]
+    pub fn apply_crowd_control(&mut self, kind: CrowdControl, duration: f64) {
+        self.crowd_control.apply(kind, duration);
+    }
+
+    /// Ends a crowd control state early
+    pub fn clear_crowd_control(&mut self, kind: CrowdControl) {
+        self.crowd_control.clear(kind);
+    }
+
+    /// The time remaining on each of the entity's crowd control states
+    pub fn get_crowd_control(&self) -> &CrowdControlStates {
+        &self.crowd_control
+    }
+
+    /// Whether the entity can move, it doesn't while stunned or rooted.
+    /// Controllers should check this before applying movement input
+    pub fn can_move(&self) -> bool {
+        self.crowd_control.can_move()
+    }
+
+    /// Whether the entity can act (i.e. attack), it can't while stunned
+    pub fn can_act(&self) -> bool {
+        self.crowd_control.can_act()
+    }
+
+    /// Whether the entity can use abilities, it can't while stunned or silenced
+    pub fn can_use_abilities(&self) -> bool {
+        self.crowd_control.can_use_abilities()
+    }
+
+    /// Sets the downward acceleration the entity falls with while airborne.
+    /// `None` leaves airborne entities floating at their launch velocity
+    pub fn set_gravity(&mut self, gravity: Option<Scalar>) {
+        self.gravity = gravity;
+    }
+
+    /// Gets the downward acceleration the entity falls with while airborne
+    pub fn get_gravity(&self) -> Option<Scalar> {
+        self.gravity
+    }
+
+    /// Makes the entity float while airborne, ignoring its gravity and losing `drag` of its
+    /// velocity per second instead. `None` falls under gravity again. Usually set by the scene's
+    /// [ZeroGravity](crate::scene::ZeroGravity) regions
+    pub fn set_floating(&mut self, drag: Option<Scalar>) {
+        self.floating = drag;
+    }
+
+    /// Whether the entity is floating instead of falling while airborne
+    pub fn is_floating(&self) -> bool {
+        self.floating.is_some()
+    }
+
+    /// Sets the socket `name` at `offset` from the entity's box's position, replacing any
+    /// socket it already had with that name
+    pub fn set_socket<N: Into<String>>(&mut self, name: N, offset: Vec2d) {
+        self.sockets.insert(name.into(), offset);
+    }
+
+    /// Gets the offset of the socket `name` from the entity's box's position
+    pub fn get_socket(&self, name: &str) -> Option<Vec2d> {
+        self.sockets.get(name).copied()
+    }
+
+    /// Where the socket `name` is in the world
+    pub fn socket_position(&self, name: &str) -> Option<Point> {
+        self.get_socket(name)
+            .map(|offset| *self.physbox.pos() + offset)
+    }
+
+    /// Makes the entity invulnerable for `duration` seconds. Already being invulnerable
+    /// for longer isn't shortened
+    pub fn grant_invulnerability(&mut self, duration: f64) {
+        self.invulnerable = self.invulnerable.max(duration);
+    }
+
+    /// Makes the entity invulnerable until it's turned off again, on top of any
+    /// [granted](Entity::grant_invulnerability) invulnerability. Invulnerable entities can
+    /// still be [targeted](Entity::set_targetable)
+    pub fn set_invulnerable(&mut self, invulnerable: bool) {
+        self.always_invulnerable = invulnerable;
+    }
+
+    /// Whether the entity can't currently be damaged
+    pub fn is_invulnerable(&self) -> bool {
+        // Counting down by the tick length can leave a sliver of time from floating point error
+        self.always_invulnerable || self.invulnerable > DEFAULT_EPSILON
+    }
+
+    /// Sets whether targeting (i.e. [Scene::lock_on](crate::scene::Scene::lock_on) and
+    /// [Scene::nearest_target](crate::scene::Scene::nearest_target)) can pick the entity.
+    /// Untargetable entities can still be damaged by attacks that reach them
+    pub fn set_targetable(&mut self, targetable: bool) {
+        self.targetable = targetable;
+    }
+
+    /// Whether targeting can pick the entity
+    pub fn is_targetable(&self) -> bool {
+        self.targetable
+    }
+
+    /// Damages the entity unless it's invulnerable, depleting its shield before its health.
+    /// Returns whether it was damaged
+    pub fn take_damage(&mut self, amount: Scalar) -> bool {
+        if self.is_invulnerable() {
+            return false;
+        }
+        let to_health = match self.shield.as_mut() {
+            Some(shield) => shield.absorb(amount),
+            None => amount,
+        };
+        if to_health != 0.0 {
+            self.health.raw_damage(to_health);
+        }
+        true
+    }
+
+    /// Gives the entity a shield that absorbs damage before its health, `None` removes it
+    pub fn set_shield(&mut self, shield: Option<Shield>) {
+        self.shield = shield;
+    }
+
+    /// The shield that absorbs damage before the entity's health
+    pub fn get_shield(&self) -> Option<&Shield> {
+        self.shield.as_ref()
+    }
+
+    /// Makes the entity a member of the shared health pool `pool`, so damage to it reduces the
+    /// pool and it dies along with everyone else in the pool. `None` uses its own health again
+    pub fn set_shared_health(&mut self, pool: Option<SharedHealthId>) {
+        self.shared_health = pool;
+    }
+
+    /// The shared health pool the entity is a member of, if any
+    pub fn get_shared_health(&self) -> Option<SharedHealthId> {
+        self.shared_health
+    }
+
+    /// Sets how the entity's sprite flashes when it takes damage and dissolves when it dies.
+    /// While dissolving the entity isn't despawned (see [Entity::is_ready_to_despawn]).
+    /// `None` removes the effects
+    pub fn set_hit_effects(&mut self, config: Option<HitEffectsConfig>) {
+        self.hit_effects = config.map(HitEffects::new);
+    }
+
+    /// How the entity's sprite flashes when it takes damage and dissolves when it dies
+    pub fn hit_effects(&self) -> Option<&HitEffects> {
+        self.hit_effects.as_ref()
+    }
+
+    /// Flashes the entity's sprite if its health went down by `amount`, and starts dissolving it
+    /// if that killed it
+    pub fn react_to_health_change(&mut self, amount: Scalar) {
+        if let Some(effects) = self.hit_effects.as_mut() {
+            if amount < 0.0 {
+                effects.flash();
+            }
+            if self.health.is_dead() {
+                effects.start_dissolve();
+            }
+        }
+    }
+
+    /// Whether the entity is dead and done dissolving, so it can be removed
+    pub fn is_ready_to_despawn(&self) -> bool {
+        self.health.is_dead()
+            && self
+                .hit_effects
+                .as_ref()
+                .map(HitEffects::is_dissolved)
+                .unwrap_or(true)
+    }
+
+    /// Gives the entity a dash ability, `None` removes it
+    pub fn set_dash(&mut self, dash: Option<Dash>) {
+        self.dash = dash;
+    }
+
+    /// The entity's dash ability, if it has one
+    pub fn get_dash(&self) -> Option<&Dash> {
+        self.dash.as_ref()
+    }
+
+    /// Whether the entity is in the middle of a dash
+    pub fn is_dashing(&self) -> bool {
+        self.dash.as_ref().map(Dash::is_active).unwrap_or(false)
+    }
+
+    /// Dashes in `direction` if the entity has a dash that's off cooldown and it can move.
+    /// The entity is invulnerable until the dash ends, and goes back to its current velocity
+    /// afterwards. Returns whether the dash started
+    pub fn try_dash(&mut self, direction: Vec2d) -> bool {
+        if !self.can_move() || self.airborne {
+            return false;
+        }
+        let velocity = self.velocity;
+        let dash = match self.dash.as_mut() {
+            Some(dash) => dash,
+            None => return false,
+        };
+        match dash.start(direction, velocity) {
+            Some(dash_velocity) => {
+                let duration = dash.duration();
+                self.velocity = dash_velocity;
+                self.grant_invulnerability(duration);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the entity at its dash velocity, ending the dash early if it's blocked
+    fn dash_move(&mut self, field: &Field, dt: f64, step: f64) -> PhysicsResult<()> {
+        let expected = *self.physbox.pos() + self.velocity * step;
+        self.try_move(field, step)?;
+        let blocked = !self.physbox.pos().approx_eq(&expected, DEFAULT_EPSILON);
+        let resume = match self.dash.as_mut() {
+            Some(dash) if blocked => dash.stop(),
+            Some(dash) => dash.tick(dt),
+            None => None,
+        };
+        if let Some(velocity) = resume {
+            self.set_velocity(velocity);
+        }
+        Ok(())
+    }
+
+    /// Launches the entity upwards at `speed`, making it airborne until it lands on something.
+    /// Launching an airborne entity again replaces its vertical velocity so it can be juggled.
+    /// Unlike [Entity::set_velocity] this isn't limited by the maximum velocity
+    pub fn launch(&mut self, speed: Scalar) {
+        self.velocity.y = -speed;
+        self.airborne = true;
+    }
+
+    /// Whether the entity has been launched and hasn't landed yet
+    pub fn is_airborne(&self) -> bool {
+        self.airborne
+    }
+
+    /// Stops the airborne entity falling, as if it landed on the ground
+    pub(crate) fn land(&mut self) {
+        self.airborne = false;
+        self.velocity.y = 0.0;
+    }
+
+    /// Makes the entity a moving platform, carrying the entities standing on top of it along
+    /// with it when the scene has [PlatformRiding](crate::scene::PlatformRiding)
+    pub fn set_carries_riders(&mut self, carries_riders: bool) {
+        self.carries_riders = carries_riders;
+    }
+
+    /// Whether the entity carries the entities standing on top of it
+    pub fn carries_riders(&self) -> bool {
+        self.carries_riders
+    }
+
+    /// Sets the layer the entity is drawn on. Entities on higher layers are drawn over those on
+    /// lower ones, see [Scene::render_order](crate::scene::Scene::render_order)
+    pub fn set_z_layer(&mut self, z_layer: i32) {
+        self.z_layer = z_layer;
+    }
+
+    /// The layer the entity is drawn on
+    pub fn get_z_layer(&self) -> i32 {
+        self.z_layer
+    }
+
+    /// The platform the entity is standing on and riding, if any
+    pub fn get_standing_on(&self) -> Option<EntityId> {
+        self.standing_on
+    }
+
+    pub(crate) fn set_standing_on(&mut self, platform: Option<EntityId>) {
+        self.standing_on = platform;
+    }
+
+    /// Sets the displacement per tick above which moves are swept against the field instead of
+    /// resolved discretely. Must be positive, `None` always resolves discretely
+    pub fn set_continuous_threshold(&mut self, threshold: Option<Scalar>) -> PhysicsResult<()> {
+        self.continuous_threshold = check_positive(threshold, PhysicsError::ContinuousThreshold)?;
+
+        Ok(())
+    }
+
+    /// Gets the displacement per tick above which moves are swept against the field
+    pub fn get_continuous_threshold(&self) -> Option<Scalar> {
+        self.continuous_threshold
+    }
+
+    /// Makes the entity bounce off of field cells, keeping `restitution` of its speed along the
+    /// blocked axis each bounce. Must be in [0, 1], `None` stops it against cells instead
+    pub fn set_field_restitution(&mut self, restitution: Option<Scalar>) -> PhysicsResult<()> {
+        self.field_restitution = check_field_restitution(restitution)?;
+
+        Ok(())
+    }
+
+    /// Gets the fraction of its speed the entity keeps when it bounces off of a field cell
+    pub fn get_field_restitution(&self) -> Option<Scalar> {
+        self.field_restitution
+    }
+
+    /// How many moves have been resolved with each collision path
+    pub fn collision_path_counts(&self) -> CollisionPathCounts {
+        self.collision_paths
+    }
+
+    /// Sets how the entity is kept within the camera's view regardless of the field
+    /// (i.e. screen-bound enemies in an arcade mode). `None` lets it leave the view
+    pub fn set_camera_constraint(&mut self, camera_constraint: Option<CameraConstraint>) {
+        self.camera_constraint = camera_constraint;
+    }
+
+    /// How the entity is kept within the camera's view
+    pub fn get_camera_constraint(&self) -> Option<CameraConstraint> {
+        self.camera_constraint
+    }
+
+    /// Moves the entity back inside of `view` according to its [CameraConstraint].
+    /// If the view moved over the entity it's carried along by the edge.
+    /// Bouncing only reflects velocity heading out of the view, so an entity carried by a moving
+    /// edge doesn't flip back and forth. Does nothing if the entity isn't constrained
+    pub fn constrain_to_view(&mut self, view: &PhysBox) {
+        let constraint = match self.camera_constraint {
+            Some(c) => c,
+            None => return,
+        };
+
+        for edge in BoxEdge::iter() {
+            let outside = match edge {
+                BoxEdge::Top | BoxEdge::Left => self.physbox.get_edge(*edge) < view.get_edge(*edge),
+                BoxEdge::Bottom | BoxEdge::Right => {
+                    self.physbox.get_edge(*edge) > view.get_edge(*edge)
+                }
+            };
+            if !outside {
+                continue;
+            }
+            self.physbox.set_touching_edge(view, *edge);
+
+            if constraint == CameraConstraint::Bounce && self.velocity.is_reduced_by_edge(*edge) {
+                match edge.perpendicular_axis() {
+                    Axis::X => self.velocity.x = -self.velocity.x,
+                    Axis::Y => self.velocity.y = -self.velocity.y,
+                }
+            }
+        }
+    }
+
     /// Gets the entity's maximum velocity
     pub fn get_max_velocity(&self) -> Scalar {
         self.max_velocity
@@ -102,16 +1107,213 @@ impl Entity {
 
     /// Returns a callback function for resolving entity-entity collisions
     pub fn game_tick<E>(&mut self, args: &GameTickArgs<E>) -> PhysicsResult<()> {
-        self.try_move(args.field, args.dt)
+        let dash_step = self.dash.as_ref().and_then(|d| d.step(args.dt));
+        // Airborne entities keep falling even when they can't move on their own
+        let moved = if self.airborne {
+            self.fall(args.field, args.dt)
+        } else if let Some(step) = dash_step {
+            self.dash_move(args.field, args.dt, step)
+        } else if self.can_move() {
+            self.try_move(args.field, args.dt)
+        } else {
+            self.tick_drop_through(args.field, args.dt);
+            Ok(())
+        };
+        if dash_step.is_none() {
+            if let Some(dash) = self.dash.as_mut() {
+                dash.tick(args.dt);
+            }
+        }
+        self.crowd_control.tick(args.dt);
+        self.invulnerable = (self.invulnerable - args.dt).max(0.0);
+        if let Some(shield) = self.shield.as_mut() {
+            shield.tick(args.dt);
+        }
+        if let Some(effects) = self.hit_effects.as_mut() {
+            effects.advance(args.dt);
+            // However it died, the entity dissolves before it's despawned
+            if self.health.is_dead() {
+                effects.start_dissolve();
+            }
+        }
+        moved
+    }
+
+    /// Moves the airborne entity under gravity, landing it once its fall is blocked
+    fn fall(&mut self, field: &Field, dt: f64) -> PhysicsResult<()> {
+        let gravity = match self.floating {
+            Some(drag) => {
+                let kept = (1.0 - drag * dt).max(0.0);
+                self.velocity.x *= kept;
+                self.velocity.y *= kept;
+                0.0
+            }
+            None => self.gravity.unwrap_or(0.0),
+        };
+        self.velocity.y += gravity * dt;
+        let falling = self.velocity.y > 0.0;
+        let expected_y = self.physbox.pos().y + self.velocity.y * dt;
+        self.try_move(field, dt)?;
+        // A bounce too weak to outlast a tick of gravity would just jitter against the ground
+        let rebound = -self.velocity.y;
+        if falling && self.physbox.pos().y < expected_y - DEFAULT_EPSILON && rebound <= gravity * dt
+        {
+            self.airborne = false;
+            self.velocity.y = 0.0;
+        }
+        Ok(())
     }
 
     /// Attempts to move this entity according to its velocity until it collides
     /// with any cells
     fn try_move(&mut self, field: &Field, dt: f64) -> PhysicsResult<()> {
-        if self.velocity == [0.0, 0.0].into() {
+        // Movement isn't defined inside of solid cells, so the entity doesn't move on its own
+        // until it's been pushed out. This way it can't sink any deeper
+        if self.resolves_penetration && self.resolve_penetration(field) {
+            self.tick_drop_through(field, dt);
+            return Ok(());
+        }
+
+        if self.is_at_rest() {
+            self.tick_drop_through(field, dt);
             return Ok(());
         }
 
+        let displacement = self.velocity * dt;
+        if self.projectile {
+            if let Some(new_box) = self.projectile_move(field, [displacement.x, displacement.y]) {
+                self.collision_paths.swept += 1;
+                let moved = *new_box.pos() - *self.physbox.pos();
+                if let Some(emitter) = self.step_emitter.as_mut() {
+                    emitter.travel(f64::hypot(moved.x, moved.y));
+                }
+                self.physbox = new_box;
+                self.tick_drop_through(field, dt);
+                return Ok(());
+            }
+        }
+        let new_box = match self.continuous_threshold {
+            Some(threshold) if displacement.magnitude_sq() > threshold * threshold => {
+                self.swept_move(field, [displacement.x, displacement.y])
+            }
+            _ => None,
+        };
+        let new_box = match new_box {
+            Some(new_box) => {
+                self.collision_paths.swept += 1;
+                new_box
+            }
+            None => {
+                self.collision_paths.discrete += 1;
+                self.discrete_move(field, dt)?
+            }
+        };
+
+        let moved = *new_box.pos() - *self.physbox.pos();
+        if let Some(restitution) = self.field_restitution {
+            if (moved.x - displacement.x).abs() > DEFAULT_EPSILON {
+                self.velocity.x = -self.velocity.x * restitution;
+            }
+            if (moved.y - displacement.y).abs() > DEFAULT_EPSILON {
+                self.velocity.y = -self.velocity.y * restitution;
+            }
+        }
+        if let Some(emitter) = self.step_emitter.as_mut() {
+            emitter.travel(f64::hypot(moved.x, moved.y));
+        }
+        self.physbox = new_box;
+        self.tick_drop_through(field, dt);
+
+        // TODO: switch to a separate "resolve entity collisions step"
+        // doing these collated will definite cause problems as the number
+        // of entities increases
+        Ok(())
+    }
+
+    /// Sweeps the entity's box along `displacement`, sliding along whatever it hits.
+    /// Returns `None` if the box would end up off of the field, which the discrete path handles
+    fn swept_move(&self, field: &Field, displacement: [Scalar; 2]) -> Option<PhysBox> {
+        let blocks = |cell: &Cell| {
+            cell.is_solid_to(self.collision_layers)
+                && !(self.drop_through.is_some() && cell.get_solidity().is_one_way())
+        };
+        let mut new_box = self.physbox;
+        let mut remaining = displacement;
+        // Each hit blocks an axis, so there can be at most one per axis
+        for _ in 0..2 {
+            match field.sweep_box_blocked_by(&new_box, remaining, blocks) {
+                Some(hit) => {
+                    new_box.set_pos(hit.pos);
+                    let left = 1.0 - hit.time;
+                    remaining = match hit.axis() {
+                        Axis::X => [0.0, remaining[1] * left],
+                        Axis::Y => [remaining[0] * left, 0.0],
+                    };
+                }
+                None => {
+                    new_box.set_pos(*new_box.pos() + remaining);
+                    remaining = [0.0, 0.0];
+                    break;
+                }
+            }
+        }
+        if remaining != [0.0, 0.0] {
+            return None;
+        }
+
+        if Self::is_on_field(field, &new_box) {
+            Some(new_box)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `physbox` lies on the field. The corners are checked slightly inside of the box,
+    /// since a box flush with the far edge of the field doesn't contain its own right and
+    /// bottom edges
+    fn is_on_field(field: &Field, physbox: &PhysBox) -> bool {
+        let inner = physbox.inset(DEFAULT_EPSILON);
+        let corner = [inner.right_x(), inner.bottom_y()];
+        field.cell_at_pos(*inner.pos()).is_some() && field.cell_at_pos(corner.into()).is_some()
+    }
+
+    /// Sweeps the projectile along `displacement` up to the first cell it hits, stopping or
+    /// bouncing there and recording the impact. Returns `None` if the box would end up off of
+    /// the field, which the discrete path handles
+    fn projectile_move(&mut self, field: &Field, displacement: [Scalar; 2]) -> Option<PhysBox> {
+        let blocks = |cell: &Cell| {
+            cell.is_solid_to(self.collision_layers)
+                && !(self.drop_through.is_some() && cell.get_solidity().is_one_way())
+        };
+        let mut new_box = self.physbox;
+        let hit = field.sweep_box_blocked_by(&new_box, displacement, blocks);
+        match hit {
+            Some(hit) => new_box.set_pos(hit.pos),
+            None => new_box.set_pos(*new_box.pos() + displacement),
+        }
+        if !Self::is_on_field(field, &new_box) {
+            return None;
+        }
+
+        if let Some(hit) = hit {
+            match (self.field_restitution, hit.axis()) {
+                (Some(restitution), Axis::X) => self.velocity.x *= -restitution,
+                (Some(restitution), Axis::Y) => self.velocity.y *= -restitution,
+                (None, _) => self.velocity = [0.0, 0.0].into(),
+            }
+            self.wall_impacts.push(WallImpact {
+                // The face of the projectile touching the cell's edge
+                point: new_box.edge_midpoint(hit.edge.opposite()),
+                normal: hit.normal(),
+                cell: hit.cell,
+            });
+        }
+        Some(new_box)
+    }
+
+    /// Resolves the move against the cells the destination overlaps, returning where the
+    /// entity ends up
+    fn discrete_move(&self, field: &Field, dt: f64) -> PhysicsResult<PhysBox> {
         // TODO: having to recalculate the current cell every time will get time intensive
         // Should create a new function to take into account the old current cell and its neighbors
         // at the very least only going through those. Even more so, we can add the edges that were
@@ -119,8 +1321,12 @@ impl Entity {
         let current_cell = field
             .cell_at_pos(*self.physbox.pos())
             .ok_or_else(|| PhysicsError::NoFieldCell(*self.physbox.pos()))?;
-        let current_cell_overlaps =
-            field.neighbors_of_cell_overlapping_box(current_cell, &self.physbox)?;
+        // Only overlaps deeper than the contact skin count, otherwise an entity resting
+        // on top of a cell would be blocked by that cell's neighbors when sliding along it
+        let current_cell_overlaps = field.neighbors_of_cell_overlapping_box(
+            current_cell,
+            &self.physbox.inset(self.contact_skin),
+        )?;
 
         let new_pos = *self.physbox.pos() + self.velocity * dt;
         let mut new_box = self.physbox.clone();
@@ -129,14 +1335,23 @@ impl Entity {
         // Cell Based collisions
         if !new_box.is_fully_contained_by(&current_cell.get_box()) {
             let mut apply_movement_reductions = |from_this_cell: &Cell| -> PhysicsResult<()> {
-                let from_cells_neighbors =
-                    field.neighbors_of_cell_overlapping_box(from_this_cell, &new_box)?;
+                let from_cells_neighbors = field.neighbors_of_cell_overlapping_box(
+                    from_this_cell,
+                    &new_box.inset(self.contact_skin),
+                )?;
 
                 for (edge, neighbors_on_edge) in from_cells_neighbors.iter_by_edge() {
                     for neighbor in neighbors_on_edge {
-                        if (!from_this_cell.get_solidity().exit_edge(edge)
-                            || !neighbor.get_solidity().enter_edge(edge.opposite()))
-                            && self.velocity.is_reduced_by_edge(edge)
+                        // Only entering a one-way cell is let through, leaving one still
+                        // stops against whatever is beside it
+                        if self.drop_through.is_some() && neighbor.get_solidity().is_one_way() {
+                            continue;
+                        }
+                        let exit_blocked = !from_this_cell.get_solidity().exit_edge(edge)
+                            && from_this_cell.is_solid_to(self.collision_layers);
+                        let enter_blocked = !neighbor.get_solidity().enter_edge(edge.opposite())
+                            && neighbor.is_solid_to(self.collision_layers);
+                        if (exit_blocked || enter_blocked) && self.velocity.is_reduced_by_edge(edge)
                         {
                             new_box.set_touching_edge(&from_this_cell.get_box(), edge);
                         }
@@ -164,12 +1379,7 @@ impl Entity {
             }
         }
 
-        self.physbox = new_box;
-
-        // TODO: switch to a separate "resolve entity collisions step"
-        // doing these collated will definite cause problems as the number
-        // of entities increases
-        Ok(())
+        Ok(new_box)
     }
 }
 
@@ -214,7 +1424,10 @@ impl View for EntityView {
 mod test {
     use super::*;
 
-    use crate::PhysicsError;
+    use crate::{
+        types::{ENTER_TOP, NO_SOLIDITY},
+        PhysicsError,
+    };
 
     // Doing a lot of square roots with the vector math propogates the floating-point error
     // a bunch, this is just to make sure it's reasonably accurate
@@ -230,6 +1443,18 @@ mod test {
         );
     }
 
+    #[test]
+    fn tiny_velocity_is_at_rest() {
+        let mut entity = Entity::new().unwrap();
+        assert!(entity.is_at_rest());
+
+        entity.set_velocity([1e-12, -1e-12].into());
+        assert!(entity.is_at_rest());
+
+        entity.set_velocity([0.5, 0.0].into());
+        assert!(!entity.is_at_rest());
+    }
+
     #[test]
     fn set_velocity_bounded_by_max_velocity_maintains_angle() {
         let mut entity = Entity::new().unwrap();
@@ -250,4 +1475,666 @@ mod test {
         entity.set_velocity(velocity);
         assert_eq!(entity.velocity, velocity);
     }
+
+    #[test]
+    fn set_contact_skin_fails_with_negative() {
+        let mut entity = Entity::new().unwrap();
+
+        assert_eq!(
+            entity.set_contact_skin(-1.0).unwrap_err(),
+            PhysicsError::ContactSkin
+        );
+    }
+
+    fn reloaded(entity: &Entity) -> Result<Entity, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(&rmp_serde::to_vec(entity).unwrap())
+    }
+
+    #[test]
+    fn loading_checks_fields_like_their_setters() {
+        assert!(reloaded(&Entity::new().unwrap()).is_ok());
+
+        let bad_saves: Vec<fn(&mut Entity)> = vec![
+            |e| e.contact_skin = -1.0,
+            |e| e.max_penetration_correction = Some(0.0),
+            |e| e.visibility_radius = Some(-5.0),
+            |e| e.continuous_threshold = Some(0.0),
+            |e| e.field_restitution = Some(1.5),
+            |e| e.field_restitution = Some(-0.1),
+            |e| e.mass = Some(0.0),
+            |e| e.throw_impulse = Some(-2.0),
+            |e| {
+                e.interactable = Some(Interactable {
+                    range: -1.0,
+                    action: "open".to_string(),
+                })
+            },
+        ];
+        for (i, make_bad) in bad_saves.into_iter().enumerate() {
+            let mut entity = Entity::new().unwrap();
+            make_bad(&mut entity);
+            assert!(reloaded(&entity).is_err(), "bad save {}", i);
+        }
+    }
+
+    #[test]
+    fn sliding_along_abutting_solid_cells_doesnt_snag() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 20.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 0.0, 50.0, 20.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 20.0, 50.0, 10.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([50.0, 20.0, 50.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(100.0).unwrap();
+        // Resting on the floor, sunk in by a rounding error
+        entity.physbox = PhysBox::new([40.0, 16.0 + 1e-9, 6.0, 4.0]).unwrap();
+        entity.set_velocity([20.0, 0.0].into());
+
+        for _ in 0..10 {
+            let x = entity.physbox.pos().x;
+            entity.try_move(&field, 0.1).unwrap();
+            assert!(entity
+                .physbox
+                .pos()
+                .approx_eq(&[x + 2.0, 16.0].into(), 1e-6));
+        }
+    }
+
+    #[test]
+    fn set_max_penetration_correction_fails_without_positive() {
+        let mut entity = Entity::new().unwrap();
+
+        assert_eq!(
+            entity
+                .set_max_penetration_correction(Some(0.0))
+                .unwrap_err(),
+            PhysicsError::PenetrationCorrection
+        );
+        entity.set_max_penetration_correction(None).unwrap();
+    }
+
+    #[test]
+    fn deep_penetration_eased_out_without_overshooting() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 30.0, 100.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([30.0, 0.0, 20.0, 100.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 0.0, 50.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(100.0).unwrap();
+        entity.set_resolves_penetration(true);
+        entity.set_max_penetration_correction(Some(2.0)).unwrap();
+        // Spawned 6 units into the wall, trying to walk further in
+        entity.physbox = PhysBox::new([32.0, 40.0, 4.0, 4.0]).unwrap();
+        entity.set_velocity([50.0, 0.0].into());
+
+        let mut xs = Vec::new();
+        for _ in 0..6 {
+            entity.try_move(&field, 0.1).unwrap();
+            xs.push(entity.physbox.pos().x);
+        }
+
+        assert_eq!(xs[..3], [30.0, 28.0, 26.0]);
+        // Back out on the near side, then blocked by the wall it came out of
+        assert!(xs[3..].iter().all(|x| (x - 26.0).abs() < 1e-9));
+        assert_eq!(entity.physbox.pos().y, 40.0);
+    }
+
+    #[test]
+    fn penetration_only_resolved_once_opted_in() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 30.0, 100.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([30.0, 0.0, 20.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+        let mut entity = Entity::new().unwrap();
+        entity.physbox = PhysBox::new([32.0, 40.0, 4.0, 4.0]).unwrap();
+
+        entity.try_move(&field, 0.1).unwrap();
+        assert_eq!(*entity.physbox.pos(), [32.0, 40.0].into());
+
+        entity.set_resolves_penetration(true);
+        entity.try_move(&field, 0.1).unwrap();
+        assert_eq!(*entity.physbox.pos(), [26.0, 40.0].into());
+    }
+
+    #[test]
+    fn collides_with_cells_at_negative_coordinates() {
+        // Like the boundary walls of the example, just outside of the origin
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 50.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([-1.0, 0.0, 1.0, 50.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, -1.0, 50.0, 1.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(100.0).unwrap();
+        entity.physbox = PhysBox::new([5.0, 6.0, 4.0, 4.0]).unwrap();
+        entity.set_velocity([-20.0, -20.0].into());
+        for _ in 0..5 {
+            entity.try_move(&field, 0.1).unwrap();
+        }
+
+        assert!(entity.physbox.pos().approx_eq(&[0.0, 0.0].into(), 1e-9));
+    }
+
+    #[test]
+    fn moves_and_collides_entirely_in_negative_quadrant() {
+        let field = Field::new(vec![
+            Cell::new(
+                NO_SOLIDITY,
+                PhysBox::new([-100.0, -100.0, 100.0, 80.0]).unwrap(),
+            ),
+            Cell::new(SOLID, PhysBox::new([-100.0, -20.0, 100.0, 10.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([-110.0, -100.0, 10.0, 90.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(100.0).unwrap();
+        entity.physbox = PhysBox::new([-50.0, -50.0, 4.0, 4.0]).unwrap();
+
+        // Moving freely
+        entity.set_velocity([-20.0, -20.0].into());
+        entity.try_move(&field, 0.5).unwrap();
+        assert!(entity.physbox.pos().approx_eq(&[-60.0, -60.0].into(), 1e-9));
+
+        // Then into the floor and the wall
+        entity.set_velocity([-20.0, 20.0].into());
+        for _ in 0..40 {
+            entity.try_move(&field, 0.1).unwrap();
+        }
+        assert!(entity.physbox.left_x().approx_eq(&-100.0, 1e-9));
+        assert!(entity.physbox.bottom_y().approx_eq(&-20.0, 1e-9));
+    }
+
+    #[test]
+    fn camera_constrained_entity_bounces_off_left_edge() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([-100.0, -100.0, 300.0, 300.0]).unwrap(),
+        )])
+        .unwrap();
+        let mut view = PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(100.0).unwrap();
+        entity.set_camera_constraint(Some(CameraConstraint::Bounce));
+        entity.physbox = PhysBox::new([2.0, 10.0, 4.0, 4.0]).unwrap();
+        entity.set_velocity([-20.0, 5.0].into());
+
+        entity.try_move(&field, 0.2).unwrap();
+        entity.constrain_to_view(&view);
+        assert_eq!(entity.physbox.pos(), &[0.0, 11.0].into());
+        assert_eq!(entity.get_velocity(), [20.0, 5.0].into());
+
+        entity.try_move(&field, 0.2).unwrap();
+        assert_eq!(entity.physbox.pos(), &[4.0, 12.0].into());
+
+        // The view scrolling past the entity carries it along without flipping it back around
+        view.set_pos([10.0, 0.0].into());
+        entity.constrain_to_view(&view);
+        assert_eq!(entity.physbox.left_x(), 10.0);
+        assert_eq!(entity.get_velocity(), [20.0, 5.0].into());
+    }
+
+    #[test]
+    fn cell_solid_only_to_projectile_layer() {
+        const PLAYER_SOLID: CollisionLayers = CollisionLayers::layer(0);
+        const PROJECTILE_SOLID: CollisionLayers = CollisionLayers::layer(2);
+
+        let mut force_field = Cell::new(SOLID, PhysBox::new([30.0, 0.0, 10.0, 50.0]).unwrap());
+        force_field.set_solid_to(PROJECTILE_SOLID);
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 30.0, 50.0]).unwrap()),
+            force_field,
+            Cell::new(NO_SOLIDITY, PhysBox::new([40.0, 0.0, 30.0, 50.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let moved_right = |layers: CollisionLayers| {
+            let mut entity = Entity::new().unwrap();
+            entity.set_max_velocity(100.0).unwrap();
+            entity.set_collision_layers(layers);
+            entity.physbox = PhysBox::new([20.0, 10.0, 4.0, 4.0]).unwrap();
+            entity.set_velocity([20.0, 0.0].into());
+            for _ in 0..10 {
+                entity.try_move(&field, 0.2).unwrap();
+            }
+            entity.physbox.right_x()
+        };
+
+        // Projectiles are stopped, players walk straight through
+        assert_eq!(moved_right(PROJECTILE_SOLID), 30.0);
+        assert!(moved_right(PLAYER_SOLID) > 40.0);
+        // The default layers are solid to everything
+        assert_eq!(moved_right(CollisionLayers::default()), 30.0);
+    }
+
+    #[test]
+    fn step_events_scale_with_speed() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let steps_in_one_second = |speed: f64| {
+            let mut entity = Entity::new().unwrap();
+            entity.set_max_velocity(100.0).unwrap();
+            entity.set_step_emitter(Some(DistanceEmitter::new(5.0)));
+            entity.set_velocity([speed, 0.0].into());
+
+            let mut steps = 0;
+            for _ in 0..8 {
+                entity.try_move(&field, 0.125).unwrap();
+                steps += entity.take_step_events();
+            }
+            steps
+        };
+
+        assert_eq!(steps_in_one_second(10.0), 2);
+        assert_eq!(steps_in_one_second(20.0), 4);
+        assert_eq!(steps_in_one_second(0.0), 0);
+    }
+
+    fn platform_field() -> Field {
+        Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 20.0]).unwrap()),
+            Cell::new(!ENTER_TOP, PhysBox::new([0.0, 20.0, 50.0, 5.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 25.0, 50.0, 50.0]).unwrap()),
+        ])
+        .unwrap()
+    }
+
+    fn standing_on_platform() -> Entity {
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(100.0).unwrap();
+        entity.physbox = PhysBox::new([10.0, 16.0, 4.0, 4.0]).unwrap();
+        entity.set_velocity([0.0, 20.0].into());
+        entity
+    }
+
+    #[test]
+    fn drop_through_falls_through_one_way_platform() {
+        let field = platform_field();
+
+        let mut entity = standing_on_platform();
+        for _ in 0..5 {
+            entity.try_move(&field, 0.1).unwrap();
+        }
+        assert_eq!(entity.physbox.pos(), &[10.0, 16.0].into());
+
+        entity.drop_through_one_way(0.2);
+        for _ in 0..5 {
+            entity.try_move(&field, 0.1).unwrap();
+        }
+        assert!(entity.physbox.top_y() >= 25.0);
+
+        // Clear of the platform so it's solid again
+        entity.try_move(&field, 0.1).unwrap();
+        assert!(!entity.is_dropping_through());
+    }
+
+    #[test]
+    fn dropping_through_platform_still_stops_at_wall_beside_it() {
+        let wall = PhysBox::new([50.0, 0.0, 10.0, 75.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 20.0]).unwrap()),
+            Cell::new(!ENTER_TOP, PhysBox::new([0.0, 20.0, 50.0, 5.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 25.0, 50.0, 50.0]).unwrap()),
+            Cell::new(SOLID, wall),
+        ])
+        .unwrap();
+
+        let mut entity = standing_on_platform();
+        entity.physbox.set_pos([44.0, 19.0].into());
+        entity.set_velocity([20.0, 20.0].into());
+        entity.drop_through_one_way(0.3);
+        for _ in 0..3 {
+            entity.try_move(&field, 0.1).unwrap();
+            assert!(entity.physbox.right_x() <= wall.left_x());
+        }
+        assert!(entity.physbox.top_y() > 20.0);
+    }
+
+    #[test]
+    fn drop_through_stays_active_until_clear_of_platform() {
+        let field = platform_field();
+
+        let mut entity = standing_on_platform();
+        entity.drop_through_one_way(0.1);
+        entity.try_move(&field, 0.1).unwrap();
+        entity.try_move(&field, 0.1).unwrap();
+
+        // Time is up, but it's still inside of the platform
+        assert!(entity
+            .physbox
+            .has_overlap(&PhysBox::new([0.0, 20.0, 50.0, 5.0]).unwrap()));
+        assert!(entity.is_dropping_through());
+        entity.try_move(&field, 0.1).unwrap();
+        assert!(entity.physbox.top_y() > 20.0);
+    }
+
+    #[test]
+    fn set_continuous_threshold_fails_without_positive() {
+        let mut entity = Entity::new().unwrap();
+
+        assert_eq!(
+            entity.set_continuous_threshold(Some(0.0)),
+            Err(PhysicsError::ContinuousThreshold)
+        );
+        assert_eq!(entity.set_continuous_threshold(Some(2.0)), Ok(()));
+        assert_eq!(entity.set_continuous_threshold(None), Ok(()));
+    }
+
+    #[test]
+    fn slow_moves_resolve_discretely_and_fast_moves_are_swept() {
+        let wall = PhysBox::new([50.0, 0.0, 1.0, 50.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 50.0]).unwrap()),
+            Cell::new(SOLID, wall),
+            Cell::new(NO_SOLIDITY, PhysBox::new([51.0, 0.0, 49.0, 50.0]).unwrap()),
+        ])
+        .unwrap();
+        let moving_entity = |x, speed| {
+            let mut entity = Entity::new().unwrap();
+            entity.physbox = PhysBox::new([x, 10.0, 4.0, 4.0]).unwrap();
+            entity.set_max_velocity(100.0).unwrap();
+            entity.set_velocity([speed, 0.0].into());
+            entity.set_continuous_threshold(Some(20.0)).unwrap();
+            entity
+        };
+
+        let mut slow = moving_entity(40.0, 10.0);
+        slow.try_move(&field, 1.0).unwrap();
+        assert_eq!(
+            slow.collision_path_counts(),
+            CollisionPathCounts {
+                discrete: 1,
+                swept: 0
+            }
+        );
+        assert_eq!(slow.physbox.right_x(), 50.0);
+        assert!(!slow.physbox.has_overlap(&wall));
+
+        let mut fast = moving_entity(10.0, 60.0);
+        fast.try_move(&field, 1.0).unwrap();
+        assert_eq!(
+            fast.collision_path_counts(),
+            CollisionPathCounts {
+                discrete: 0,
+                swept: 1
+            }
+        );
+        assert_eq!(fast.physbox.right_x(), 50.0);
+        assert!(!fast.physbox.has_overlap(&wall));
+    }
+
+    #[test]
+    fn fast_projectiles_hit_thin_boundary_cells_on_the_near_face() {
+        let boundary = PhysBox::new([100.0, 0.0, 1.0, 50.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap()),
+            Cell::new(SOLID, boundary),
+        ])
+        .unwrap();
+        let dt = 1.0 / 60.0;
+        for speed in [300.0, 1200.0, 6000.0] {
+            for start in [10.0, 33.3, 71.9] {
+                let mut projectile = Entity::new().unwrap();
+                projectile.physbox = PhysBox::new([start, 20.0, 2.0, 2.0]).unwrap();
+                projectile.set_max_velocity(speed).unwrap();
+                projectile.set_velocity([speed, 0.0].into());
+                projectile.set_projectile(true);
+
+                // Reported on the same tick that it reaches the wall, not a tick later
+                let ticks_to_wall = ((98.0 - start) / (speed * dt)).ceil() as usize;
+                for _ in 1..ticks_to_wall {
+                    projectile.try_move(&field, dt).unwrap();
+                    assert!(projectile.take_wall_impacts().is_empty());
+                }
+                projectile.try_move(&field, dt).unwrap();
+                let impacts = projectile.take_wall_impacts();
+                assert_eq!(impacts.len(), 1, "speed {} from {}", speed, start);
+                assert_eq!(impacts[0].point, [100.0, 21.0].into());
+                assert_eq!(impacts[0].normal, [-1.0, 0.0]);
+                assert_eq!(projectile.physbox.right_x(), 100.0);
+                assert!(!projectile.physbox.has_overlap(&boundary));
+                assert!(projectile.is_at_rest());
+            }
+        }
+    }
+
+    #[test]
+    fn projectile_hitting_floor_flush_with_field_edge_reports_impact() {
+        let floor = PhysBox::new([0.0, 100.0, 100.0, 10.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap()),
+            Cell::new(SOLID, floor),
+        ])
+        .unwrap();
+        let mut projectile = Entity::new().unwrap();
+        projectile.physbox = PhysBox::new([96.0, 80.0, 4.0, 4.0]).unwrap();
+        projectile.set_max_velocity(1200.0).unwrap();
+        projectile.set_velocity([0.0, 1200.0].into());
+        projectile.set_projectile(true);
+
+        projectile.try_move(&field, 1.0 / 60.0).unwrap();
+
+        let impacts = projectile.take_wall_impacts();
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].point, [98.0, 100.0].into());
+        assert_eq!(impacts[0].normal, [0.0, -1.0]);
+        assert_eq!(projectile.physbox.bottom_y(), 100.0);
+        assert!(!projectile.physbox.has_overlap(&floor));
+    }
+
+    #[test]
+    fn set_field_restitution_fails_outside_unit_range() {
+        let mut entity = Entity::new().unwrap();
+
+        assert_eq!(
+            entity.set_field_restitution(Some(1.5)),
+            Err(PhysicsError::Restitution)
+        );
+        assert_eq!(
+            entity.set_field_restitution(Some(-0.1)),
+            Err(PhysicsError::Restitution)
+        );
+        assert_eq!(entity.set_field_restitution(Some(0.7)), Ok(()));
+    }
+
+    #[test]
+    fn restitution_bounces_decay_to_rest() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 40.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 40.0, 50.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+        let mut grenade = Entity::new().unwrap();
+        grenade.physbox = PhysBox::new([10.0, 34.0, 2.0, 2.0]).unwrap();
+        grenade.set_max_velocity(100.0).unwrap();
+        grenade.set_field_restitution(Some(0.7)).unwrap();
+
+        grenade.set_velocity([0.0, 20.0].into());
+        grenade.try_move(&field, 0.5).unwrap();
+        assert_eq!(grenade.physbox.bottom_y(), 40.0);
+        assert!((grenade.get_velocity().y + 14.0).abs() < EPSILON);
+
+        // Thrown upwards under gravity, each bounce comes back up slower than the last
+        let mut pending_effects: Vec<crate::effect::PendingEffect<()>> = Vec::new();
+        let args = GameTickArgs {
+            field: &field,
+            pending_effects: &mut pending_effects,
+            dt: 0.05,
+        };
+        grenade.set_gravity(Some(100.0));
+        grenade.launch(30.0);
+        let mut rebounds = vec![];
+        for _ in 0..200 {
+            let before = grenade.get_velocity().y;
+            grenade.game_tick(&args).unwrap();
+            if before > 0.0 && grenade.get_velocity().y < 0.0 {
+                let impact = before + 100.0 * 0.05;
+                assert!((grenade.get_velocity().y + impact * 0.7).abs() < EPSILON);
+                rebounds.push(-grenade.get_velocity().y);
+            }
+        }
+        assert!(rebounds.len() >= 3);
+        assert!(rebounds.windows(2).all(|w| w[1] < w[0]));
+        assert!(!grenade.is_airborne());
+        assert!(grenade.is_at_rest());
+        assert_eq!(grenade.physbox.bottom_y(), 40.0);
+    }
+
+    #[test]
+    fn shield_absorbs_damage_first_and_regenerates_after_delay() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let mut pending_effects: Vec<crate::effect::PendingEffect<()>> = Vec::new();
+        let args = GameTickArgs {
+            field: &field,
+            pending_effects: &mut pending_effects,
+            dt: 0.5,
+        };
+        let mut entity = Entity::new().unwrap();
+        entity.set_shield(Some(Shield::new(5.0, 2.0, 1.0).unwrap()));
+        let shield = |entity: &Entity| entity.get_shield().unwrap().current();
+
+        entity.take_damage(3.0);
+        assert_eq!((shield(&entity), entity.health.current()), (2.0, 10.0));
+        // Breaking through the shield carries the rest over to health
+        entity.take_damage(4.0);
+        assert_eq!((shield(&entity), entity.health.current()), (0.0, 8.0));
+
+        entity.game_tick(&args).unwrap();
+        assert_eq!(shield(&entity), 0.0);
+        // Another hit halfway through the delay restarts it
+        entity.take_damage(1.0);
+        assert_eq!(entity.health.current(), 7.0);
+        entity.game_tick(&args).unwrap();
+        entity.game_tick(&args).unwrap();
+        assert_eq!(shield(&entity), 0.0);
+
+        entity.game_tick(&args).unwrap();
+        assert_eq!(shield(&entity), 1.0);
+        for _ in 0..4 {
+            entity.game_tick(&args).unwrap();
+        }
+        assert_eq!(shield(&entity), 5.0);
+        // Health doesn't regenerate along with it
+        assert_eq!(entity.health.current(), 7.0);
+        assert_eq!(Shield::new(0.0, 1.0, 1.0), Err(PhysicsError::Shield));
+    }
+
+    #[test]
+    fn dash_moves_its_distance_with_i_frames_and_cooldown() {
+        let wall = PhysBox::new([60.0, 0.0, 10.0, 100.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 60.0, 100.0]).unwrap()),
+            Cell::new(SOLID, wall),
+        ])
+        .unwrap();
+        let mut pending_effects: Vec<crate::effect::PendingEffect<()>> = Vec::new();
+        let args = GameTickArgs {
+            field: &field,
+            pending_effects: &mut pending_effects,
+            dt: 0.05,
+        };
+        let mut entity = Entity::new().unwrap();
+        entity.physbox = PhysBox::new([10.0, 10.0, 4.0, 4.0]).unwrap();
+        entity.set_max_velocity(10.0).unwrap();
+        assert!(!entity.try_dash([1.0, 0.0]));
+        entity.set_dash(Some(Dash::new(20.0, 0.2, 1.0).unwrap()));
+        assert!(!entity.try_dash([0.0, 0.0]));
+
+        entity.set_velocity([0.0, 5.0].into());
+        assert!(entity.try_dash([1.0, 0.0]));
+        for _ in 0..4 {
+            assert!(entity.is_dashing());
+            assert!(entity.is_invulnerable());
+            assert!(!entity.take_damage(1.0));
+            entity.game_tick(&args).unwrap();
+        }
+        assert!(entity
+            .physbox
+            .pos()
+            .approx_eq(&[30.0, 10.0].into(), EPSILON));
+        assert!(!entity.is_dashing());
+        assert!(!entity.is_invulnerable());
+        assert_eq!(entity.get_velocity(), [0.0, 5.0].into());
+        assert!(entity.take_damage(1.0));
+        assert_eq!(entity.health.current(), 9.0);
+
+        // The cooldown started with the dash and has 0.8s left
+        entity.set_velocity([0.0, 0.0].into());
+        assert!(!entity.try_dash([1.0, 0.0]));
+        for _ in 0..16 {
+            entity.game_tick(&args).unwrap();
+        }
+        // Stops early against the wall
+        entity.physbox.set_pos([46.0, 10.0].into());
+        assert!(entity.try_dash([1.0, 0.0]));
+        for _ in 0..3 {
+            entity.game_tick(&args).unwrap();
+        }
+        assert_eq!(entity.physbox.right_x(), wall.left_x());
+        assert!(!entity.is_dashing());
+    }
+
+    #[test]
+    fn rooted_entity_acts_and_silenced_entity_moves() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let mut pending_effects: Vec<crate::effect::PendingEffect<()>> = Vec::new();
+        let args = GameTickArgs {
+            field: &field,
+            pending_effects: &mut pending_effects,
+            dt: 0.5,
+        };
+        let moving_entity = || {
+            let mut entity = Entity::new().unwrap();
+            entity.physbox = PhysBox::new([10.0, 10.0, 4.0, 4.0]).unwrap();
+            entity.set_max_velocity(10.0).unwrap();
+            entity.set_velocity([4.0, 0.0].into());
+            entity
+        };
+
+        let mut rooted = moving_entity();
+        rooted.apply_crowd_control(CrowdControl::Root, 0.75);
+        rooted.game_tick(&args).unwrap();
+        assert_eq!(rooted.physbox.pos().x, 10.0);
+        assert!(rooted.can_act());
+        assert!(rooted.can_use_abilities());
+        // Moves again once the root wears off
+        rooted.game_tick(&args).unwrap();
+        assert_eq!(rooted.physbox.pos().x, 10.0);
+        rooted.game_tick(&args).unwrap();
+        assert_eq!(rooted.physbox.pos().x, 12.0);
+
+        let mut silenced = moving_entity();
+        silenced.apply_crowd_control(CrowdControl::Silence, 1.0);
+        silenced.game_tick(&args).unwrap();
+        assert_eq!(silenced.physbox.pos().x, 12.0);
+        assert!(silenced.can_act());
+        assert!(!silenced.can_use_abilities());
+
+        let mut stunned = moving_entity();
+        stunned.apply_crowd_control(CrowdControl::Stun, 1.0);
+        stunned.game_tick(&args).unwrap();
+        assert_eq!(stunned.physbox.pos().x, 10.0);
+        assert!(!stunned.can_act());
+    }
 }
@@ -119,23 +119,122 @@ pub trait RegisteredDebugEntity: RegisteredEntity {
     ) -> RenderResult<()>;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// An identifier assigned to an entity when it's registered.
+/// Ids are assigned in ascending order and are never reused by the same registry
+pub struct EntityId(pub u64);
+
 /// The registry of all entities that are active in a scene
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(from = "SavedRegistry<E>")]
 pub struct EntityRegistry<E> {
     inner: Vec<E>,
+    /// The id of each entity in `inner`, always in the same order
+    ids: Vec<EntityId>,
+    next_id: u64,
+}
+
+#[derive(Deserialize)]
+/// A registry as it's loaded, which may be from before entities had ids
+struct SavedRegistry<E> {
+    inner: Vec<E>,
+    #[serde(default)]
+    ids: Vec<EntityId>,
+    #[serde(default)]
+    next_id: u64,
+}
+
+impl<E> From<SavedRegistry<E>> for EntityRegistry<E> {
+    fn from(saved: SavedRegistry<E>) -> Self {
+        let SavedRegistry {
+            inner,
+            mut ids,
+            mut next_id,
+        } = saved;
+        // Saves without ids get them in the order the entities were saved in
+        if ids.len() != inner.len() {
+            ids = (0..inner.len() as u64).map(EntityId).collect();
+        }
+        if let Some(last) = ids.last() {
+            next_id = next_id.max(last.0 + 1);
+        }
+        Self {
+            inner,
+            ids,
+            next_id,
+        }
+    }
 }
 
 impl<E> Default for EntityRegistry<E> {
     fn default() -> Self {
-        Self { inner: Vec::new() }
+        Self {
+            inner: Vec::new(),
+            ids: Vec::new(),
+            next_id: 0,
+        }
     }
 }
 
 impl<E: RegisteredEntity> EntityRegistry<E> {
-    /// Attempts to register a new entity to the scene
-    pub fn register(&mut self, to_register: E) -> ScarabResult<()> {
+    /// Attempts to register a new entity to the scene, returning its new id
+    pub fn register(&mut self, to_register: E) -> ScarabResult<EntityId> {
+        let id = EntityId(self.next_id);
+        self.next_id += 1;
         self.inner.push(to_register);
-        Ok(())
+        self.ids.push(id);
+        Ok(id)
+    }
+
+    /// Registers each of the entities, returning their ids in the same order
+    pub fn register_all<I: IntoIterator<Item = E>>(
+        &mut self,
+        to_register: I,
+    ) -> ScarabResult<Vec<EntityId>> {
+        to_register
+            .into_iter()
+            .map(|entity| self.register(entity))
+            .collect()
+    }
+
+    /// Removes and returns every entity for which `should_remove` is true, in registration order
+    pub fn remove_where<F: FnMut(EntityId, &E) -> bool>(&mut self, mut should_remove: F) -> Vec<E> {
+        let mut removed = Vec::new();
+        let mut i = 0;
+        while i < self.inner.len() {
+            if should_remove(self.ids[i], &self.inner[i]) {
+                self.ids.remove(i);
+                removed.push(self.inner.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        removed
+    }
+
+    /// The id of the registered entity at the given index if it exists
+    pub fn id_of(&self, i: usize) -> Option<EntityId> {
+        self.ids.get(i).copied()
+    }
+
+    /// The current index of the registered entity with the given id if it exists
+    pub fn index_of(&self, id: EntityId) -> Option<usize> {
+        self.ids.binary_search(&id).ok()
+    }
+
+    /// Gets a reference to the registered entity with the given id if it exists
+    pub fn get_by_id(&self, id: EntityId) -> Option<&E> {
+        self.index_of(id).and_then(|i| self.inner.get(i))
+    }
+
+    /// Gets a mutable reference to the registered entity with the given id if it exists
+    pub fn get_by_id_mut(&mut self, id: EntityId) -> Option<&mut E> {
+        self.index_of(id).and_then(|i| self.inner.get_mut(i))
+    }
+
+    /// Iterates across the registered entities along with their ids
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (EntityId, &E)> {
+        self.ids.iter().copied().zip(self.inner.iter())
     }
 
     /// Gets a reference to the registered player
@@ -200,3 +299,98 @@ impl<'a, E> IntoIterator for &'a mut EntityRegistry<E> {
         self.inner.iter_mut()
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    /// A minimal registered entity for testing the scene and registry
+    pub(crate) struct TestEntity(pub Entity);
+
+    impl TestEntity {
+        /// Makes a test entity with the given box
+        pub(crate) fn with_box(physbox: [f64; 4]) -> Self {
+            let mut entity = Entity::new().unwrap();
+            *entity.get_box_mut() = PhysBox::new(physbox).unwrap();
+            Self(entity)
+        }
+    }
+
+    impl RegisteredEntity for TestEntity {
+        type Player = Entity;
+
+        fn inner_entity(&self) -> &Entity {
+            &self.0
+        }
+
+        fn inner_entity_mut(&mut self) -> &mut Entity {
+            &mut self.0
+        }
+
+        fn maybe_player(&self) -> Option<&Self::Player> {
+            None
+        }
+
+        fn maybe_player_mut(&mut self) -> Option<&mut Self::Player> {
+            None
+        }
+
+        fn render(
+            &mut self,
+            _args: &RenderArgs,
+            _camera: &Camera,
+            _ctx: Context,
+            _texture_registry: &TextureRegistry,
+            _gl: &mut GlGraphics,
+        ) -> RenderResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn ids_are_ascending_and_not_reused() {
+        let mut registry = EntityRegistry::default();
+        let first = registry
+            .register(TestEntity::with_box([0.0, 0.0, 1.0, 1.0]))
+            .unwrap();
+        let second = registry
+            .register(TestEntity::with_box([0.0, 0.0, 1.0, 1.0]))
+            .unwrap();
+        assert!(first < second);
+
+        registry.remove_where(|id, _| id == second);
+        let third = registry
+            .register(TestEntity::with_box([0.0, 0.0, 1.0, 1.0]))
+            .unwrap();
+        assert!(second < third);
+        assert_eq!(registry.index_of(first), Some(0));
+        assert_eq!(registry.index_of(second), None);
+        assert_eq!(registry.id_of(1), Some(third));
+    }
+
+    #[test]
+    fn registry_saved_without_ids_gets_them_when_loaded() {
+        #[derive(Serialize)]
+        struct OldRegistry {
+            inner: Vec<TestEntity>,
+        }
+        let old = OldRegistry {
+            inner: vec![
+                TestEntity::with_box([0.0, 0.0, 1.0, 1.0]),
+                TestEntity::with_box([5.0, 0.0, 1.0, 1.0]),
+            ],
+        };
+
+        let mut registry: EntityRegistry<TestEntity> =
+            rmp_serde::from_slice(&rmp_serde::to_vec_named(&old).unwrap()).unwrap();
+        assert_eq!(registry.id_of(0), Some(EntityId(0)));
+        assert_eq!(registry.id_of(1), Some(EntityId(1)));
+        let second = registry.get_by_id(EntityId(1)).unwrap();
+        assert_eq!(second.get_box().pos().x, 5.0);
+        let new = registry
+            .register(TestEntity::with_box([0.0, 0.0, 1.0, 1.0]))
+            .unwrap();
+        assert_eq!(new, EntityId(2));
+    }
+}
@@ -0,0 +1,130 @@
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+
+use crate::{PhysicsError, PhysicsResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SavedShield")]
+/// A layer of protection over an entity's health that absorbs damage first and regenerates
+/// once the entity hasn't been damaged for a while (see [Entity::set_shield](super::Entity::set_shield))
+pub struct Shield {
+    current: Scalar,
+    max: Scalar,
+    regen_rate: Scalar,
+    regen_delay: f64,
+    /// The time since the entity was last damaged
+    #[serde(default)]
+    since_damage: f64,
+}
+
+#[derive(Deserialize)]
+/// A shield as it's loaded, checked the same way as [Shield::new] and with no more than its max
+struct SavedShield {
+    current: Scalar,
+    max: Scalar,
+    regen_rate: Scalar,
+    regen_delay: f64,
+    #[serde(default)]
+    since_damage: f64,
+}
+
+impl TryFrom<SavedShield> for Shield {
+    type Error = PhysicsError;
+
+    fn try_from(saved: SavedShield) -> PhysicsResult<Self> {
+        let shield = Self::new(saved.max, saved.regen_rate, saved.regen_delay)?;
+        if !(0.0..=shield.max).contains(&saved.current) {
+            return Err(PhysicsError::Shield);
+        }
+        Ok(Self {
+            current: saved.current,
+            since_damage: saved.since_damage,
+            ..shield
+        })
+    }
+}
+
+impl Shield {
+    /// A full shield of `max` that regenerates `regen_rate` per second, starting `regen_delay`
+    /// seconds after the last damage. The max must be positive and the others non-negative
+    pub fn new(max: Scalar, regen_rate: Scalar, regen_delay: f64) -> PhysicsResult<Self> {
+        if max <= 0.0 || regen_rate < 0.0 || regen_delay < 0.0 {
+            return Err(PhysicsError::Shield);
+        }
+        Ok(Self {
+            current: max,
+            max,
+            regen_rate,
+            regen_delay,
+            since_damage: regen_delay,
+        })
+    }
+
+    /// The shield remaining
+    pub fn current(&self) -> Scalar {
+        self.current
+    }
+
+    /// The shield when it's full
+    pub fn max(&self) -> Scalar {
+        self.max
+    }
+
+    /// The shield remaining as a fraction of the max
+    pub fn fraction(&self) -> Scalar {
+        self.current / self.max
+    }
+
+    /// Whether the shield has been broken through
+    pub fn is_depleted(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Absorbs as much of `amount` damage as the shield has left, returning the rest to be dealt
+    /// to health. Any damage restarts the wait before regenerating, even once it's depleted
+    pub fn absorb(&mut self, amount: Scalar) -> Scalar {
+        if amount <= 0.0 {
+            return amount;
+        }
+        self.since_damage = 0.0;
+        let absorbed = amount.min(self.current);
+        self.current -= absorbed;
+        amount - absorbed
+    }
+
+    /// Regenerates for however much of the `dt` seconds is past the delay since the last damage
+    pub fn tick(&mut self, dt: f64) {
+        let waiting = (self.regen_delay - self.since_damage).max(0.0);
+        self.since_damage += dt;
+        let regen_time = (dt - waiting).max(0.0);
+        self.current = (self.current + self.regen_rate * regen_time).min(self.max);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn loading_a_shield_checks_it_like_new() {
+        let mut shield = Shield::new(5.0, 2.0, 1.0).unwrap();
+        shield.absorb(3.0);
+        let saved = rmp_serde::to_vec(&shield).unwrap();
+        assert_eq!(rmp_serde::from_slice::<Shield>(&saved).unwrap(), shield);
+
+        for bad in [
+            Shield { max: 0.0, ..shield },
+            Shield {
+                regen_rate: -1.0,
+                ..shield
+            },
+            Shield {
+                current: 6.0,
+                ..shield
+            },
+        ] {
+            let saved = rmp_serde::to_vec(&bad).unwrap();
+            assert!(rmp_serde::from_slice::<Shield>(&saved).is_err());
+        }
+    }
+}
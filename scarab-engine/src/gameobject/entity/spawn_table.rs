@@ -0,0 +1,167 @@
+use std::{collections::HashMap, fmt::Display, fs::File, path::Path};
+
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+
+use super::{loot::DropTable, Entity};
+use crate::{
+    error::SpawnError,
+    types::{HasHealth, MaxHealthPolicy},
+    PhysicsResult, ScarabResult,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// The stats a [SpawnDefinition] gives the entities built from its prefab.
+/// Anything left as `None` keeps the prefab's own value
+pub struct StatOverrides {
+    /// The maximum health, which the entity spawns with all of
+    #[serde(default)]
+    pub max_health: Option<Scalar>,
+    /// The maximum velocity
+    #[serde(default)]
+    pub max_velocity: Option<Scalar>,
+    /// The mass
+    #[serde(default)]
+    pub mass: Option<Scalar>,
+    /// Replaces the prefab's drop table
+    #[serde(default)]
+    pub drop_table: Option<DropTable>,
+    /// Labels added to the prefab's own
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl StatOverrides {
+    /// Gives `entity` the overridden stats, failing if any of them are invalid
+    pub fn apply(&self, entity: &mut Entity) -> PhysicsResult<()> {
+        if let Some(max_health) = self.max_health {
+            entity
+                .get_health_mut()
+                .set_max(max_health, MaxHealthPolicy::HealToFull);
+        }
+        if let Some(max_velocity) = self.max_velocity {
+            entity.set_max_velocity(max_velocity)?;
+        }
+        if let Some(mass) = self.mass {
+            entity.set_mass(Some(mass))?;
+        }
+        if let Some(drop_table) = self.drop_table.as_ref() {
+            entity.set_drop_table(Some(drop_table.clone()));
+        }
+        for tag in &self.tags {
+            entity.add_tag(tag.as_str());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A variant of an entity built from a prefab, i.e. an armored version of a basic enemy
+pub struct SpawnDefinition {
+    /// The [prefab](crate::scene::Scene::register_prefab) the variant is built from
+    pub prefab: String,
+    /// The stats the variant has instead of the prefab's
+    #[serde(default)]
+    pub stats: StatOverrides,
+    /// The game's key for the AI that the variant is given, if any
+    #[serde(default)]
+    pub behavior: Option<String>,
+    /// The game's key for the animations that the variant is drawn with, if any
+    #[serde(default)]
+    pub animation: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+/// Spawn definitions keyed by the name they're spawned with, usually loaded from a data file
+/// so that designers can add variants without code
+/// (see [Scene::register_spawn_definitions](crate::scene::Scene::register_spawn_definitions))
+pub struct SpawnDefinitions {
+    definitions: HashMap<String, SpawnDefinition>,
+}
+
+impl SpawnDefinitions {
+    /// No definitions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the definitions from the file at `path`, parsing it with `parse` in whatever
+    /// format the game's data is in (i.e. `rmp_serde::from_read`)
+    pub fn load<P, F, Err>(path: P, parse: F) -> ScarabResult<Self>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(File) -> Result<Self, Err>,
+        Err: Display,
+    {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        parse(file).map_err(|e| SpawnError::Parse(path.to_path_buf(), e.to_string()).into())
+    }
+
+    /// Adds the definition `name`, replacing any definition that already had the name
+    pub fn insert<N: Into<String>>(&mut self, name: N, definition: SpawnDefinition) {
+        self.definitions.insert(name.into(), definition);
+    }
+
+    /// The definition `name`
+    pub fn get(&self, name: &str) -> Option<&SpawnDefinition> {
+        self.definitions.get(name)
+    }
+
+    /// The number of definitions
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    /// Whether there are no definitions
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Checks that every definition is built from a prefab for which `is_prefab` is true, and only
+    /// uses the `behaviors` and `animations` that the game has
+    pub fn validate<F: Fn(&str) -> bool>(
+        &self,
+        is_prefab: F,
+        behaviors: &[&str],
+        animations: &[&str],
+    ) -> Result<(), SpawnError> {
+        // Sorted so the same file always reports the same error first
+        let mut names: Vec<&String> = self.definitions.keys().collect();
+        names.sort();
+        for name in names {
+            let definition = &self.definitions[name];
+            if !is_prefab(&definition.prefab) {
+                return Err(SpawnError::UnknownPrefab(
+                    name.clone(),
+                    definition.prefab.clone(),
+                ));
+            }
+            if let Some(behavior) = unknown_key(definition.behavior.as_ref(), behaviors) {
+                return Err(SpawnError::UnknownBehavior(
+                    name.clone(),
+                    behavior.clone(),
+                    behaviors.join(", "),
+                ));
+            }
+            if let Some(animation) = unknown_key(definition.animation.as_ref(), animations) {
+                return Err(SpawnError::UnknownAnimation(
+                    name.clone(),
+                    animation.clone(),
+                    animations.join(", "),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves all of `other`'s definitions into these, replacing any with the same names
+    pub(crate) fn extend(&mut self, other: SpawnDefinitions) {
+        self.definitions.extend(other.definitions);
+    }
+}
+
+fn unknown_key<'a>(key: Option<&'a String>, known: &[&str]) -> Option<&'a String> {
+    key.filter(|key| !known.contains(&key.as_str()))
+}
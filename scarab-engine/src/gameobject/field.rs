@@ -1,17 +1,30 @@
 use core::slice::Iter;
 use std::fmt::Debug;
 
-use graphics::{types::Color, Context};
+use graphics::{
+    math::transform_pos,
+    types::{Color, Scalar, Vec2d},
+    Context, Graphics, BACK_END_MAX_VERTEX_COUNT,
+};
 use opengl_graphics::GlGraphics;
-use petgraph::{graph::NodeIndex, prelude::DiGraph, stable_graph::DefaultIx, visit::EdgeRef};
+use petgraph::{
+    algo::astar,
+    graph::NodeIndex,
+    prelude::DiGraph,
+    stable_graph::DefaultIx,
+    visit::{EdgeFiltered, EdgeRef},
+};
 use piston::RenderArgs;
-use serde::{Deserialize, Serialize};
-use shapes::Point;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use shapes::{Point, Size};
 
 use crate::{
     error::RenderResult,
     rendering::{registry::TextureRegistry, Camera, View},
-    types::{physbox::PhysBox, BoxEdge, Solidity, NO_SOLIDITY, SOLID},
+    types::{
+        physbox::PhysBox, ApproxEq, Axis, BoxEdge, CollisionLayers, Solidity, DEFAULT_EPSILON,
+        NO_SOLIDITY, SOLID,
+    },
     HasBox, HasBoxMut, HasSolidity, PhysicsError, PhysicsResult,
 };
 
@@ -20,6 +33,9 @@ use crate::{
 /// that edge is passable by solidity entering/exiting rules
 pub type FieldGraphInner = DiGraph<Cell, (BoxEdge, bool)>;
 
+/// Identifies a cell within its [Field]
+pub type CellId = NodeIndex<DefaultIx>;
+
 /// A field is a graph of rectangles ([cells](Cell)) that aids in movement within a scene
 ///
 /// The cells have a [solidity](Solidity) field which dictates
@@ -31,6 +47,12 @@ pub type FieldGraphInner = DiGraph<Cell, (BoxEdge, bool)>;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Field {
     graph: FieldGraphInner,
+    /// The grid used for converting between tile and world coordinates, if the field has one
+    #[serde(default)]
+    tile_grid: Option<TileGrid>,
+    /// Counts the changes to the field's cells, so cached renders know when to rebuild
+    #[serde(skip)]
+    revision: u64,
 }
 
 impl Field {
@@ -53,97 +75,190 @@ impl Field {
         // - ensure there are no gaps in between cells
         //     this would probably take a very long time unless I can come up with a clever alg
 
-        Ok(Self { graph })
+        Ok(Self {
+            graph,
+            tile_grid: None,
+            revision: 0,
+        })
     }
 
-    fn build_cells(graph: &mut FieldGraphInner) -> PhysicsResult<()> {
-        // Find the bordering cells along the given edge,
-        // and mark the appropriate graph edges
-        // c: the current cell
-        // test_pos: mut TileVec.
-        //     Must be initialized to `TileVec(c.pos.0 + off_x, c.pos.1 + off_y)`
-        //     where `off_x` is 0 when `direction` is 0 or c.size.0 otherwise and
-        //     `off_y` is 0 when `direction is 1 or c.size.1 otherwise.
-        //     This part is not pulled into the macro due to some complications
-        //     with typing because TileVec is unsigned.
-        // edge: A `BoxEdge` variant
-        fn cell_edges(
-            this_cell_idx: NodeIndex,
-            graph: &mut FieldGraphInner,
-            test_pos: Point,
-            edge: BoxEdge,
-        ) -> PhysicsResult<()> {
-            let mut test_pos = test_pos.clone();
-
-            let this_cell_far_axis = Field::cell_at_idx(graph, this_cell_idx)?
-                .physbox
-                .get_far_axis(edge.parallel_axis());
-
-            // We iterate in the direction is orthogonal to edge's axis
-            while edge.parallel_axis().component_of_point(&test_pos) < this_cell_far_axis {
-                // Find the cell at the current 'test_pos'. If it exists,
-                // add the edge it's on to neighbors along with wither or not the
-                // edge is typically passable (the corresponding edge can be entered
-                // or exited for each cell)
-                // Then set the new test pos to the far end of the neighbor
-                let new_normal_component = if let Some(cell_at_test_pos) =
-                    Field::cell_at_pos_internal(graph.node_weights(), test_pos)
-                {
-                    let new_normal_component =
-                        cell_at_test_pos.physbox.get_far_axis(edge.parallel_axis());
-                    let edge_is_passable = Field::cell_at_idx(graph, this_cell_idx)?
-                        .solidity
-                        .exit_edge(edge)
-                        && cell_at_test_pos.solidity.enter_edge(edge.opposite());
-
-                    // Rust gives a warning about derefing 'graph' in a call to graph
-                    // if we put it immediately in the function call
-                    let cell_at_test_pos_idx = cell_at_test_pos.i;
-                    graph.update_edge(
-                        this_cell_idx,
-                        cell_at_test_pos_idx,
-                        (edge, edge_is_passable),
-                    );
-                    new_normal_component
-                } else {
-                    edge.parallel_axis().component_of_point(&test_pos) + 1.0
-                };
-
-                test_pos = match edge {
-                    BoxEdge::Top | BoxEdge::Bottom => Point {
-                        x: new_normal_component,
-                        y: test_pos.y,
-                    },
-                    BoxEdge::Left | BoxEdge::Right => Point {
-                        x: test_pos.x,
-                        y: new_normal_component,
-                    },
-                };
+    /// Creates a field with a cell for every tile of a grid `width` tiles wide, with the top
+    /// left tile at `origin`. `solidities` has the solidity of each tile, row by row
+    pub fn from_grid(
+        origin: Point,
+        tile_size: Size,
+        width: usize,
+        solidities: &[Solidity],
+    ) -> PhysicsResult<Self> {
+        if solidities.len().checked_rem(width) != Some(0) {
+            return Err(PhysicsError::GridShape(solidities.len(), width));
+        }
+        let cells = solidities
+            .iter()
+            .enumerate()
+            .map(|(i, solidity)| {
+                let (x, y) = ((i % width) as Scalar, (i / width) as Scalar);
+                PhysBox::new([
+                    origin.x + x * tile_size.w,
+                    origin.y + y * tile_size.h,
+                    tile_size.w,
+                    tile_size.h,
+                ])
+                .map(|physbox| Cell::new(*solidity, physbox))
+            })
+            .collect::<PhysicsResult<Vec<Cell>>>()?;
+        Field::new(cells)
+    }
+
+    /// Converts a field made up of `tile_size` tiles into a run-length encoded grid of
+    /// solidities. Cells can cover several tiles, but must line up with the grid and leave
+    /// no tiles uncovered. Anything other than the cells' solidities isn't kept
+    pub fn to_rle_grid(&self, tile_size: Size) -> PhysicsResult<RleGrid> {
+        if !(tile_size.w > 0.0 && tile_size.h > 0.0) {
+            return Err(PhysicsError::PhysBoxSize);
+        }
+        let bounds = self.cells().fold(None, |bounds: Option<[Scalar; 4]>, c| {
+            let b = &c.physbox;
+            Some(match bounds {
+                Some([l, t, r, btm]) => [
+                    l.min(b.left_x()),
+                    t.min(b.top_y()),
+                    r.max(b.right_x()),
+                    btm.max(b.bottom_y()),
+                ],
+                None => [b.left_x(), b.top_y(), b.right_x(), b.bottom_y()],
+            })
+        });
+        let [left, top, right, bottom] = bounds.ok_or(PhysicsError::NotAGrid)?;
+        let origin: Point = [left, top].into();
+
+        // The number of whole tiles along `length`, if it's a whole number of them
+        let tiles = |length: Scalar, tile: Scalar| -> PhysicsResult<usize> {
+            let n = (length / tile).round();
+            if (n * tile - length).abs() > DEFAULT_EPSILON * length.abs().max(1.0) {
+                Err(PhysicsError::NotAGrid)
+            } else {
+                Ok(n as usize)
+            }
+        };
+        let width = tiles(right - left, tile_size.w)?;
+        let height = tiles(bottom - top, tile_size.h)?;
+
+        let mut solidities = vec![None; width * height];
+        for cell in self.cells() {
+            let b = &cell.physbox;
+            let x = tiles(b.left_x() - left, tile_size.w)?;
+            let y = tiles(b.top_y() - top, tile_size.h)?;
+            let w = tiles(b.size().w, tile_size.w)?;
+            let h = tiles(b.size().h, tile_size.h)?;
+            for row in y..y + h {
+                for tile in &mut solidities[row * width + x..row * width + x + w] {
+                    *tile = Some(cell.solidity);
+                }
             }
+        }
+        let solidities = solidities
+            .into_iter()
+            .collect::<Option<Vec<Solidity>>>()
+            .ok_or(PhysicsError::NotAGrid)?;
+
+        Ok(RleGrid::encode(origin, tile_size, width, &solidities))
+    }
+
+    /// Adds a cell to the field, connecting it to its neighbors. Returns its id
+    pub fn add_cell(&mut self, cell: Cell) -> PhysicsResult<CellId> {
+        let i = self.graph.add_node(cell);
+        if let Some(c) = self.graph.node_weight_mut(i) {
+            c.i = i;
+        }
+        self.rebuild_cells()?;
+        Ok(i)
+    }
 
-            Ok(())
+    /// Removes a cell from the field, returning it if it was on the field.
+    /// Removing a cell may change the id of the last cell to the removed cell's
+    pub fn remove_cell(&mut self, id: CellId) -> PhysicsResult<Option<Cell>> {
+        let removed = self.graph.remove_node(id);
+        if removed.is_some() {
+            // The last cell is moved into the removed one's place
+            if let Some(c) = self.graph.node_weight_mut(id) {
+                c.i = id;
+            }
+            self.rebuild_cells()?;
         }
+        Ok(removed)
+    }
+
+    /// Iterates over every cell in the field
+    pub fn cells(&self) -> impl Iterator<Item = &Cell> {
+        self.graph.node_weights()
+    }
 
-        // Initialize the neighbors and edges
-        for cell_idx in graph.node_indices() {
-            let physbox = Field::cell_at_idx(graph, cell_idx)?.physbox;
-            let mut test_pos;
+    /// Changes every time cells are added to or removed from the field
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn rebuild_cells(&mut self) -> PhysicsResult<()> {
+        self.revision += 1;
+        self.graph.clear_edges();
+        Field::build_cells(&mut self.graph)
+    }
 
-            // Along the top edge
-            test_pos = *physbox.pos() - [0.0, 1.0];
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Top)?;
+    /// Lays a grid of tiles over the field, letting it be addressed by tile coordinates
+    pub fn set_tile_grid(&mut self, tile_grid: Option<TileGrid>) {
+        self.tile_grid = tile_grid;
+    }
 
-            // Along the left edge
-            test_pos = *physbox.pos() - [1.0, 0.0];
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Left)?;
+    /// The grid of tiles laid over the field, if any
+    pub fn tile_grid(&self) -> Option<&TileGrid> {
+        self.tile_grid.as_ref()
+    }
 
-            // Along the bottom edge
-            test_pos = [physbox.left_x(), physbox.bottom_y()].into();
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Bottom)?;
+    fn build_cells(graph: &mut FieldGraphInner) -> PhysicsResult<()> {
+        // Cells are neighbors along an edge when the edges coincide and they overlap along it.
+        // Comparing the edges directly (rather than probing just past them) doesn't assume
+        // anything about where the origin is or how large the cells are, so thin cells
+        // and cells at negative coordinates get the same neighbors as any others
+        let cells = graph
+            .node_indices()
+            .map(|idx| Field::cell_at_idx(graph, idx).map(|cell| (idx, cell)))
+            .collect::<PhysicsResult<Vec<(NodeIndex, &Cell)>>>()?;
+        let mut neighbors = Vec::new();
+        for edge in BoxEdge::iter() {
+            let axis = edge.parallel_axis();
+            // The cells sorted by their opposite edge, so only the ones lining up with a cell's
+            // edge are compared against it
+            let mut facing: Vec<(Scalar, NodeIndex, &Cell)> = cells
+                .iter()
+                .map(|&(idx, cell)| (cell.physbox.get_edge(edge.opposite()), idx, cell))
+                .collect();
+            facing.sort_by(|(a, ..), (b, ..)| a.total_cmp(b));
+
+            for &(this_cell_idx, this_cell) in &cells {
+                let at = this_cell.physbox.get_edge(*edge);
+                let start =
+                    facing.partition_point(|(other_at, ..)| *other_at < at - DEFAULT_EPSILON);
+                for &(other_at, other_idx, other) in &facing[start..] {
+                    if !other_at.approx_eq(&at, DEFAULT_EPSILON) {
+                        break;
+                    }
+                    let touching = other_idx != this_cell_idx
+                        && this_cell.physbox.get_near_axis(axis) < other.physbox.get_far_axis(axis)
+                        && other.physbox.get_near_axis(axis) < this_cell.physbox.get_far_axis(axis);
+                    if touching {
+                        let edge_is_passable = this_cell.solidity.exit_edge(*edge)
+                            && other.solidity.enter_edge(edge.opposite());
+                        neighbors.push((this_cell_idx, other_idx, (*edge, edge_is_passable)));
+                    }
+                }
+            }
+        }
 
-            // Along the right edge
-            test_pos = [physbox.right_x(), physbox.top_y()].into();
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Right)?;
+        // Added in the order the cells were given, so the graph doesn't depend on the sorting
+        neighbors.sort_by_key(|&(this_cell_idx, other_idx, _)| (this_cell_idx, other_idx));
+        for (this_cell_idx, other_idx, weight) in neighbors {
+            graph.update_edge(this_cell_idx, other_idx, weight);
         }
 
         Ok(())
@@ -175,6 +290,245 @@ impl Field {
         Field::cell_at_pos_internal(self.graph.node_weights(), pos)
     }
 
+    /// Iterates over every cell that overlaps `physbox`
+    pub fn cells_overlapping_box<'a>(
+        &'a self,
+        physbox: &'a PhysBox,
+    ) -> impl Iterator<Item = &'a Cell> + 'a {
+        self.graph
+            .node_weights()
+            .filter(move |c| c.physbox.has_overlap(physbox))
+    }
+
+    /// Returns the cell containing the top left corner of the given tile.
+    /// None if the field has no tile grid or there's no cell there
+    pub fn cell_at_tile(&self, tile: (i32, i32)) -> Option<&Cell> {
+        let tile_box = self.tile_grid?.tile_to_world(tile);
+        self.cell_at_pos(*tile_box.pos())
+    }
+
+    /// Sweeps `physbox` along `displacement`, returning the earliest impact with the edge of any
+    /// cell that can't be entered from that edge. Cells the box already overlaps are ignored,
+    /// and only cells overlapping the area covered by the whole movement are checked
+    pub fn sweep_box(&self, physbox: &PhysBox, displacement: Vec2d) -> Option<SweepResult> {
+        self.sweep_box_blocked_by(physbox, displacement, |_| true)
+    }
+
+    /// The same as [Field::sweep_box], but only cells that `blocks` returns true for can be hit
+    pub fn sweep_box_blocked_by<F: Fn(&Cell) -> bool>(
+        &self,
+        physbox: &PhysBox,
+        displacement: Vec2d,
+        blocks: F,
+    ) -> Option<SweepResult> {
+        let [dx, dy] = displacement;
+        let swept_area = PhysBox::new([
+            physbox.left_x() + dx.min(0.0),
+            physbox.top_y() + dy.min(0.0),
+            physbox.size().w + dx.abs(),
+            physbox.size().h + dy.abs(),
+        ])
+        .ok()?;
+
+        let mut earliest: Option<SweepResult> = None;
+        for cell in self
+            .graph
+            .node_weights()
+            .filter(|c| c.physbox.has_overlap(&swept_area) && !c.physbox.has_overlap(physbox))
+        {
+            let x = sweep_axis(physbox, &cell.physbox, dx, Axis::X);
+            let y = sweep_axis(physbox, &cell.physbox, dy, Axis::Y);
+            let ((x_entry, x_exit), (y_entry, y_exit)) = match (x, y) {
+                (Some(x), Some(y)) => (x, y),
+                _ => continue,
+            };
+
+            let entry = x_entry.max(y_entry);
+            // Only touching on a corner or edge doesn't block movement
+            if entry >= x_exit.min(y_exit) || !(0.0..=1.0).contains(&entry) {
+                continue;
+            }
+
+            let edge = if x_entry > y_entry {
+                if dx > 0.0 {
+                    BoxEdge::Left
+                } else {
+                    BoxEdge::Right
+                }
+            } else if dy > 0.0 {
+                BoxEdge::Top
+            } else {
+                BoxEdge::Bottom
+            };
+            if cell.solidity.enter_edge(edge) || !blocks(cell) {
+                continue;
+            }
+
+            if earliest.as_ref().map(|e| entry < e.time).unwrap_or(true) {
+                earliest = Some(SweepResult {
+                    time: entry,
+                    edge,
+                    pos: *physbox.pos() + [dx * entry, dy * entry],
+                    cell: cell.i,
+                });
+            }
+        }
+
+        earliest
+    }
+
+    /// Whether nothing solid blocks the straight line from `from` to `to`.
+    /// Cells containing `from` don't block it, so looking out of a cell works as expected
+    pub fn has_line_of_sight(&self, from: Point, to: Point) -> bool {
+        const EYE_SIZE: Scalar = 1e-3;
+        let eye = match PhysBox::new([
+            from.x - EYE_SIZE / 2.0,
+            from.y - EYE_SIZE / 2.0,
+            EYE_SIZE,
+            EYE_SIZE,
+        ]) {
+            Ok(eye) => eye,
+            Err(_) => return false,
+        };
+        self.sweep_box(&eye, [to.x - from.x, to.y - from.y])
+            .map(|hit| hit.time >= 1.0)
+            .unwrap_or(true)
+    }
+
+    /// The shortest route through the field's cells for the center of a box of `size` on
+    /// `layers` from `from` to `to`, as the centers of the cells passed through, ending at `to`
+    /// itself. A cell is only passed through if the box fits centered on it, so gaps narrower
+    /// than the box are avoided. None if either point is off the field or there's no way through
+    pub fn find_path(
+        &self,
+        from: Point,
+        to: Point,
+        size: Size,
+        layers: CollisionLayers,
+    ) -> Option<Vec<Point>> {
+        let start = self.cell_at_pos(from)?.i;
+        let goal = self.cell_at_pos(to)?.i;
+        let distance = |a: Point, b: Point| f64::hypot(b.x - a.x, b.y - a.y);
+        let center = |i: NodeIndex| self.graph[i].physbox.center();
+
+        let walkable = EdgeFiltered::from_fn(&self.graph, |e| {
+            (e.weight().1 || !self.graph[e.target()].is_solid_to(layers))
+                && self.fits_in_cell(e.target(), size, layers)
+        });
+        let (_, cells) = astar(
+            &walkable,
+            start,
+            |i| i == goal,
+            |e| distance(center(e.source()), center(e.target())),
+            |i| distance(center(i), center(goal)),
+        )?;
+
+        let mut path: Vec<Point> = cells.into_iter().skip(1).map(center).collect();
+        path.pop();
+        path.push(to);
+        Some(path)
+    }
+
+    /// Whether a box of `size` centered on cell `i` is on the field without overlapping any
+    /// other cell solid to `layers`. The cell itself is left to the edges leading into it
+    fn fits_in_cell(&self, i: NodeIndex, size: Size, layers: CollisionLayers) -> bool {
+        let center = self.graph[i].physbox.center();
+        let candidate = match PhysBox::new([
+            center.x - size.w / 2.0,
+            center.y - size.h / 2.0,
+            size.w,
+            size.h,
+        ]) {
+            Ok(candidate) => candidate,
+            Err(_) => return false,
+        };
+        self.box_is_clear(&candidate, layers, Some(i))
+    }
+
+    /// Whether `candidate` is on the field without overlapping any cell solid to `layers`,
+    /// other than `skip`
+    fn box_is_clear(
+        &self,
+        candidate: &PhysBox,
+        layers: CollisionLayers,
+        skip: Option<NodeIndex>,
+    ) -> bool {
+        let inner = Self::overlap_test_box(candidate);
+        let mut covered = 0.0;
+        for cell in self.cells_overlapping_box(candidate) {
+            if Some(cell.i) != skip && cell.is_solid_to(layers) && cell.physbox.has_overlap(&inner)
+            {
+                return false;
+            }
+            covered += overlap_area(candidate, &cell.physbox);
+        }
+        covered >= candidate.area() * (1.0 - DEFAULT_EPSILON)
+    }
+
+    /// The part of `physbox` that can't overlap anything for it to be clear. Touching an edge
+    /// isn't overlapping it, even after rounding
+    fn overlap_test_box(physbox: &PhysBox) -> PhysBox {
+        let size = physbox.size();
+        physbox.inset(DEFAULT_EPSILON * size.w.max(size.h).max(1.0))
+    }
+
+    /// The closest position within `radius` of `physbox`'s that it can be moved to without
+    /// overlapping any cell solid to `layers` or hanging off of the field. None if there isn't one
+    pub fn nearest_clear_position(
+        &self,
+        physbox: &PhysBox,
+        layers: CollisionLayers,
+        radius: Scalar,
+    ) -> Option<Point> {
+        self.nearest_clear_position_avoiding(physbox, layers, radius, &[])
+    }
+
+    /// The same as [Field::nearest_clear_position], but the box also can't overlap any of `obstacles`
+    pub fn nearest_clear_position_avoiding(
+        &self,
+        physbox: &PhysBox,
+        layers: CollisionLayers,
+        radius: Scalar,
+        obstacles: &[PhysBox],
+    ) -> Option<Point> {
+        let origin = *physbox.pos();
+        let size = *physbox.size();
+        let search_area = PhysBox::new([
+            origin.x - radius,
+            origin.y - radius,
+            size.w + 2.0 * radius,
+            size.h + 2.0 * radius,
+        ])
+        .ok()?;
+        // The closest clear spot is either where the box already is or touching an edge of a
+        // cell or obstacle on each axis
+        let mut xs = vec![origin.x];
+        let mut ys = vec![origin.y];
+        for obstacle in self
+            .cells_overlapping_box(&search_area)
+            .map(|c| &c.physbox)
+            .chain(obstacles.iter().filter(|o| o.has_overlap(&search_area)))
+        {
+            xs.extend([obstacle.left_x() - size.w, obstacle.right_x()]);
+            ys.extend([obstacle.top_y() - size.h, obstacle.bottom_y()]);
+        }
+
+        let mut candidates: Vec<(Scalar, Point)> = xs
+            .iter()
+            .flat_map(|&x| ys.iter().map(move |&y| Point::from([x, y])))
+            .map(|p| (f64::hypot(p.x - origin.x, p.y - origin.y), p))
+            .filter(|(distance, _)| *distance <= radius)
+            .collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        candidates.into_iter().map(|(_, p)| p).find(|&p| {
+            let mut candidate = *physbox;
+            candidate.set_pos(p);
+            let inner = Self::overlap_test_box(&candidate);
+            self.box_is_clear(&candidate, layers, None)
+                && !obstacles.iter().any(|o| o.has_overlap(&inner))
+        })
+    }
+
     /// Given a cell on the field and a physbox, returns the neighbors of
     /// the cell that the physbox overlaps.
     pub fn neighbors_of_cell_overlapping_box(
@@ -195,6 +549,159 @@ impl Field {
     }
 }
 
+/// The area where `a` and `b` overlap
+fn overlap_area(a: &PhysBox, b: &PhysBox) -> Scalar {
+    let w = a.right_x().min(b.right_x()) - a.left_x().max(b.left_x());
+    let h = a.bottom_y().min(b.bottom_y()) - a.top_y().max(b.top_y());
+    w.max(0.0) * h.max(0.0)
+}
+
+/// The fractions of the movement `d` along `axis` at which `moving` starts and stops overlapping
+/// `fixed` on that axis. Infinite when there's no movement but they overlap, None when they never overlap
+fn sweep_axis(
+    moving: &PhysBox,
+    fixed: &PhysBox,
+    d: Scalar,
+    axis: Axis,
+) -> Option<(Scalar, Scalar)> {
+    let (near, far) = (moving.get_near_axis(axis), moving.get_far_axis(axis));
+    let (fixed_near, fixed_far) = (fixed.get_near_axis(axis), fixed.get_far_axis(axis));
+
+    if d > 0.0 {
+        Some(((fixed_near - far) / d, (fixed_far - near) / d))
+    } else if d < 0.0 {
+        Some(((fixed_far - near) / d, (fixed_near - far) / d))
+    } else if near < fixed_far && far > fixed_near {
+        Some((Scalar::NEG_INFINITY, Scalar::INFINITY))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The first impact of a box moving across the field from [Field::sweep_box]
+pub struct SweepResult {
+    /// The fraction of the displacement moved before the impact, in [0, 1]
+    pub time: Scalar,
+    /// The edge of the cell that was hit
+    pub edge: BoxEdge,
+    /// The position of the box when it makes contact
+    pub pos: Point,
+    /// The index of the cell that was hit
+    pub cell: NodeIndex,
+}
+
+impl SweepResult {
+    /// The normal of the contact surface, pointing away from the cell that was hit
+    pub fn normal(&self) -> [Scalar; 2] {
+        self.edge.normal_vector()
+    }
+
+    /// The axis that the movement was blocked along
+    pub fn axis(&self) -> Axis {
+        self.edge.perpendicular_axis()
+    }
+}
+
+/// Loading a tile size of 0 would otherwise make [TileGrid::tile_to_world] panic
+fn deserialize_tile_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Size, D::Error> {
+    TileGrid::new(Size::deserialize(deserializer)?)
+        .map(|grid| grid.tile_size)
+        .map_err(D::Error::custom)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A uniform grid of tiles with the tile (0, 0) having its top left corner at the world origin
+pub struct TileGrid {
+    #[serde(deserialize_with = "deserialize_tile_size")]
+    tile_size: Size,
+}
+
+impl TileGrid {
+    /// Creates a grid of tiles with the given size.
+    /// The width and height must each be greater than 0.
+    pub fn new(tile_size: Size) -> PhysicsResult<Self> {
+        if tile_size.w > 0.0 && tile_size.h > 0.0 {
+            Ok(Self { tile_size })
+        } else {
+            Err(PhysicsError::PhysBoxSize)
+        }
+    }
+
+    /// The size of each tile in world units
+    pub fn tile_size(&self) -> Size {
+        self.tile_size
+    }
+
+    /// The coordinates of the tile containing `pos`.
+    /// Tiles contain their top and left edges, so positions left of or above the origin
+    /// are in negative tiles
+    pub fn world_to_tile(&self, pos: Point) -> (i32, i32) {
+        (
+            (pos.x / self.tile_size.w).floor() as i32,
+            (pos.y / self.tile_size.h).floor() as i32,
+        )
+    }
+
+    /// The area covered by the tile at the given coordinates
+    pub fn tile_to_world(&self, (x, y): (i32, i32)) -> PhysBox {
+        // The size is validated when the grid is created or loaded, so this can't fail
+        PhysBox::new([
+            x as Scalar * self.tile_size.w,
+            y as Scalar * self.tile_size.h,
+            self.tile_size.w,
+            self.tile_size.h,
+        ])
+        .unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A compact grid of tile solidities, stored as runs of the same solidity row by row.
+/// Meant for saving grid based fields and editing them by hand
+pub struct RleGrid {
+    /// The position of the top left corner of the grid
+    pub origin: Point,
+    /// The size of each tile in world units
+    pub tile_size: Size,
+    /// The number of tiles in each row
+    pub width: usize,
+    /// The solidity and number of tiles in each run
+    pub runs: Vec<(Solidity, usize)>,
+}
+
+impl RleGrid {
+    /// Encodes a grid `width` tiles wide from the solidities of its tiles, row by row
+    pub fn encode(origin: Point, tile_size: Size, width: usize, solidities: &[Solidity]) -> Self {
+        let mut runs: Vec<(Solidity, usize)> = Vec::new();
+        for solidity in solidities {
+            match runs.last_mut() {
+                Some((run_solidity, count)) if run_solidity == solidity => *count += 1,
+                _ => runs.push((*solidity, 1)),
+            }
+        }
+        Self {
+            origin,
+            tile_size,
+            width,
+            runs,
+        }
+    }
+
+    /// The solidity of every tile, row by row
+    pub fn decode(&self) -> Vec<Solidity> {
+        self.runs
+            .iter()
+            .flat_map(|(solidity, count)| vec![*solidity; *count])
+            .collect()
+    }
+
+    /// Builds a field with a cell for every tile (see [Field::from_grid])
+    pub fn to_field(&self) -> PhysicsResult<Field> {
+        Field::from_grid(self.origin, self.tile_size, self.width, &self.decode())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Renders a Field, coloring all cells with a color determined by their solidity
 /// i.e. all cells of a unique solidity are a single color
@@ -215,6 +722,15 @@ impl FieldColorView {
             _ => &mut self.default_view,
         }
     }
+
+    /// The color the cell is rendered with
+    pub fn color_for_cell(&self, cell: &Cell) -> Color {
+        match cell.solidity {
+            SOLID => self.solid_view.color,
+            NO_SOLIDITY => self.air_view.color,
+            _ => self.default_view.color,
+        }
+    }
 }
 
 impl View for FieldColorView {
@@ -237,6 +753,119 @@ impl View for FieldColorView {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Renders a Field with the same colors as a [FieldColorView], but builds the triangles for all of
+/// its cells once and redraws them with just the camera's transform each frame. This is much faster
+/// for large static fields, the mesh is only rebuilt after cells are added or removed
+pub struct FieldMeshView {
+    /// The colors for the cells
+    pub colors: FieldColorView,
+    #[serde(skip)]
+    mesh: Option<FieldMesh>,
+}
+
+#[derive(Debug, Clone)]
+/// The triangles for a field's cells in world coordinates, batched by color
+struct FieldMesh {
+    /// The field's revision when the mesh was built
+    revision: u64,
+    batches: Vec<(Color, Vec<[Scalar; 2]>)>,
+}
+
+impl FieldMeshView {
+    /// Creates a view that hasn't built its mesh yet
+    pub fn new(colors: FieldColorView) -> Self {
+        Self { colors, mesh: None }
+    }
+
+    /// Rebuilds the cached mesh if the field has changed since it was built.
+    /// Returns whether it was rebuilt
+    pub fn prepare(&mut self, field: &Field) -> bool {
+        if self
+            .mesh
+            .as_ref()
+            .map(|mesh| mesh.revision == field.revision())
+            .unwrap_or(false)
+        {
+            return false;
+        }
+
+        let mut batches: Vec<(Color, Vec<[Scalar; 2]>)> = Vec::new();
+        for cell in field.cells() {
+            let color = self.colors.color_for_cell(cell);
+            let vertices = match batches.iter_mut().find(|(c, _)| *c == color) {
+                Some((_, vertices)) => vertices,
+                None => {
+                    batches.push((color, Vec::new()));
+                    &mut batches.last_mut().unwrap().1
+                }
+            };
+            let (l, t, r, b) = (
+                cell.physbox.left_x(),
+                cell.physbox.top_y(),
+                cell.physbox.right_x(),
+                cell.physbox.bottom_y(),
+            );
+            vertices.extend([[l, t], [r, t], [l, b], [r, t], [r, b], [l, b]]);
+        }
+        self.mesh = Some(FieldMesh {
+            revision: field.revision(),
+            batches,
+        });
+        true
+    }
+
+    /// The number of vertices in the cached mesh
+    pub fn vertex_count(&self) -> usize {
+        self.mesh
+            .as_ref()
+            .map(|mesh| mesh.batches.iter().map(|(_, v)| v.len()).sum())
+            .unwrap_or(0)
+    }
+
+    /// Draws the field's cached mesh (rebuilding it first if necessary) with any graphics backend
+    pub fn draw<G: Graphics>(&mut self, field: &Field, camera: &Camera, ctx: Context, g: &mut G) {
+        self.prepare(field);
+        let mesh = match self.mesh.as_ref() {
+            Some(mesh) => mesh,
+            None => return,
+        };
+
+        let transform = camera.view_transform(&ctx);
+        // Whole triangles per chunk
+        let chunk_size = BACK_END_MAX_VERTEX_COUNT / 3 * 3;
+        let mut buffer = [[0.0f32; 2]; BACK_END_MAX_VERTEX_COUNT];
+        for (color, vertices) in &mesh.batches {
+            g.tri_list(&ctx.draw_state, color, |f| {
+                for chunk in vertices.chunks(chunk_size) {
+                    for (screen, world) in buffer.iter_mut().zip(chunk) {
+                        let [x, y] = transform_pos(transform, *world);
+                        *screen = [x as f32, y as f32];
+                    }
+                    f(&buffer[..chunk.len()]);
+                }
+            });
+        }
+    }
+}
+
+impl View for FieldMeshView {
+    type Viewed = Field;
+
+    fn render(
+        &mut self,
+        viewed: &Self::Viewed,
+        _args: &RenderArgs,
+        camera: &Camera,
+        ctx: Context,
+        _texture_registry: &TextureRegistry,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        self.draw(viewed, camera, ctx, gl);
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, HasBox, HasBoxMut, HasSolidity)]
 /// Represents a static area on a Field that determines the passability for other standard entities
 pub struct Cell {
@@ -248,17 +877,57 @@ pub struct Cell {
     #[has_box]
     /// The upper left corner and width/height of the cell
     physbox: PhysBox,
+    /// The collision layers that the cell's solidity applies to
+    #[serde(default)]
+    solid_to: CollisionLayers,
+    /// The name of the region the cell belongs to, if any
+    #[serde(default)]
+    name: Option<String>,
 }
 
 impl Cell {
-    /// Creates a new cell with the given Solidity and PhysBox
+    /// Creates a new cell with the given Solidity and PhysBox, solid to every collision layer
     pub fn new(solidity: Solidity, physbox: PhysBox) -> Self {
         Self {
             i: NodeIndex::new(0),
             solidity,
             physbox,
+            solid_to: CollisionLayers::default(),
+            name: None,
         }
     }
+
+    /// Sets the collision layers that the cell's solidity applies to.
+    /// Entities on none of them pass through the cell as if it had no solidity
+    pub fn set_solid_to(&mut self, solid_to: CollisionLayers) {
+        self.solid_to = solid_to;
+    }
+
+    /// The collision layers that the cell's solidity applies to
+    pub fn solid_to(&self) -> CollisionLayers {
+        self.solid_to
+    }
+
+    /// Identifies the cell within its field
+    pub fn id(&self) -> CellId {
+        self.i
+    }
+
+    /// Names the region that the cell belongs to. Cells with no solidity that share a name are
+    /// treated as a single region by [Scene::set_region_events](crate::scene::Scene::set_region_events)
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    /// The name of the region the cell belongs to, if any
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Whether the cell's solidity applies to an entity on the given collision layers
+    pub fn is_solid_to(&self, layers: CollisionLayers) -> bool {
+        self.solidity.has_solidity() && self.solid_to.intersects(layers)
+    }
 }
 
 /// Represents the neighbors of a cell organized by what edge the neighbor is on
@@ -372,7 +1041,7 @@ impl View for CellColorView {
 
 #[cfg(test)]
 mod test {
-    use crate::types::physbox::HasBox;
+    use crate::types::{physbox::HasBox, ENTER_TOP};
 
     use super::*;
 
@@ -545,4 +1214,277 @@ mod test {
             &vec![field.cell_at_pos(*boxes[3].pos()).unwrap()]
         );
     }
+
+    #[test]
+    fn tile_coordinates_round_trip() {
+        let grid = TileGrid::new([16.0, 8.0].into()).unwrap();
+
+        for tile in [(0, 0), (1, 2), (-1, 0), (0, -1), (-3, -7), (12, -5)] {
+            let tile_box = grid.tile_to_world(tile);
+            assert_eq!(grid.world_to_tile(*tile_box.pos()), tile);
+            assert_eq!(grid.world_to_tile(*tile_box.pos() + [8.0, 4.0]), tile);
+        }
+
+        // Just left of and above the origin is in the negative tile, not tile 0
+        assert_eq!(grid.world_to_tile([-0.5, -0.5].into()), (-1, -1));
+        assert_eq!(
+            grid.tile_to_world((-1, -1)),
+            PhysBox::new([-16.0, -8.0, 16.0, 8.0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn tile_grid_needs_positive_size() {
+        assert_eq!(
+            TileGrid::new([0.0, 8.0].into()).unwrap_err(),
+            PhysicsError::PhysBoxSize
+        );
+
+        let saved = rmp_serde::to_vec(&TileGrid::new([8.0, 8.0].into()).unwrap()).unwrap();
+        assert!(rmp_serde::from_slice::<TileGrid>(&saved).is_ok());
+        let zero_size = TileGrid {
+            tile_size: [8.0, 0.0].into(),
+        };
+        let saved = rmp_serde::to_vec(&zero_size).unwrap();
+        assert!(rmp_serde::from_slice::<TileGrid>(&saved).is_err());
+    }
+
+    #[test]
+    fn cell_at_tile_needs_a_grid() {
+        let (boxes, mut field) = create_test_field();
+        assert!(field.cell_at_tile((0, 0)).is_none());
+
+        field.set_tile_grid(Some(TileGrid::new([10.0, 10.0].into()).unwrap()));
+        assert_eq!(field.cell_at_tile((0, 0)).unwrap().get_box(), &boxes[0]);
+        assert_eq!(field.cell_at_tile((3, 1)).unwrap().get_box(), &boxes[3]);
+        assert!(field.cell_at_tile((-2, 0)).is_none());
+
+        field.set_tile_grid(Some(TileGrid::new([1.0, 1.0].into()).unwrap()));
+        assert_eq!(field.cell_at_tile((-1, 5)).unwrap().get_box(), &boxes[9]);
+    }
+
+    fn corner_field() -> Field {
+        Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 30.0, 40.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([30.0, 0.0, 10.0, 50.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 40.0, 30.0, 10.0]).unwrap()),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn sweep_box_into_corner_hits_nearest_wall_first() {
+        let field = corner_field();
+
+        // Reaches the wall's left edge (x = 30) before the floor's top edge (y = 40)
+        let physbox = PhysBox::new([10.0, 10.0, 4.0, 4.0]).unwrap();
+        let hit = field.sweep_box(&physbox, [40.0, 40.0]).unwrap();
+        assert_eq!(hit.axis(), Axis::X);
+        assert_eq!(hit.edge, BoxEdge::Left);
+        assert_eq!(hit.normal(), [-1.0, 0.0]);
+        assert_eq!(hit.time, 0.4);
+        assert_eq!(hit.pos, [26.0, 26.0].into());
+        assert_eq!(
+            field.graph[hit.cell].physbox,
+            *field.cell_at_pos([30.0, 0.0].into()).unwrap().get_box()
+        );
+
+        // Closer to the floor than the wall
+        let physbox = PhysBox::new([5.0, 30.0, 4.0, 4.0]).unwrap();
+        let hit = field.sweep_box(&physbox, [20.0, 20.0]).unwrap();
+        assert_eq!(hit.axis(), Axis::Y);
+        assert_eq!(hit.edge, BoxEdge::Top);
+        assert_eq!(hit.time, 0.3);
+        assert_eq!(hit.pos, [11.0, 36.0].into());
+    }
+
+    #[test]
+    fn sweep_box_sliding_along_floor_doesnt_hit() {
+        let field = corner_field();
+
+        let physbox = PhysBox::new([0.0, 36.0, 4.0, 4.0]).unwrap();
+        assert!(field.sweep_box(&physbox, [20.0, 0.0]).is_none());
+        assert!(field.sweep_box(&physbox, [0.0, 0.0]).is_none());
+    }
+
+    fn negative_quadrant_field() -> Field {
+        Field::new(vec![
+            Cell::new(
+                NO_SOLIDITY,
+                PhysBox::new([-30.0, -10.0, 30.0, 10.0]).unwrap(),
+            ),
+            // A thin ledge, thinner than a unit
+            Cell::new(SOLID, PhysBox::new([-30.0, -10.5, 30.0, 0.5]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([-40.0, -10.0, 10.0, 10.0]).unwrap()),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn negative_and_thin_cells_are_neighbors() {
+        let field = negative_quadrant_field();
+
+        let cell_at = field.cell_at_pos([-0.5, -0.5].into()).unwrap();
+        assert_eq!(
+            cell_at.get_box(),
+            &PhysBox::new([-30.0, -10.0, 30.0, 10.0]).unwrap()
+        );
+
+        let testbox = PhysBox::new([-32.0, -11.0, 4.0, 4.0]).unwrap();
+        let neighbors = field
+            .neighbors_of_cell_overlapping_box(cell_at, &testbox)
+            .unwrap();
+        assert_eq!(
+            neighbors.get_neighbors(BoxEdge::Top)[0].get_box(),
+            &PhysBox::new([-30.0, -10.5, 30.0, 0.5]).unwrap()
+        );
+        assert_eq!(
+            neighbors.get_neighbors(BoxEdge::Left)[0].get_box(),
+            &PhysBox::new([-40.0, -10.0, 10.0, 10.0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn large_grid_cells_neighbor_only_the_cells_beside_them() {
+        let width = 100;
+        let field = Field::from_grid(
+            [-50.0, 0.0].into(),
+            [0.5, 2.0].into(),
+            width,
+            &vec![NO_SOLIDITY; width * width],
+        )
+        .unwrap();
+
+        // Every pair of tiles sharing an edge, in both directions
+        assert_eq!(field.graph.edge_count(), 2 * 2 * width * (width - 1));
+        let corner = NodeIndex::new(0);
+        let mut corner_neighbors: Vec<(usize, BoxEdge)> = field
+            .graph
+            .edges(corner)
+            .map(|e| (e.target().index(), e.weight().0))
+            .collect();
+        corner_neighbors.sort_by_key(|(i, _)| *i);
+        assert_eq!(
+            corner_neighbors,
+            [(1, BoxEdge::Right), (width, BoxEdge::Bottom)]
+        );
+    }
+
+    #[test]
+    fn sweep_box_in_negative_quadrant() {
+        let field = negative_quadrant_field();
+
+        let physbox = PhysBox::new([-20.0, -5.0, 4.0, 4.0]).unwrap();
+        let hit = field.sweep_box(&physbox, [-20.0, 0.0]).unwrap();
+        assert_eq!(hit.edge, BoxEdge::Right);
+        assert!(hit.pos.approx_eq(&[-30.0, -5.0].into(), 1e-9));
+
+        let hit = field.sweep_box(&physbox, [0.0, -10.0]).unwrap();
+        assert_eq!(hit.edge, BoxEdge::Bottom);
+        assert!(hit.pos.approx_eq(&[-20.0, -10.0].into(), 1e-9));
+    }
+
+    fn cell_set(field: &Field) -> Vec<(PhysBox, Solidity)> {
+        let mut cells: Vec<_> = field.cells().map(|c| (c.physbox, c.solidity)).collect();
+        cells.sort_by(|(a, _), (b, _)| {
+            (a.top_y(), a.left_x())
+                .partial_cmp(&(b.top_y(), b.left_x()))
+                .unwrap()
+        });
+        cells
+    }
+
+    #[test]
+    fn field_round_trips_through_rle_grid() {
+        let solidities = [
+            SOLID,
+            SOLID,
+            SOLID,
+            SOLID, //
+            SOLID,
+            NO_SOLIDITY,
+            !ENTER_TOP,
+            SOLID, //
+            SOLID,
+            NO_SOLIDITY,
+            NO_SOLIDITY,
+            SOLID,
+        ];
+        let field =
+            Field::from_grid([-8.0, 16.0].into(), [8.0, 4.0].into(), 4, &solidities).unwrap();
+
+        let grid = field.to_rle_grid([8.0, 4.0].into()).unwrap();
+        assert_eq!(grid.origin, [-8.0, 16.0].into());
+        assert_eq!(grid.width, 4);
+        assert_eq!(grid.decode(), solidities);
+
+        let bytes = rmp_serde::to_vec(&grid).unwrap();
+        let loaded: RleGrid = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(cell_set(&loaded.to_field().unwrap()), cell_set(&field));
+
+        // The grid doesn't line up with the cells
+        assert_eq!(
+            field.to_rle_grid([3.0, 4.0].into()).unwrap_err(),
+            PhysicsError::NotAGrid
+        );
+        assert_eq!(
+            Field::from_grid([0.0, 0.0].into(), [1.0, 1.0].into(), 5, &solidities).unwrap_err(),
+            PhysicsError::GridShape(12, 5)
+        );
+    }
+
+    #[test]
+    fn uniform_region_compresses_to_few_runs() {
+        // A large open room with a solid floor, as a few big cells rather than one per tile
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 400.0, 396.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 396.0, 400.0, 4.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let grid = field.to_rle_grid([4.0, 4.0].into()).unwrap();
+        assert_eq!(grid.width, 100);
+        assert_eq!(grid.runs, vec![(NO_SOLIDITY, 9900), (SOLID, 100)]);
+        assert_eq!(grid.decode().len(), 10_000);
+    }
+
+    #[test]
+    fn mesh_rebuilds_after_cell_removed() {
+        let (_, mut field) = create_test_field();
+        let color_view = CellColorView { color: [1.0; 4] };
+        let mut view = FieldMeshView::new(FieldColorView {
+            solid_view: CellColorView {
+                color: [0.0, 0.0, 0.0, 1.0],
+            },
+            air_view: color_view.clone(),
+            default_view: color_view,
+        });
+
+        assert!(view.prepare(&field));
+        assert_eq!(view.vertex_count(), 60);
+        // Nothing changed so the cached mesh is reused
+        assert!(!view.prepare(&field));
+
+        let removed = field.cell_at_pos([5.0, 5.0].into()).unwrap().id();
+        field.remove_cell(removed).unwrap().unwrap();
+        assert_eq!(field.cell_at_pos([5.0, 5.0].into()), None);
+        assert!(view.prepare(&field));
+        assert_eq!(view.vertex_count(), 54);
+
+        // The ids are still consistent for the cell that took the removed one's place
+        for cell in field.cells() {
+            assert_eq!(field.graph.node_weight(cell.id()), Some(cell));
+        }
+    }
+
+    #[test]
+    fn line_of_sight_blocked_by_solid_cells() {
+        let (_, field) = create_test_field();
+
+        // Across the air cells
+        assert!(field.has_line_of_sight([35.0, 5.0].into(), [40.0, 50.0].into()));
+        // Out of a solid cell into the air, but not into another solid cell
+        assert!(field.has_line_of_sight([5.0, 5.0].into(), [20.0, 5.0].into()));
+        assert!(!field.has_line_of_sight([40.0, 5.0].into(), [20.0, 20.0].into()));
+    }
 }
@@ -0,0 +1,299 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::AnimationError,
+    rendering::sprite::{AnimationStateMachine, AnimationStates},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A way out of one of a [LogicStateMachine]'s states
+pub enum Transition<S> {
+    /// Moves on once the state has lasted `after` seconds
+    Timed {
+        /// How long the state lasts
+        after: f64,
+        /// The state moved to
+        to: S,
+    },
+    /// Moves on when `event` is [triggered](LogicStateMachine::trigger)
+    OnEvent {
+        /// The name of the event
+        event: String,
+        /// The state moved to
+        to: S,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The logic states of a game object and the timed and triggered transitions between them,
+/// i.e. a door that starts opening when a switch is pulled and is open a second later.
+///
+/// Kept alongside an entity by the type registering it, and separate from its
+/// [AnimationStateMachine], which it can [drive](LogicStateMachine::drive_animation).
+/// Time spent in the current state is saved, so a loaded machine carries on mid-transition
+pub struct LogicStateMachine<S: Debug + Clone + Eq + Hash> {
+    current_state: S,
+    /// The time (in seconds) spent in the current state
+    elapsed: f64,
+    transitions: HashMap<S, Vec<Transition<S>>>,
+    /// The states entered since they were last taken, in the order they were entered
+    #[serde(default = "Vec::new")]
+    entered: Vec<S>,
+}
+
+impl<S: Debug + Clone + Eq + Hash> LogicStateMachine<S> {
+    /// Creates a machine in `initial_state` without any transitions
+    pub fn new(initial_state: S) -> Self {
+        Self {
+            current_state: initial_state,
+            elapsed: 0.0,
+            transitions: HashMap::new(),
+            entered: Vec::new(),
+        }
+    }
+
+    /// Adds a way out of `from`. Timed transitions are checked in the order they were added
+    pub fn add_transition(&mut self, from: S, transition: Transition<S>) {
+        self.transitions.entry(from).or_default().push(transition);
+    }
+
+    /// The state the machine is in
+    pub fn current_state(&self) -> &S {
+        &self.current_state
+    }
+
+    /// How long (in seconds) the machine has been in the current state
+    pub fn time_in_state(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Moves straight to `state`, whatever the transitions are
+    pub fn set_current_state(&mut self, state: S) {
+        self.entered.push(state.clone());
+        self.current_state = state;
+        self.elapsed = 0.0;
+    }
+
+    /// Takes the transition out of the current state for `event`, if it has one.
+    /// Returns whether the state changed
+    pub fn trigger(&mut self, event: &str) -> bool {
+        let to = self
+            .transitions
+            .get(&self.current_state)
+            .and_then(|transitions| {
+                transitions.iter().find_map(|t| match t {
+                    Transition::OnEvent { event: e, to } if e == event => Some(to.clone()),
+                    _ => None,
+                })
+            });
+        match to {
+            Some(to) => {
+                self.set_current_state(to);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Counts `dt` seconds towards the current state's timed transitions, taking them as they
+    /// come due. Time left over after a transition counts towards the next state, so several
+    /// short states can pass in one tick
+    pub fn tick(&mut self, dt: f64) {
+        self.elapsed += dt;
+        // Limited so that a cycle of instant transitions can't loop forever
+        for _ in 0..=self.transitions.len() {
+            let due = self
+                .transitions
+                .get(&self.current_state)
+                .and_then(|transitions| {
+                    transitions.iter().find_map(|t| match t {
+                        Transition::Timed { after, to } if *after <= self.elapsed => {
+                            Some((*after, to.clone()))
+                        }
+                        _ => None,
+                    })
+                });
+            match due {
+                Some((after, to)) => {
+                    let leftover = self.elapsed - after;
+                    self.set_current_state(to);
+                    self.elapsed = leftover;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Empties the list of states entered since it was last taken, i.e. to react to a door
+    /// finishing opening
+    pub fn take_entered(&mut self) -> Vec<S> {
+        std::mem::take(&mut self.entered)
+    }
+
+    /// Switches `animations` to the animation state that `to_animation` gives for the current
+    /// logic state, if it isn't already showing it. Should be called after ticking and triggering
+    pub fn drive_animation<A, F>(
+        &self,
+        animations: &mut AnimationStateMachine<A>,
+        to_animation: F,
+    ) -> Result<(), AnimationError>
+    where
+        A: AnimationStates,
+        F: Fn(&S) -> A,
+    {
+        let state = to_animation(&self.current_state);
+        if *animations.current_state() != state {
+            animations.set_current_state(state)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{
+        gameobject::entity::{registry::test::TestEntity, Entity},
+        rendering::sprite::{SpriteAnimation, SpriteView},
+        types::physbox::{HasBox, PhysBox},
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum DoorState {
+        Closed,
+        Opening,
+        Open,
+        Closing,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum DoorAnimation {
+        Shut,
+        Moving,
+        Ajar,
+    }
+
+    impl AnimationStates for DoorAnimation {
+        type Viewed = PhysBox;
+
+        fn next_state(&self, _viewed: &Self::Viewed) -> Option<Self> {
+            None
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Door {
+        entity: Entity,
+        logic: LogicStateMachine<DoorState>,
+    }
+
+    fn door() -> Door {
+        use DoorState::*;
+        let mut logic = LogicStateMachine::new(Closed);
+        let on_event = |event: &str, to| Transition::OnEvent {
+            event: event.to_string(),
+            to,
+        };
+        logic.add_transition(Closed, on_event("open", Opening));
+        logic.add_transition(
+            Opening,
+            Transition::Timed {
+                after: 1.0,
+                to: Open,
+            },
+        );
+        logic.add_transition(Open, on_event("close", Closing));
+        logic.add_transition(
+            Open,
+            Transition::Timed {
+                after: 3.0,
+                to: Closing,
+            },
+        );
+        logic.add_transition(
+            Closing,
+            Transition::Timed {
+                after: 1.0,
+                to: Closed,
+            },
+        );
+        Door {
+            entity: TestEntity::with_box([10.0, 10.0, 5.0, 20.0]).0,
+            logic,
+        }
+    }
+
+    fn door_animation(state: &DoorState) -> DoorAnimation {
+        match state {
+            DoorState::Closed => DoorAnimation::Shut,
+            DoorState::Opening | DoorState::Closing => DoorAnimation::Moving,
+            DoorState::Open => DoorAnimation::Ajar,
+        }
+    }
+
+    #[test]
+    fn door_opens_on_trigger_and_resumes_mid_transition_after_loading() {
+        let frame = || {
+            SpriteAnimation::new_static_frame(
+                SpriteView::new([0.0, 0.0].into(), [5.0, 20.0].into(), "door.png".into()).unwrap(),
+            )
+        };
+        let mut animations = AnimationStateMachine::new(
+            DoorAnimation::Shut,
+            [
+                (DoorAnimation::Shut, frame()),
+                (DoorAnimation::Moving, frame()),
+                (DoorAnimation::Ajar, frame()),
+            ]
+            .into_iter()
+            .collect(),
+        )
+        .unwrap();
+
+        let mut door = door();
+        // Nothing happens until it's triggered
+        door.logic.tick(5.0);
+        assert!(!door.logic.trigger("close"));
+        assert_eq!(door.logic.current_state(), &DoorState::Closed);
+
+        assert!(door.logic.trigger("open"));
+        door.logic.tick(0.4);
+        door.logic
+            .drive_animation(&mut animations, door_animation)
+            .unwrap();
+        assert_eq!(door.logic.current_state(), &DoorState::Opening);
+        assert_eq!(animations.current_state(), &DoorAnimation::Moving);
+
+        // Saved and loaded partway through opening
+        let saved = rmp_serde::to_vec(&door).unwrap();
+        let mut door: Door = rmp_serde::from_slice(&saved).unwrap();
+        assert_eq!(door.logic.current_state(), &DoorState::Opening);
+        assert_eq!(door.logic.time_in_state(), 0.4);
+        assert_eq!(door.entity.get_box().pos(), &[10.0, 10.0].into());
+
+        door.logic.tick(0.5);
+        assert_eq!(door.logic.current_state(), &DoorState::Opening);
+        door.logic.tick(0.3);
+        door.logic
+            .drive_animation(&mut animations, door_animation)
+            .unwrap();
+        assert_eq!(door.logic.current_state(), &DoorState::Open);
+        assert!((door.logic.time_in_state() - 0.2).abs() < 1e-9);
+        assert_eq!(animations.current_state(), &DoorAnimation::Ajar);
+        assert_eq!(
+            door.logic.take_entered(),
+            vec![DoorState::Opening, DoorState::Open]
+        );
+
+        // Left open it closes by itself, passing through closing in one long tick
+        door.logic.tick(5.0);
+        assert_eq!(door.logic.current_state(), &DoorState::Closed);
+        assert_eq!(
+            door.logic.take_entered(),
+            vec![DoorState::Closing, DoorState::Closed]
+        );
+    }
+}
@@ -3,3 +3,6 @@ pub mod entity;
 
 /// The setting of a game scene, determines static obstables
 pub mod field;
+
+/// Timed and triggered logic states for game objects, i.e. doors
+pub mod logic;
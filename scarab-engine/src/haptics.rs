@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+/// Drives the rumble motors of connected controllers.
+/// Piston doesn't expose haptics, so games implement this with whatever gamepad library they use
+pub trait RumbleBackend {
+    /// The number of controllers currently connected
+    fn controller_count(&self) -> usize;
+
+    /// Whether the connected controller at `controller_index` has rumble motors
+    fn supports_rumble(&self, controller_index: usize) -> bool;
+
+    /// Starts the controller's motors. The command is already validated for the controller
+    fn rumble(&mut self, controller_index: usize, command: RumbleCommand);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// How strongly and for how long to rumble a controller
+pub struct RumbleCommand {
+    /// The strength of the low frequency (usually heavier) motor in [0, 1]
+    pub low_freq: f64,
+    /// The strength of the high frequency (usually lighter) motor in [0, 1]
+    pub high_freq: f64,
+    /// How long to rumble for in seconds
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// What a controller slot is capable of
+pub enum HapticCapability {
+    /// There's no controller connected at the index
+    Disconnected,
+    /// The controller is connected, but has no rumble motors
+    NoRumble,
+    /// The controller can rumble
+    Rumble,
+}
+
+/// The game-facing haptics API, wrapping a [RumbleBackend]
+#[derive(Debug)]
+pub struct Haptics<B> {
+    backend: B,
+}
+
+impl<B: RumbleBackend> Haptics<B> {
+    /// Creates haptics driven by `backend`
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// What the controller at `controller_index` is capable of
+    pub fn capability(&self, controller_index: usize) -> HapticCapability {
+        if controller_index >= self.backend.controller_count() {
+            HapticCapability::Disconnected
+        } else if self.backend.supports_rumble(controller_index) {
+            HapticCapability::Rumble
+        } else {
+            HapticCapability::NoRumble
+        }
+    }
+
+    /// Rumbles the controller at `controller_index`, clamping the strengths to [0, 1].
+    /// Does nothing for disconnected controllers or those without rumble.
+    /// Returns whether the command was sent
+    pub fn set_rumble(
+        &mut self,
+        controller_index: usize,
+        low_freq: f64,
+        high_freq: f64,
+        duration: f64,
+    ) -> bool {
+        if self.capability(controller_index) != HapticCapability::Rumble || duration <= 0.0 {
+            return false;
+        }
+
+        self.backend.rumble(
+            controller_index,
+            RumbleCommand {
+                low_freq: low_freq.clamp(0.0, 1.0),
+                high_freq: high_freq.clamp(0.0, 1.0),
+                duration,
+            },
+        );
+        true
+    }
+
+    /// A reference to the underlying backend
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// A mutable reference to the underlying backend
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordingBackend {
+        rumble_support: Vec<bool>,
+        sent: Vec<(usize, RumbleCommand)>,
+    }
+
+    impl RumbleBackend for RecordingBackend {
+        fn controller_count(&self) -> usize {
+            self.rumble_support.len()
+        }
+
+        fn supports_rumble(&self, controller_index: usize) -> bool {
+            self.rumble_support[controller_index]
+        }
+
+        fn rumble(&mut self, controller_index: usize, command: RumbleCommand) {
+            self.sent.push((controller_index, command));
+        }
+    }
+
+    #[test]
+    fn rumble_dispatched_only_to_capable_controllers() {
+        let mut haptics = Haptics::new(RecordingBackend {
+            rumble_support: vec![true, false],
+            ..Default::default()
+        });
+
+        assert!(haptics.set_rumble(0, 2.0, 0.5, 0.25));
+        assert!(!haptics.set_rumble(1, 1.0, 1.0, 0.25));
+        assert!(!haptics.set_rumble(2, 1.0, 1.0, 0.25));
+
+        assert_eq!(
+            haptics.backend().sent,
+            vec![(
+                0,
+                RumbleCommand {
+                    low_freq: 1.0,
+                    high_freq: 0.5,
+                    duration: 0.25,
+                }
+            )]
+        );
+        assert_eq!(haptics.capability(1), HapticCapability::NoRumble);
+        assert_eq!(haptics.capability(2), HapticCapability::Disconnected);
+    }
+}
@@ -3,8 +3,9 @@ use piston::{
     MouseButton,
 };
 use serde::{Deserialize, Serialize};
+use shapes::Point;
 
-use crate::{types::ROOT_2, ScarabResult};
+use crate::{gameobject::entity::registry::EntityId, types::ROOT_2, ScarabResult};
 
 /// A trait for types that handle user inputs.
 /// User input handling is split into two stages: mapping input to action and performing the action
@@ -296,3 +297,244 @@ impl InputBinding for ButtonBinding {
         None
     }
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The state of a [PolledButton] over all of the input events since it was last polled
+pub struct ButtonPoll {
+    /// Whether the action should be considered held this update
+    pub down: bool,
+    /// Whether the button was pressed at any point since the last poll
+    pub tapped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A button binding that's read once per update instead of per event.
+/// Events are recorded as they arrive and [PolledButton::poll] reports them all at once,
+/// so a press and release landing between two updates isn't missed
+pub struct PolledButton {
+    button: SingleButton,
+    /// Whether a press that's released before the next poll still counts as down for that poll.
+    /// When false `down` is only the button's state when polled
+    pub coalesce: bool,
+    #[serde(skip)]
+    held: bool,
+    #[serde(skip)]
+    pressed_since_poll: bool,
+}
+
+impl PolledButton {
+    /// Makes a new instance of self for the given button, coalescing events by default
+    pub fn new(button: SingleButton) -> Self {
+        Self {
+            button,
+            coalesce: true,
+            held: false,
+            pressed_since_poll: false,
+        }
+    }
+
+    /// Whether the button is physically held right now
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// Reports the button's state since the last poll, consuming the tap
+    pub fn poll(&mut self) -> ButtonPoll {
+        let tapped = core::mem::take(&mut self.pressed_since_poll);
+        ButtonPoll {
+            down: self.held || (self.coalesce && tapped),
+            tapped,
+        }
+    }
+}
+
+impl InputBinding for PolledButton {
+    type ActionArg = bool;
+
+    /// Records the event, returning whether the button is now held
+    fn maybe_to_action(&mut self, input: &Input) -> Option<Self::ActionArg> {
+        if let Input::Button(args) = input {
+            if args.button == self.button {
+                self.held = args.state == ButtonState::Press;
+                self.pressed_since_poll |= self.held;
+                return Some(self.held);
+            }
+        };
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Biases an analog aim direction towards nearby targets, for aiming with a controller.
+/// Usually used through [crate::scene::Scene::assist_aim], which finds the candidate targets
+pub struct AimAssist {
+    /// Targets within this angle (in radians) either side of the aim are assisted towards
+    pub half_angle: f64,
+    /// Targets further than this from the aim's origin are ignored
+    pub range: f64,
+    /// The fraction in [0, 1] of the angle between the aim and the target that the aim is turned by
+    pub bias: f64,
+    /// The extra angle (in radians) that the last assisted target stays assisted within,
+    /// so that the aim "sticks" to it instead of dropping it right at the edge of the cone
+    pub stickiness: f64,
+    #[serde(skip)]
+    sticky_target: Option<EntityId>,
+}
+
+impl AimAssist {
+    /// Creates an aim assist with no current target
+    pub fn new(half_angle: f64, range: f64, bias: f64, stickiness: f64) -> Self {
+        Self {
+            half_angle,
+            range,
+            bias,
+            stickiness,
+            sticky_target: None,
+        }
+    }
+
+    /// The target that the aim was last assisted towards
+    pub fn sticky_target(&self) -> Option<EntityId> {
+        self.sticky_target
+    }
+
+    /// Turns `aim` from `origin` towards the candidate target closest to it by angle,
+    /// keeping its magnitude. The aim is unchanged when no candidate is within the cone
+    pub fn apply<I: IntoIterator<Item = (EntityId, Point)>>(
+        &mut self,
+        origin: Point,
+        aim: [f64; 2],
+        candidates: I,
+    ) -> [f64; 2] {
+        let magnitude = f64::hypot(aim[0], aim[1]);
+        if magnitude == 0.0 {
+            self.sticky_target = None;
+            return aim;
+        }
+
+        let closest = candidates
+            .into_iter()
+            .filter_map(|(id, pos)| {
+                let to_target = pos - origin;
+                if f64::hypot(to_target.x, to_target.y) > self.range {
+                    return None;
+                }
+                // The signed angle from the aim to the target
+                let angle = f64::atan2(
+                    aim[0] * to_target.y - aim[1] * to_target.x,
+                    aim[0] * to_target.x + aim[1] * to_target.y,
+                );
+                let allowed = if Some(id) == self.sticky_target {
+                    self.half_angle + self.stickiness
+                } else {
+                    self.half_angle
+                };
+                (angle.abs() <= allowed).then_some((id, angle))
+            })
+            .min_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()));
+
+        self.sticky_target = closest.map(|(id, _)| id);
+        match closest {
+            Some((_, angle)) => {
+                let turned = f64::atan2(aim[1], aim[0]) + angle * self.bias.clamp(0.0, 1.0);
+                [magnitude * turned.cos(), magnitude * turned.sin()]
+            }
+            None => aim,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Lock-on targeting for a controller, i.e. Z-targeting. Targets are picked and kept by
+/// [crate::scene::Scene::lock_on], [crate::scene::Scene::cycle_lock_on] and
+/// [crate::scene::Scene::update_lock_on]
+pub struct LockOn {
+    /// How far away targets can be locked onto, the lock breaks beyond it
+    pub range: f64,
+    /// If set, new targets are only picked within this angle (in radians) either side of
+    /// the facing direction. Once locked the target can be in any direction
+    pub half_angle: Option<f64>,
+    #[serde(skip)]
+    target: Option<EntityId>,
+}
+
+impl LockOn {
+    /// Creates a lock-on that isn't locked onto anything
+    pub fn new(range: f64, half_angle: Option<f64>) -> Self {
+        Self {
+            range,
+            half_angle,
+            target: None,
+        }
+    }
+
+    /// The currently locked target
+    pub fn target(&self) -> Option<EntityId> {
+        self.target
+    }
+
+    /// Locks onto `target`, or breaks the lock with `None`
+    pub fn set_target(&mut self, target: Option<EntityId>) {
+        self.target = target;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn space(state: ButtonState) -> Input {
+        Input::Button(ButtonArgs {
+            state,
+            button: Button::Keyboard(Key::Space),
+            scancode: None,
+        })
+    }
+
+    #[test]
+    fn aim_sticks_to_last_target() {
+        let mut assist = AimAssist::new(0.5, 100.0, 0.5, 0.2);
+        let origin = [0.0, 0.0].into();
+        let target = vec![(EntityId(0), Point::from([10.0, 0.0]))];
+
+        // Just outside of the cone with no sticky target
+        let aim = [0.6f64.cos(), 0.6f64.sin()];
+        assert_eq!(assist.apply(origin, aim, target.clone()), aim);
+
+        // Once assisted towards, it stays assisted slightly outside the cone
+        assist.apply(origin, [1.0, 0.1], target.clone());
+        assert_eq!(assist.sticky_target(), Some(EntityId(0)));
+        let assisted = assist.apply(origin, aim, target);
+        assert!((f64::atan2(assisted[1], assisted[0]) - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tap_within_one_poll_is_not_missed() {
+        let mut binding = PolledButton::new(SingleButton::Keyboard(Key::Space));
+
+        binding.maybe_to_action(&space(ButtonState::Press));
+        binding.maybe_to_action(&space(ButtonState::Release));
+        assert!(!binding.is_held());
+        assert_eq!(
+            binding.poll(),
+            ButtonPoll {
+                down: true,
+                tapped: true
+            }
+        );
+        // The tap is consumed
+        assert_eq!(binding.poll(), ButtonPoll::default());
+
+        binding.coalesce = false;
+        binding.maybe_to_action(&space(ButtonState::Press));
+        binding.maybe_to_action(&space(ButtonState::Release));
+        assert_eq!(
+            binding.poll(),
+            ButtonPoll {
+                down: false,
+                tapped: true
+            }
+        );
+    }
+}
@@ -13,16 +13,25 @@ pub mod effect;
 pub mod error;
 /// Game objects
 pub mod gameobject;
+/// Controller rumble
+#[cfg(feature = "haptics")]
+pub mod haptics;
 /// Player input
 pub mod input;
 /// Rendering everything
 pub mod rendering;
+/// Compressing and decompressing save data
+pub mod save;
 /// The scene wrapping game objects
 pub mod scene;
+/// Layered scenes, i.e. a pause menu over gameplay
+pub mod scene_stack;
+/// Fixed timestep simulation helpers
+pub mod timestep;
 /// Generic types
 pub mod types;
 
-pub use app::App;
+pub use app::{build_window, AntiAliasing, App, SampledWindow};
 pub use error::{PhysicsError, PhysicsResult, ScarabError, ScarabResult};
 pub use glutin_window::GlutinWindow;
 pub use winit::dpi::LogicalSize;
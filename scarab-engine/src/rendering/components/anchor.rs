@@ -0,0 +1,122 @@
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A point on the window that screen-space UI elements can be attached to
+pub enum Anchor {
+    /// The top left corner of the window
+    TopLeft,
+    /// The middle of the top edge of the window
+    Top,
+    /// The top right corner of the window
+    TopRight,
+    /// The middle of the left edge of the window
+    Left,
+    /// The center of the window
+    Center,
+    /// The middle of the right edge of the window
+    Right,
+    /// The bottom left corner of the window
+    BottomLeft,
+    /// The middle of the bottom edge of the window
+    Bottom,
+    /// The bottom right corner of the window
+    BottomRight,
+}
+
+impl Anchor {
+    /// The anchor's position as a fraction of the width and height of a rectangle
+    /// i.e. `TopLeft` is `[0.0, 0.0]` and `BottomRight` is `[1.0, 1.0]`
+    pub fn fraction(&self) -> [Scalar; 2] {
+        match self {
+            Anchor::TopLeft => [0.0, 0.0],
+            Anchor::Top => [0.5, 0.0],
+            Anchor::TopRight => [1.0, 0.0],
+            Anchor::Left => [0.0, 0.5],
+            Anchor::Center => [0.5, 0.5],
+            Anchor::Right => [1.0, 0.5],
+            Anchor::BottomLeft => [0.0, 1.0],
+            Anchor::Bottom => [0.5, 1.0],
+            Anchor::BottomRight => [1.0, 1.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A rectangle in screen space that stays attached to an [Anchor] as the window resizes.
+///
+/// The point of the rectangle matching the anchor (i.e. its own top right corner for `TopRight`)
+/// is placed on the window's anchor point, then moved by `offset`.
+/// ```
+/// use scarab_engine::rendering::components::anchor::{Anchor, AnchoredRect};
+///
+/// // 10 points in from the right edge and 5 points down from the top
+/// let hud = AnchoredRect::new(Anchor::TopRight, [-10.0, 5.0], [50.0, 8.0]);
+/// assert_eq!(hud.resolve([200.0, 100.0]), [140.0, 5.0, 50.0, 8.0]);
+/// ```
+pub struct AnchoredRect {
+    /// The window point this rectangle is attached to
+    pub anchor: Anchor,
+    /// The offset (in points) from the anchor point. +x is right and +y is down
+    pub offset: [Scalar; 2],
+    /// The width and height of the rectangle (in points)
+    pub size: [Scalar; 2],
+}
+
+impl AnchoredRect {
+    /// Creates a new anchored rectangle
+    pub fn new(anchor: Anchor, offset: [Scalar; 2], size: [Scalar; 2]) -> Self {
+        Self {
+            anchor,
+            offset,
+            size,
+        }
+    }
+
+    /// Gives the `[x, y, w, h]` screen rectangle for the current window size.
+    /// The result can be drawn with the context's untransformed `ctx.transform`
+    pub fn resolve(&self, window_size: [Scalar; 2]) -> [Scalar; 4] {
+        let [fx, fy] = self.anchor.fraction();
+        [
+            window_size[0] * fx - self.size[0] * fx + self.offset[0],
+            window_size[1] * fy - self.size[1] * fy + self.offset[1],
+            self.size[0],
+            self.size[1],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn top_right_keeps_distance_from_right_edge_on_resize() {
+        let rect = AnchoredRect::new(Anchor::TopRight, [-10.0, 5.0], [50.0, 8.0]);
+
+        for window_size in [[800.0, 600.0], [1024.0, 768.0], [320.0, 180.0]] {
+            let [x, y, w, _h] = rect.resolve(window_size);
+            assert_eq!(window_size[0] - (x + w), 10.0);
+            assert_eq!(y, 5.0);
+        }
+    }
+
+    #[test]
+    fn center_stays_centered_on_resize() {
+        let rect = AnchoredRect::new(Anchor::Center, [0.0, 0.0], [40.0, 20.0]);
+
+        assert_eq!(rect.resolve([800.0, 600.0]), [380.0, 290.0, 40.0, 20.0]);
+        assert_eq!(rect.resolve([400.0, 200.0]), [180.0, 90.0, 40.0, 20.0]);
+    }
+
+    #[test]
+    fn bottom_left_keeps_distance_from_bottom_edge_on_resize() {
+        let rect = AnchoredRect::new(Anchor::BottomLeft, [4.0, -6.0], [30.0, 10.0]);
+
+        for window_size in [[800.0, 600.0], [640.0, 360.0]] {
+            let [x, y, _w, h] = rect.resolve(window_size);
+            assert_eq!(x, 4.0);
+            assert_eq!(window_size[1] - (y + h), 6.0);
+        }
+    }
+}
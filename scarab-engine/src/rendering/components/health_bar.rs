@@ -0,0 +1,195 @@
+use graphics::{
+    math::Matrix2d,
+    types::{Color, Rectangle, Scalar},
+    Graphics,
+};
+use serde::{Deserialize, Serialize};
+
+use super::progress_bar;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// How a [HealthBar] animates to a new value instead of snapping to it
+pub struct BarSmoothing {
+    /// How long in seconds the bar takes to ease to a new value
+    pub duration: f64,
+    /// A ghost bar of the health just lost waits this long in seconds before draining down
+    /// after the main bar. `None` doesn't draw a ghost
+    pub ghost_delay: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A fraction easing from one value to another over time
+struct EasedFraction {
+    from: Scalar,
+    to: Scalar,
+    /// Negative while waiting to start
+    elapsed: f64,
+}
+
+impl EasedFraction {
+    fn at(value: Scalar) -> Self {
+        Self {
+            from: value,
+            to: value,
+            elapsed: 0.0,
+        }
+    }
+
+    fn value(&self, duration: f64) -> Scalar {
+        if duration <= 0.0 {
+            return self.to;
+        }
+        let t = (self.elapsed / duration).clamp(0.0, 1.0);
+        // Ease out, so the change is noticeable straight away and settles gently
+        let eased = 1.0 - (1.0 - t) * (1.0 - t);
+        self.from + (self.to - self.from) * eased
+    }
+
+    /// Starts easing from the current value to `to` after `delay` seconds
+    fn retarget(&mut self, to: Scalar, delay: f64, duration: f64) {
+        self.from = self.value(duration);
+        self.to = to;
+        self.elapsed = -delay;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A left to right health bar that drains smoothly, with an optional ghost of the damage taken
+/// lagging behind it (like a fighting game's health bars)
+pub struct HealthBar {
+    /// The color of the health remaining
+    pub fill_color: Color,
+    /// The color of the ghost bar of the health just lost
+    pub ghost_color: Color,
+    smoothing: Option<BarSmoothing>,
+    displayed: EasedFraction,
+    ghost: EasedFraction,
+}
+
+impl HealthBar {
+    /// A full health bar that snaps to new values
+    pub fn new(fill_color: Color, ghost_color: Color) -> Self {
+        Self {
+            fill_color,
+            ghost_color,
+            smoothing: None,
+            displayed: EasedFraction::at(1.0),
+            ghost: EasedFraction::at(1.0),
+        }
+    }
+
+    /// Sets how the bar animates to new values. `None` snaps to them
+    pub fn set_smoothing(&mut self, smoothing: Option<BarSmoothing>) {
+        self.smoothing = smoothing;
+    }
+
+    /// How the bar animates to new values
+    pub fn smoothing(&self) -> Option<&BarSmoothing> {
+        self.smoothing.as_ref()
+    }
+
+    fn duration(&self) -> f64 {
+        self.smoothing.map(|s| s.duration).unwrap_or(0.0)
+    }
+
+    /// Updates the bar towards the true health `fraction` (see
+    /// [Health::fraction](crate::types::Health::fraction)) over `dt` seconds
+    pub fn update(&mut self, fraction: Scalar, dt: f64) {
+        let duration = self.duration();
+        if fraction != self.displayed.to {
+            let lost = fraction < self.displayed.to;
+            self.displayed.retarget(fraction, 0.0, duration);
+            match self.smoothing.and_then(|s| s.ghost_delay) {
+                // Another hit before the ghost drains restarts its wait from where it was
+                Some(delay) if lost => self.ghost.retarget(fraction, delay, duration),
+                // Otherwise it just follows the bar, hidden behind it
+                _ => self.ghost = self.displayed,
+            }
+        }
+        self.displayed.elapsed += dt;
+        self.ghost.elapsed += dt;
+    }
+
+    /// The fraction of the bar currently filled
+    pub fn displayed_fraction(&self) -> Scalar {
+        self.displayed.value(self.duration())
+    }
+
+    /// The fraction of the bar covered by the ghost, behind the filled part.
+    /// `None` if there's no ghost
+    pub fn ghost_fraction(&self) -> Option<Scalar> {
+        let ghost = self.ghost.value(self.duration());
+        (ghost > self.displayed_fraction()).then_some(ghost)
+    }
+
+    /// Draws the bar filling `rect`
+    pub fn draw<G: Graphics>(&self, rect: Rectangle, transform: Matrix2d, g: &mut G) {
+        let [x, y, w, h] = rect;
+        if let Some(ghost) = self.ghost_fraction() {
+            let ghost_rect = progress_bar::left_to_right(w, h, ghost, [x, y].into());
+            graphics::rectangle(self.ghost_color, ghost_rect, transform, g);
+        }
+        let fill_rect = progress_bar::left_to_right(w, h, self.displayed_fraction(), [x, y].into());
+        graphics::rectangle(self.fill_color, fill_rect, transform, g);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn smoothed_bar(ghost_delay: Option<f64>) -> HealthBar {
+        let mut bar = HealthBar::new([1.0, 0.0, 0.0, 1.0], [1.0; 4]);
+        bar.set_smoothing(Some(BarSmoothing {
+            duration: 0.4,
+            ghost_delay,
+        }));
+        bar
+    }
+
+    #[test]
+    fn hit_eases_displayed_value_over_duration() {
+        let mut bar = smoothed_bar(None);
+        bar.update(0.5, 0.0);
+        // Hasn't moved yet, rather than jumping straight there
+        assert_eq!(bar.displayed_fraction(), 1.0);
+
+        let mut displayed = vec![bar.displayed_fraction()];
+        for _ in 0..4 {
+            bar.update(0.5, 0.1);
+            displayed.push(bar.displayed_fraction());
+        }
+        assert!(displayed.windows(2).all(|w| w[1] < w[0]));
+        assert!(displayed[1] > 0.5 && displayed[3] > 0.5);
+        assert!((displayed[4] - 0.5).abs() < 1e-9);
+
+        bar.update(0.5, 0.1);
+        assert!((bar.displayed_fraction() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ghost_waits_then_drains_after_the_bar() {
+        let mut bar = smoothed_bar(Some(0.2));
+        bar.update(0.5, 0.2);
+        // The bar drains while the ghost holds at the old value
+        assert_eq!(bar.ghost_fraction(), Some(1.0));
+        bar.update(0.5, 0.3);
+        assert!((bar.displayed_fraction() - 0.5).abs() < 1e-9);
+        let ghost = bar.ghost_fraction().unwrap();
+        assert!(ghost < 1.0 && ghost > 0.5);
+        bar.update(0.5, 0.2);
+        assert_eq!(bar.ghost_fraction(), None);
+
+        // Healing doesn't leave a ghost behind
+        bar.update(0.8, 0.1);
+        assert_eq!(bar.ghost_fraction(), None);
+    }
+
+    #[test]
+    fn without_smoothing_bar_snaps() {
+        let mut bar = HealthBar::new([1.0; 4], [1.0; 4]);
+        bar.update(0.25, 0.0);
+        assert_eq!(bar.displayed_fraction(), 0.25);
+        assert_eq!(bar.ghost_fraction(), None);
+    }
+}
@@ -1,2 +1,6 @@
+/// Positions screen-space UI elements relative to the edges of the window
+pub mod anchor;
+/// A health bar that drains smoothly instead of snapping to new values
+pub mod health_bar;
 /// Renders a rectangular area that fills up according to a given fraction
 pub mod progress_bar;
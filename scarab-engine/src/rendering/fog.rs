@@ -0,0 +1,87 @@
+use graphics::{types::Scalar, Context};
+use opengl_graphics::GlGraphics;
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+use super::Camera;
+use crate::types::physbox::PhysBox;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A circular area that's visible through the fog of war, usually carried by an entity
+pub struct VisibilityLight {
+    /// The center of the visible area in world coordinates
+    pub center: Point,
+    /// How far from the center is fully visible
+    pub radius: Scalar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A soft fog of war that darkens everything outside of the union of [VisibilityLight]s.
+/// This doesn't cast shadows, anything within a light's radius is visible
+pub struct FogOfWar {
+    /// How dark the fog is in [0, 1], 1 hides everything outside of the lights completely
+    pub darkness: f64,
+    /// The distance past a light's radius over which it fades into the fog
+    pub falloff: Scalar,
+    /// The size (in pixels) of the squares the fog is drawn with. Smaller squares give smoother
+    /// edges at the cost of drawing more of them
+    pub tile_size: Scalar,
+}
+
+impl FogOfWar {
+    /// How bright the world is at `pos` in [1 - darkness, 1], 1 being fully visible
+    pub fn brightness_at(&self, pos: Point, lights: &[VisibilityLight]) -> f64 {
+        let darkness = self.darkness.clamp(0.0, 1.0);
+        let visibility = lights
+            .iter()
+            .map(|light| {
+                let distance = f64::hypot(pos.x - light.center.x, pos.y - light.center.y);
+                if distance <= light.radius {
+                    1.0
+                } else if self.falloff > 0.0 {
+                    (1.0 - (distance - light.radius) / self.falloff).max(0.0)
+                } else {
+                    0.0
+                }
+            })
+            .fold(0.0, f64::max);
+        1.0 - darkness * (1.0 - visibility)
+    }
+
+    /// Darkens the camera's view outside of the lights. Should be rendered after everything
+    /// else the fog covers
+    pub fn render(
+        &self,
+        lights: &[VisibilityLight],
+        camera: &Camera,
+        ctx: Context,
+        gl: &mut GlGraphics,
+    ) {
+        let view = camera.get_box();
+        let tile_size = self.tile_size.max(1.0);
+        let mut y = view.top_y();
+        while y < view.bottom_y() {
+            let h = tile_size.min(view.bottom_y() - y);
+            let mut x = view.left_x();
+            while x < view.right_x() {
+                let w = tile_size.min(view.right_x() - x);
+                let brightness = self.brightness_at([x + w / 2.0, y + h / 2.0].into(), lights);
+                if brightness < 1.0 {
+                    let renderables = PhysBox::new([x, y, w, h])
+                        .ok()
+                        .and_then(|tile| camera.box_renderables(&tile, ctx));
+                    if let Some((transform, rect)) = renderables {
+                        graphics::rectangle(
+                            [0.0, 0.0, 0.0, (1.0 - brightness) as f32],
+                            rect,
+                            transform,
+                            gl,
+                        );
+                    }
+                }
+                x += tile_size;
+            }
+            y += tile_size;
+        }
+    }
+}
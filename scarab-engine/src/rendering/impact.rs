@@ -0,0 +1,70 @@
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use super::Camera;
+use crate::{timestep::FixedTimestep, types::shared::Shared};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Shakes a [Camera]'s view around where it is, i.e. when a heavy attack lands
+/// (see [Camera::shake])
+pub struct CameraShake {
+    /// How far the view is moved in pixels at the start of the shake, fading to nothing by its end
+    pub amplitude: f64,
+    /// How long the shake lasts in seconds
+    pub duration: f64,
+    /// How many times per second the view swings back and forth
+    pub frequency: f64,
+}
+
+impl CameraShake {
+    /// How far the view is moved `elapsed` seconds into the shake
+    pub fn offset(&self, elapsed: f64) -> [f64; 2] {
+        if elapsed >= self.duration {
+            return [0.0, 0.0];
+        }
+        let strength = self.amplitude * (1.0 - elapsed / self.duration);
+        let phase = TAU * self.frequency * elapsed;
+        // Different rates on each axis so the view doesn't just slide back and forth along a line
+        [strength * phase.sin(), strength * (phase * 1.3).cos()]
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// What an impact feels like, any camera shake and hitstop it causes
+pub struct Impact {
+    /// Shakes the camera, if set
+    pub shake: Option<CameraShake>,
+    /// Freezes the simulation for this many seconds, if set (see [FixedTimestep::start_hitstop])
+    pub hitstop: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// An impact triggered when a [SpriteAnimation](super::sprite::SpriteAnimation) enters one of its
+/// frames, i.e. the frame that a heavy attack connects on
+/// (see [AnimationStateMachine::set_impact_queue](super::sprite::AnimationStateMachine::set_impact_queue))
+pub struct FrameImpact {
+    /// The frame that triggers the impact, counting from 0
+    pub frame: usize,
+    /// The impact triggered
+    pub impact: Impact,
+}
+
+/// A shared queue of impacts waiting to be felt, usually filled by animation frame events.
+/// Clones push to the same queue
+pub type ImpactQueue = Shared<Vec<Impact>>;
+
+impl ImpactQueue {
+    /// Shakes `camera` and stops `timestep` for every impact queued since the last call, emptying
+    /// the queue. Should be called once per update
+    pub fn apply(&self, camera: &mut Camera, timestep: &mut FixedTimestep) {
+        for impact in self.take() {
+            if let Some(shake) = impact.shake {
+                camera.shake(shake);
+            }
+            if let Some(hitstop) = impact.hitstop {
+                timestep.start_hitstop(hitstop);
+            }
+        }
+    }
+}
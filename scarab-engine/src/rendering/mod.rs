@@ -1,6 +1,7 @@
 /// All things generic rendering
 use graphics::{
-    types::{Color, Scalar},
+    math::Matrix2d,
+    types::{Color, Rectangle, Scalar},
     Context, Transformed,
 };
 use opengl_graphics::GlGraphics;
@@ -8,8 +9,8 @@ use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
 use shapes::Point;
 
-use self::registry::TextureRegistry;
-use crate::{error::RenderResult, types::physbox::PhysBox};
+use self::{impact::CameraShake, registry::TextureRegistry};
+use crate::{error::RenderResult, types::physbox::PhysBox, PhysicsResult};
 
 #[cfg(feature = "component-rendering")]
 /// For rendering generic reusable components
@@ -17,10 +18,16 @@ pub mod components;
 #[cfg(feature = "debug-rendering")]
 /// Rendering additional debugging info
 pub mod debug;
+/// A soft fog of war around visible areas
+pub mod fog;
+/// Camera shake and hitstop when attacks land
+pub mod impact;
 /// Rendering registries
 pub mod registry;
 /// Specifically for rendering sprites
 pub mod sprite;
+/// Rendering at a fixed internal resolution scaled up to the window
+pub mod upscale;
 
 /// A trait for types that control how another type is rendered
 pub trait View {
@@ -39,6 +46,29 @@ pub trait View {
     ) -> RenderResult<()>;
 }
 
+#[derive(Clone, Copy)]
+/// Everything a [Drawable] needs to know about the current render to draw itself in the right place
+pub struct RenderContext {
+    /// The graphics context for the whole window
+    pub ctx: Context,
+    /// Transforms world coordinates to screen coordinates, including the camera's scaling.
+    /// See [Camera::view_transform]
+    pub transform: Matrix2d,
+    /// The area of the window (in points) that the camera renders to, inside of the gutters
+    pub viewport: Rectangle,
+    /// The window's size in points
+    pub window_size: [f64; 2],
+    /// The fraction of a fixed update elapsed since the last one, for interpolating
+    pub alpha: f64,
+}
+
+/// A trait for custom things to draw, i.e. special effects or UI widgets,
+/// that aren't tied to a viewed type like a [View] is
+pub trait Drawable {
+    /// Draws on the screen using the current render's context
+    fn draw(&mut self, render_ctx: &RenderContext, gl: &mut GlGraphics) -> RenderResult<()>;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Controls how the pixel art is rendered and maintained through play
 ///
@@ -56,6 +86,78 @@ pub struct Camera {
     vertical_bar_width: f64,
     /// The width of each horizontal bar necessary to fill up the window (in points)
     horizontal_bar_height: f64,
+    /// Whether the points per pixel is limited to whole numbers, so that every pixel
+    /// covers the same number of points
+    #[serde(default)]
+    integer_scaling: bool,
+    /// How the camera tracks a target, if at all
+    #[serde(default)]
+    follow: Option<CameraFollow>,
+    /// The target position from the last call to [Camera::follow]
+    #[serde(skip)]
+    last_target: Option<Point>,
+    /// The camera's position before the last call to [Camera::follow]
+    #[serde(skip)]
+    previous_pos: Option<Point>,
+    /// The fraction of a fixed update elapsed since the last one, for the current render
+    #[serde(skip)]
+    render_alpha: f64,
+    /// The current shake and how far into it the camera is, if it's shaking
+    #[serde(skip)]
+    shake: Option<(CameraShake, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Controls how a [Camera] smoothly tracks a target position
+pub struct CameraFollow {
+    /// The fraction of the remaining distance to the target that the camera covers each update.
+    /// Should be in the range (0, 1]
+    pub lerp_factor: f64,
+    /// Time constants in seconds for the x and y axes, used instead of the lerp factor by
+    /// [Camera::follow_timed] so that the smoothing is the same whatever the frame rate.
+    /// Each time constant the camera covers about 63% of the remaining distance along that axis,
+    /// i.e. `[0.05, 0.3]` tracks horizontally much faster than vertically. 0 snaps along the axis
+    #[serde(default)]
+    pub time_constants: Option<[f64; 2]>,
+    /// If the target moves more than this distance (in pixels) between updates the camera snaps
+    /// directly to it instead of panning across everything in between (i.e. respawns or warps).
+    /// `None` never snaps
+    pub snap_distance: Option<f64>,
+    /// Where the target is kept within the view, as a fraction of the view's size from its top left.
+    /// i.e. `[0.5, 0.5]` keeps it centered and `[0.5, 0.66]` keeps it two-thirds of the way down
+    #[serde(default = "CameraFollow::default_anchor")]
+    pub anchor: [f64; 2],
+    /// Whether the camera follows the target along the x and y axes.
+    /// A locked axis stays wherever the camera is, i.e. a side-scroller that never moves vertically
+    #[serde(default = "CameraFollow::default_axes")]
+    pub axes: [bool; 2],
+    /// Only follow vertically while the target is grounded (see [Camera::follow_grounded]),
+    /// so the camera doesn't bob up and down with every jump but still moves to new floors
+    #[serde(default)]
+    pub vertical_only_grounded: bool,
+}
+
+impl CameraFollow {
+    fn default_anchor() -> [f64; 2] {
+        [0.5, 0.5]
+    }
+
+    fn default_axes() -> [bool; 2] {
+        [true, true]
+    }
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            lerp_factor: 0.1,
+            time_constants: None,
+            snap_distance: None,
+            anchor: Self::default_anchor(),
+            axes: Self::default_axes(),
+            vertical_only_grounded: false,
+        }
+    }
 }
 
 impl Camera {
@@ -67,6 +169,12 @@ impl Camera {
             window_size: window_size.clone(),
             vertical_bar_width: 0.0,
             horizontal_bar_height: 0.0,
+            integer_scaling: false,
+            follow: None,
+            last_target: None,
+            previous_pos: None,
+            render_alpha: 0.0,
+            shake: None,
         };
 
         s.set_window_size(&window_size);
@@ -87,20 +195,281 @@ impl Camera {
 
         self.window_size = [w_w, h_w];
         self.points_per_pixel = f64::min(w_w / self.physbox.size().w, h_w / self.physbox.size().h);
+        if self.integer_scaling {
+            // The window is at least as big as the camera so this is always at least 1
+            self.points_per_pixel = self.points_per_pixel.floor();
+        }
 
         self.vertical_bar_width = (w_w - self.physbox.size().w * self.points_per_pixel) / 2.0;
         self.horizontal_bar_height = (h_w - self.physbox.size().h * self.points_per_pixel) / 2.0;
     }
 
+    /// Sets whether the camera only scales its pixels up by whole numbers for crisp pixel art.
+    /// The camera's size is the internal resolution, scaled up as much as fits in the window
+    /// with the remainder letterboxed by the gutters. To render into a buffer of that size and
+    /// upscale it in one go instead, see
+    /// [InternalResolution::for_camera](upscale::InternalResolution::for_camera)
+    pub fn set_integer_scaling(&mut self, integer_scaling: bool) {
+        self.integer_scaling = integer_scaling;
+        let window_size = self.window_size;
+        self.set_window_size(&window_size);
+    }
+
+    /// Whether the camera only scales its pixels up by whole numbers
+    pub fn is_integer_scaling(&self) -> bool {
+        self.integer_scaling
+    }
+
+    /// The width of the vertical bars and the height of the horizontal bars on either
+    /// side of the rendered area (in points)
+    pub fn letterbox(&self) -> [f64; 2] {
+        [self.vertical_bar_width, self.horizontal_bar_height]
+    }
+
     /// Creates a trasnform matrix for the given point from world coordinates to screen coordinates
     pub fn transform(&self, ctx: &Context, pos: Point) -> [[f64; 3]; 2] {
-        let top_left = pos - *self.physbox.pos();
+        let top_left = pos - self.shaken_pos();
         let top_left_scaled = top_left * self.points_per_pixel.into()
             + [self.vertical_bar_width, self.horizontal_bar_height];
 
         ctx.transform.trans(top_left_scaled.x, top_left_scaled.y)
     }
 
+    /// Converts a position on the screen (in points, i.e. the mouse cursor) to world coordinates.
+    /// While the camera shakes this is where the shaken view shows the position
+    pub fn screen_to_world(&self, screen_pos: Point) -> Point {
+        let pos = self.shaken_pos();
+        [
+            (screen_pos.x - self.vertical_bar_width) / self.points_per_pixel + pos.x,
+            (screen_pos.y - self.horizontal_bar_height) / self.points_per_pixel + pos.y,
+        ]
+        .into()
+    }
+
+    /// The transform from world coordinates to screen coordinates for the camera's whole view.
+    /// Unlike [Camera::transform] this also scales by the points per pixel, so world sized
+    /// shapes can be drawn with it directly
+    pub fn view_transform(&self, ctx: &Context) -> Matrix2d {
+        let pos = self.shaken_pos();
+        ctx.transform
+            .trans(self.vertical_bar_width, self.horizontal_bar_height)
+            .scale(self.points_per_pixel, self.points_per_pixel)
+            .trans(-pos.x, -pos.y)
+    }
+
+    /// The area of the window (in points) that the camera renders to, inside of the gutters
+    pub fn viewport(&self) -> Rectangle {
+        let size = *self.physbox.size();
+        [
+            self.vertical_bar_width,
+            self.horizontal_bar_height,
+            size.w * self.points_per_pixel,
+            size.h * self.points_per_pixel,
+        ]
+    }
+
+    /// The context to give to [Drawable]s for the current render
+    pub fn render_context(&self, ctx: Context) -> RenderContext {
+        RenderContext {
+            ctx,
+            transform: self.view_transform(&ctx),
+            viewport: self.viewport(),
+            window_size: self.window_size,
+            alpha: self.render_alpha,
+        }
+    }
+
+    /// Sets how the camera tracks targets given to [Camera::follow]. `None` disables following
+    pub fn set_follow(&mut self, follow: Option<CameraFollow>) {
+        self.follow = follow;
+        self.last_target = None;
+    }
+
+    /// The camera's current follow settings
+    pub fn get_follow(&self) -> Option<&CameraFollow> {
+        self.follow.as_ref()
+    }
+
+    /// The center of the camera's view in world coordinates
+    pub fn center(&self) -> Point {
+        self.physbox.center()
+    }
+
+    /// Moves the camera so that its view is centered on `center`
+    pub fn set_center(&mut self, center: Point) {
+        let size = *self.physbox.size();
+        self.physbox.set_pos(center - [size.w / 2.0, size.h / 2.0]);
+    }
+
+    /// Centers and zooms the camera so that `bounds` (i.e. the whole level) exactly fits in
+    /// its view. The view keeps its aspect ratio, so `bounds` fills it along one axis and is
+    /// centered with equal margins along the other. Following afterwards keeps the new zoom
+    pub fn fit_bounds(&mut self, bounds: &PhysBox) -> PhysicsResult<()> {
+        let view = *self.physbox.size();
+        let bounds_size = *bounds.size();
+        let aspect = view.w / view.h;
+        let size = if bounds_size.w / bounds_size.h > aspect {
+            [bounds_size.w, bounds_size.w / aspect]
+        } else {
+            [bounds_size.h * aspect, bounds_size.h]
+        };
+        self.physbox.set_size(size.into())?;
+        self.set_center(bounds.center());
+        // Jumps straight there instead of interpolating from wherever the camera was
+        self.previous_pos = None;
+
+        let window_size = self.window_size;
+        self.set_window_size(&window_size);
+        Ok(())
+    }
+
+    /// Moves the camera's anchor point (its center by default) towards `target` according to its follow settings.
+    /// Should be called once per update. Does nothing if the camera isn't following.
+    ///
+    /// The first target after following is enabled, and any target that jumps further than
+    /// the snap distance, is snapped to immediately. Snapping also resets the interpolation
+    /// so that [Camera::interpolated_pos] doesn't pan across the jump either.
+    pub fn follow(&mut self, target: Point) {
+        self.follow_grounded(target, true)
+    }
+
+    /// Sets which axes the camera follows its target along. Does nothing if the camera isn't following
+    pub fn follow_axes(&mut self, x: bool, y: bool) {
+        if let Some(follow) = self.follow.as_mut() {
+            follow.axes = [x, y];
+        }
+    }
+
+    /// The same as [Camera::follow], but with whether the target is standing on the ground
+    /// for [CameraFollow::vertical_only_grounded]
+    pub fn follow_grounded(&mut self, target: Point, grounded: bool) {
+        self.follow_step(target, grounded, None)
+    }
+
+    /// The same as [Camera::follow_grounded], but smoothed over the `dt` seconds since the last
+    /// update by the follow's [time constants](CameraFollow::time_constants) if it has them
+    pub fn follow_timed(&mut self, target: Point, grounded: bool, dt: f64) {
+        self.follow_step(target, grounded, Some(dt))
+    }
+
+    fn follow_step(&mut self, target: Point, grounded: bool, dt: Option<f64>) {
+        let follow = match self.follow {
+            Some(f) => f,
+            None => return,
+        };
+
+        let should_snap = match (self.last_target, follow.snap_distance) {
+            (None, _) => true,
+            (Some(last), Some(snap)) => f64::hypot(target.x - last.x, target.y - last.y) > snap,
+            (Some(_), None) => false,
+        };
+
+        let [ax, ay] = follow.anchor;
+        let size = *self.physbox.size();
+        let anchor_offset = [size.w * ax, size.h * ay];
+
+        let current = *self.physbox.pos();
+        self.previous_pos = Some(current);
+        // Exponential smoothing covers the same distance in the same time however it's split up
+        let [fx, fy] = match (follow.time_constants, dt) {
+            (Some(time_constants), Some(dt)) => time_constants.map(|tau| {
+                if tau > 0.0 {
+                    1.0 - (-dt / tau).exp()
+                } else {
+                    1.0
+                }
+            }),
+            _ => [follow.lerp_factor; 2],
+        };
+        let mut new_pos = if should_snap {
+            target - anchor_offset
+        } else {
+            let anchored = current + anchor_offset;
+            let to_target = target - anchored;
+            anchored + [to_target.x * fx, to_target.y * fy] - anchor_offset
+        };
+        if !follow.axes[0] {
+            new_pos.x = current.x;
+        }
+        if !follow.axes[1] || (follow.vertical_only_grounded && !grounded) {
+            new_pos.y = current.y;
+        }
+        self.physbox.set_pos(new_pos);
+        if should_snap {
+            self.previous_pos = Some(new_pos);
+        }
+        self.last_target = Some(target);
+    }
+
+    /// The camera's position interpolated between the previous and current follow update.
+    /// `alpha` is the fraction of an update that has elapsed since the last one
+    pub fn interpolated_pos(&self, alpha: f64) -> Point {
+        let current = *self.physbox.pos();
+        self.previous_pos
+            .map_or(current, |previous| previous + (current - previous) * alpha)
+    }
+
+    /// Sets the fraction of a fixed update elapsed since the last one (i.e. [crate::timestep::FixedTimestep::alpha]).
+    /// Should be set before rendering so views can interpolate with [Camera::render_alpha]
+    pub fn set_render_alpha(&mut self, alpha: f64) {
+        self.render_alpha = alpha;
+    }
+
+    /// The fraction of a fixed update elapsed since the last one for the current render
+    pub fn render_alpha(&self) -> f64 {
+        self.render_alpha
+    }
+
+    /// Starts shaking the view, replacing the current shake unless it's currently stronger.
+    /// Only what's rendered moves, the camera's box stays where it is
+    pub fn shake(&mut self, shake: CameraShake) {
+        let current_strength = self
+            .shake
+            .map(|(current, elapsed)| current.amplitude * (1.0 - elapsed / current.duration))
+            .unwrap_or(0.0);
+        if shake.amplitude >= current_strength {
+            self.shake = Some((shake, 0.0));
+        }
+    }
+
+    /// The camera's current shake, if it's shaking
+    pub fn get_shake(&self) -> Option<&CameraShake> {
+        self.shake.as_ref().map(|(shake, _)| shake)
+    }
+
+    /// Advances the current shake by `dt` seconds, ending it once it's run its duration.
+    /// Should be called every update
+    pub fn tick_shake(&mut self, dt: f64) {
+        if let Some((shake, elapsed)) = self.shake.as_mut() {
+            *elapsed += dt;
+            if *elapsed >= shake.duration {
+                self.shake = None;
+            }
+        }
+    }
+
+    /// How far the view is currently moved by shaking, in pixels
+    pub fn shake_offset(&self) -> [f64; 2] {
+        self.shake
+            .map(|(shake, elapsed)| shake.offset(elapsed))
+            .unwrap_or([0.0, 0.0])
+    }
+
+    /// Where the view is drawn from, including any shake
+    fn shaken_pos(&self) -> Point {
+        *self.physbox.pos() + self.shake_offset()
+    }
+
+    /// The camera's position and dimensions in world coordinates
+    pub fn get_box(&self) -> &PhysBox {
+        &self.physbox
+    }
+
+    /// The window's size in points, for laying out screen-space elements
+    pub fn window_size(&self) -> [f64; 2] {
+        self.window_size
+    }
+
     /// The actual screen "points" per pixel-art-pixel for the camera
     pub fn points_per_pixel(&self) -> f64 {
         self.points_per_pixel
@@ -176,3 +545,245 @@ impl Camera {
         );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn following_camera(snap_distance: Option<f64>) -> Camera {
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [100.0, 50.0],
+        );
+        camera.set_follow(Some(CameraFollow {
+            lerp_factor: 0.5,
+            snap_distance,
+            ..Default::default()
+        }));
+        camera
+    }
+
+    #[test]
+    fn render_context_matches_view_transform() {
+        let mut camera = Camera::new(
+            PhysBox::new([20.0, 10.0, 100.0, 50.0]).unwrap(),
+            [250.0, 100.0],
+        );
+        camera.set_render_alpha(0.25);
+        let ctx = Context::new_abs(250.0, 100.0);
+        let render_ctx = camera.render_context(ctx);
+
+        assert_eq!(render_ctx.transform, camera.view_transform(&ctx));
+        assert_eq!(render_ctx.viewport, [25.0, 0.0, 200.0, 100.0]);
+        assert_eq!(render_ctx.window_size, [250.0, 100.0]);
+        assert_eq!(render_ctx.alpha, 0.25);
+
+        // A world position lands in the same place as with the per-position transform
+        let world = [50.0, 30.0];
+        let expected =
+            graphics::math::transform_pos(camera.transform(&ctx, world.into()), [0.0; 2]);
+        let actual = graphics::math::transform_pos(render_ctx.transform, world);
+        assert!((expected[0] - actual[0]).abs() < 1e-9 && (expected[1] - actual[1]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn screen_to_world_follows_shaken_view() {
+        let mut camera = Camera::new(
+            PhysBox::new([20.0, 10.0, 100.0, 50.0]).unwrap(),
+            [250.0, 100.0],
+        );
+        camera.shake(CameraShake {
+            amplitude: 4.0,
+            duration: 1.0,
+            frequency: 3.0,
+        });
+        camera.tick_shake(0.1);
+        assert_ne!(camera.shake_offset(), [0.0, 0.0]);
+
+        // Clicking where a world position is drawn picks that position
+        let ctx = Context::new();
+        let world: Point = [50.0, 30.0].into();
+        let drawn = graphics::math::transform_pos(camera.transform(&ctx, world), [0.0; 2]);
+        let picked = camera.screen_to_world(drawn.into());
+        assert!((picked.x - world.x).abs() < 1e-9 && (picked.y - world.y).abs() < 1e-9);
+    }
+
+    #[test]
+    fn integer_scaling_letterboxes_remainder() {
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 320.0, 180.0]).unwrap(),
+            [800.0, 600.0],
+        );
+        assert_eq!(camera.points_per_pixel(), 2.5);
+
+        camera.set_integer_scaling(true);
+        assert_eq!(camera.points_per_pixel(), 2.0);
+        // 640x360 rendered in the middle of the window
+        assert_eq!(camera.letterbox(), [80.0, 120.0]);
+
+        camera.set_window_size(&[1000.0, 600.0]);
+        assert_eq!(camera.points_per_pixel(), 3.0);
+        assert_eq!(camera.letterbox(), [20.0, 30.0]);
+    }
+
+    #[test]
+    fn fitting_wide_bounds_fits_width_and_centers_vertically() {
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [900.0, 400.0],
+        );
+        camera
+            .fit_bounds(&PhysBox::new([10.0, 20.0, 300.0, 100.0]).unwrap())
+            .unwrap();
+
+        // All 300 wide and twice as wide as tall, like before
+        assert_eq!(
+            *camera.get_box(),
+            PhysBox::new([10.0, -5.0, 300.0, 150.0]).unwrap()
+        );
+        // 25 of margin above and below the bounds
+        assert_eq!(camera.get_box().top_y(), 20.0 - 25.0);
+        assert_eq!(camera.get_box().bottom_y(), 120.0 + 25.0);
+        assert_eq!(camera.center(), [160.0, 70.0].into());
+
+        // The window is wider than the view, so it's still letterboxed at the sides
+        let ppp = camera.points_per_pixel();
+        assert_eq!(ppp, 400.0 / 150.0);
+        assert_eq!(camera.letterbox(), [(900.0 - 300.0 * ppp) / 2.0, 0.0]);
+    }
+
+    #[test]
+    fn follow_anchor_offsets_target_in_view() {
+        let mut camera = following_camera(None);
+        camera.set_follow(Some(CameraFollow {
+            lerp_factor: 0.5,
+            snap_distance: None,
+            anchor: [0.5, 0.75],
+            ..Default::default()
+        }));
+
+        camera.follow([200.0, 200.0].into());
+        // A 100x50 view with the target 3/4 of the way down instead of centered
+        assert_eq!(camera.get_box().pos(), &[150.0, 162.5].into());
+        assert_eq!(camera.center(), [200.0, 187.5].into());
+
+        camera.follow([220.0, 200.0].into());
+        assert_eq!(camera.get_box().pos(), &[160.0, 162.5].into());
+    }
+
+    #[test]
+    fn follow_snaps_to_first_target() {
+        let mut camera = following_camera(Some(100.0));
+
+        camera.follow([200.0, 200.0].into());
+        assert_eq!(camera.center(), [200.0, 200.0].into());
+    }
+
+    #[test]
+    fn follow_lerps_small_moves() {
+        let mut camera = following_camera(Some(100.0));
+        camera.follow([50.0, 25.0].into());
+
+        camera.follow([60.0, 25.0].into());
+        assert_eq!(camera.center(), [55.0, 25.0].into());
+        assert_eq!(camera.interpolated_pos(0.0), [0.0, 0.0].into());
+    }
+
+    #[test]
+    fn follow_snaps_when_target_teleports() {
+        let mut camera = following_camera(Some(100.0));
+        camera.follow([50.0, 25.0].into());
+
+        camera.follow([500.0, 25.0].into());
+        assert_eq!(camera.center(), [500.0, 25.0].into());
+        // The interpolation is also skipped for the snap
+        assert_eq!(camera.interpolated_pos(0.0), *camera.get_box().pos());
+    }
+
+    #[test]
+    fn follow_never_snaps_without_snap_distance() {
+        let mut camera = following_camera(None);
+        camera.follow([50.0, 25.0].into());
+
+        camera.follow([550.0, 25.0].into());
+        assert_eq!(camera.center(), [300.0, 25.0].into());
+    }
+
+    #[test]
+    fn locked_vertical_axis_stays_fixed_during_jump() {
+        let mut camera = following_camera(None);
+        camera.follow_axes(true, false);
+        camera.set_center([50.0, 25.0].into());
+
+        let jump = [
+            (60.0, 25.0),
+            (70.0, 10.0),
+            (80.0, 0.0),
+            (90.0, 10.0),
+            (100.0, 25.0),
+        ];
+        let mut last_x = camera.center().x;
+        for (x, y) in jump {
+            camera.follow([x, y].into());
+            assert_eq!(camera.center().y, 25.0);
+            assert!(camera.center().x > last_x);
+            last_x = camera.center().x;
+        }
+    }
+
+    #[test]
+    fn timed_follow_converges_on_fast_axis_first_at_any_frame_rate() {
+        let follow_diagonal = |dt: f64| {
+            let mut camera = following_camera(None);
+            camera.set_follow(Some(CameraFollow {
+                time_constants: Some([0.05, 0.4]),
+                ..Default::default()
+            }));
+            camera.follow_timed([50.0, 25.0].into(), true, dt);
+            let target: Point = [90.0, 65.0].into();
+            // The time that each axis gets within a pixel of the target
+            let (mut x_settled, mut y_settled) = (None, None);
+            let mut elapsed = 0.0;
+            while y_settled.is_none() {
+                camera.follow_timed(target, true, dt);
+                elapsed += dt;
+                let center = camera.center();
+                if x_settled.is_none() && (target.x - center.x).abs() < 1.0 {
+                    x_settled = Some(elapsed);
+                }
+                if (target.y - center.y).abs() < 1.0 {
+                    y_settled = Some(elapsed);
+                }
+            }
+            (x_settled.unwrap(), y_settled.unwrap())
+        };
+
+        let (x_settled, y_settled) = follow_diagonal(1.0 / 60.0);
+        assert!(x_settled < 0.25);
+        assert!(y_settled > 1.2);
+        assert!(x_settled * 5.0 < y_settled);
+        // The same smoothing at a quarter of the frame rate
+        let (slow_x, slow_y) = follow_diagonal(1.0 / 15.0);
+        assert!((slow_x - x_settled).abs() < 0.07);
+        assert!((slow_y - y_settled).abs() < 0.07);
+    }
+
+    #[test]
+    fn vertical_only_follows_when_grounded() {
+        let mut camera = following_camera(None);
+        camera.set_follow(Some(CameraFollow {
+            lerp_factor: 1.0,
+            vertical_only_grounded: true,
+            ..Default::default()
+        }));
+        camera.follow_grounded([50.0, 25.0].into(), true);
+
+        // Airborne
+        camera.follow_grounded([60.0, 5.0].into(), false);
+        assert_eq!(camera.center(), [60.0, 25.0].into());
+
+        // Landed on a higher floor
+        camera.follow_grounded([70.0, 10.0].into(), true);
+        assert_eq!(camera.center(), [70.0, 10.0].into());
+    }
+}
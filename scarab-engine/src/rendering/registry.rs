@@ -46,6 +46,8 @@ pub struct TextureRegistry {
     default_path_texture: PathTexture,
     #[derivative(Debug = "ignore")]
     textures: HashMap<PathBuf, Texture>,
+    /// Fallback textures for missing textures under a directory, instead of the global default
+    namespace_fallbacks: HashMap<PathBuf, PathBuf>,
 }
 
 impl TextureRegistry {
@@ -73,14 +75,38 @@ impl TextureRegistry {
             assets_path,
             default_path_texture,
             textures,
+            namespace_fallbacks: HashMap::new(),
         })
     }
 
     /// Gets a loaded texture a the given path or the default texture
     pub fn get_or_default(&self, path: &PathBuf) -> &Texture {
-        self.textures
-            .get(path)
-            .unwrap_or_else(|| &self.default_path_texture.texture())
+        self.get_or_fallback(path, None)
+    }
+
+    /// Gets a loaded texture at the given path, or if it's missing the first loaded one of:
+    /// `fallback` (i.e. a placeholder specific to an animation), the fallback for the
+    /// path's namespace (see [TextureRegistry::set_namespace_fallback]) or the default texture
+    pub fn get_or_fallback(&self, path: &PathBuf, fallback: Option<&PathBuf>) -> &Texture {
+        let resolved = resolve_path(
+            |p| self.get(p).is_some(),
+            path,
+            fallback,
+            &self.namespace_fallbacks,
+            self.default_path_texture.path(),
+        );
+        self.get(resolved)
+            .unwrap_or_else(|| self.default_path_texture.texture())
+    }
+
+    /// Sets the fallback for missing textures whose paths are within `namespace`
+    /// (i.e. "tiles" for "tiles/grass.png"), so different kinds of textures can have
+    /// distinct placeholders. The fallback should already be loaded. `None` removes it
+    pub fn set_namespace_fallback(&mut self, namespace: PathBuf, fallback: Option<PathBuf>) {
+        match fallback {
+            Some(fallback) => self.namespace_fallbacks.insert(namespace, fallback),
+            None => self.namespace_fallbacks.remove(&namespace),
+        };
     }
 
     /// Gets a texture at the given path if it's already loaded
@@ -111,12 +137,37 @@ impl TextureRegistry {
 impl TryFrom<TextureList> for TextureRegistry {
     type Error = ScarabError;
     fn try_from(value: TextureList) -> ScarabResult<Self> {
-        Self::new(
+        let mut registry = Self::new(
             value.assets_path,
             value.default_texture_path,
             &value.other_texture_paths,
-        )
+        )?;
+        registry.namespace_fallbacks = value.namespace_fallbacks;
+        Ok(registry)
+    }
+}
+
+/// Picks the path of the texture to use for `path` out of the loaded ones, see
+/// [TextureRegistry::get_or_fallback]. The most specific namespace containing the path wins
+fn resolve_path<'a, F: Fn(&PathBuf) -> bool>(
+    is_loaded: F,
+    path: &'a PathBuf,
+    fallback: Option<&'a PathBuf>,
+    namespace_fallbacks: &'a HashMap<PathBuf, PathBuf>,
+    default_path: &'a PathBuf,
+) -> &'a PathBuf {
+    if is_loaded(path) {
+        return path;
+    }
+    if let Some(fallback) = fallback.filter(|f| is_loaded(f)) {
+        return fallback;
     }
+    namespace_fallbacks
+        .iter()
+        .filter(|(namespace, fallback)| path.starts_with(namespace) && is_loaded(fallback))
+        .max_by_key(|(namespace, _)| namespace.components().count())
+        .map(|(_, fallback)| fallback)
+        .unwrap_or(default_path)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,6 +176,8 @@ pub struct TextureList {
     assets_path: PathBuf,
     default_texture_path: PathBuf,
     other_texture_paths: Vec<PathBuf>,
+    #[serde(default)]
+    namespace_fallbacks: HashMap<PathBuf, PathBuf>,
 }
 
 impl From<TextureRegistry> for TextureList {
@@ -133,6 +186,7 @@ impl From<TextureRegistry> for TextureList {
             assets_path: value.assets_path,
             default_texture_path: value.default_path_texture.path().clone(),
             other_texture_paths: value.textures.keys().map(|k| k.clone()).collect(),
+            namespace_fallbacks: value.namespace_fallbacks,
         }
     }
 }
@@ -143,6 +197,60 @@ impl From<&TextureRegistry> for TextureList {
             assets_path: value.assets_path.clone(),
             default_texture_path: value.default_path_texture.path().clone(),
             other_texture_paths: value.textures.keys().map(|k| k.clone()).collect(),
+            namespace_fallbacks: value.namespace_fallbacks.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_texture_resolves_to_most_specific_fallback() {
+        let loaded: Vec<PathBuf> = ["hero.png", "hero-missing.png", "tile-missing.png"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let is_loaded = |p: &PathBuf| loaded.contains(p);
+        let default = PathBuf::from("texture-default.png");
+        let mut namespaces = HashMap::new();
+        let missing = PathBuf::from("characters/villain.png");
+        let hero_fallback = PathBuf::from("hero-missing.png");
+
+        // Loaded textures are used as is
+        let hero = PathBuf::from("hero.png");
+        let resolved = resolve_path(
+            is_loaded,
+            &hero,
+            Some(&hero_fallback),
+            &namespaces,
+            &default,
+        );
+        assert_eq!(resolved, &hero);
+
+        // Missing with an animation specific fallback
+        let resolved = resolve_path(
+            is_loaded,
+            &missing,
+            Some(&hero_fallback),
+            &namespaces,
+            &default,
+        );
+        assert_eq!(resolved, &hero_fallback);
+
+        // The global default otherwise
+        assert_eq!(
+            resolve_path(is_loaded, &missing, None, &namespaces, &default),
+            &default
+        );
+
+        // Or the namespace's fallback
+        namespaces.insert(PathBuf::from("tiles"), PathBuf::from("tile-missing.png"));
+        let tile = PathBuf::from("tiles/grass.png");
+        assert_eq!(
+            resolve_path(is_loaded, &tile, None, &namespaces, &default),
+            &PathBuf::from("tile-missing.png")
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use graphics::types::{Color, Scalar};
+use serde::{Deserialize, Serialize};
+
+use crate::types::DEFAULT_EPSILON;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// How an entity's sprite reacts to taking damage and dying
+pub struct HitEffectsConfig {
+    /// How long in seconds the sprite flashes for after taking damage. 0 doesn't flash
+    pub flash_duration: f64,
+    /// The color added over the sprite while it flashes, full brightness white is the usual
+    pub flash_color: Color,
+    /// How long in seconds the sprite takes to dissolve after dying. The entity isn't despawned
+    /// until it's done, 0 despawns it right away
+    pub dissolve_duration: f64,
+    /// The sprite's scale once it's fully dissolved, i.e. 0 shrinks it away to nothing
+    pub dissolve_scale: Scalar,
+}
+
+impl Default for HitEffectsConfig {
+    fn default() -> Self {
+        Self {
+            flash_duration: 0.1,
+            flash_color: [1.0; 4],
+            dissolve_duration: 0.5,
+            dissolve_scale: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How a sprite is drawn this frame with its hit effects applied
+pub struct HitEffectsStyle {
+    /// The opacity to draw the sprite with
+    pub alpha: f64,
+    /// How much to scale the sprite by about its center
+    pub scale: Scalar,
+    /// The color to add over the sprite, if it's flashing
+    pub flash: Option<Color>,
+}
+
+impl HitEffectsStyle {
+    /// The sprite as it's normally drawn, without any effects
+    pub const NONE: Self = Self {
+        alpha: 1.0,
+        scale: 1.0,
+        flash: None,
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The flash after taking damage and the dissolve after dying for an entity's sprite
+/// (see [Entity::set_hit_effects](crate::gameobject::entity::Entity::set_hit_effects))
+pub struct HitEffects {
+    config: HitEffectsConfig,
+    /// The time remaining on the current flash
+    #[serde(default)]
+    flash_remaining: f64,
+    /// The time since the dissolve started, if it has
+    #[serde(default)]
+    dissolve_elapsed: Option<f64>,
+}
+
+impl HitEffects {
+    /// Hit effects that haven't been triggered yet
+    pub fn new(config: HitEffectsConfig) -> Self {
+        Self {
+            config,
+            flash_remaining: 0.0,
+            dissolve_elapsed: None,
+        }
+    }
+
+    /// How the sprite reacts to taking damage and dying
+    pub fn config(&self) -> &HitEffectsConfig {
+        &self.config
+    }
+
+    /// Starts the flash over from the beginning
+    pub fn flash(&mut self) {
+        self.flash_remaining = self.config.flash_duration;
+    }
+
+    /// Starts dissolving, unless it already has
+    pub fn start_dissolve(&mut self) {
+        self.dissolve_elapsed.get_or_insert(0.0);
+    }
+
+    /// Counts down the flash and dissolve by `dt` seconds
+    pub fn advance(&mut self, dt: f64) {
+        self.flash_remaining = (self.flash_remaining - dt).max(0.0);
+        if let Some(elapsed) = self.dissolve_elapsed.as_mut() {
+            *elapsed = (*elapsed + dt).min(self.config.dissolve_duration);
+        }
+    }
+
+    /// Whether the sprite is currently flashing
+    pub fn is_flashing(&self) -> bool {
+        // Counting down by the tick length can leave a sliver of time from floating point error
+        self.flash_remaining > DEFAULT_EPSILON
+    }
+
+    /// How far through the dissolve the sprite is in [0, 1], `None` if it isn't dissolving
+    pub fn dissolve_progress(&self) -> Option<f64> {
+        self.dissolve_elapsed.map(|elapsed| {
+            if self.config.dissolve_duration > 0.0 {
+                (elapsed / self.config.dissolve_duration).min(1.0)
+            } else {
+                1.0
+            }
+        })
+    }
+
+    /// Whether the dissolve has played all the way through
+    pub fn is_dissolved(&self) -> bool {
+        self.dissolve_elapsed
+            .map(|elapsed| elapsed >= self.config.dissolve_duration - DEFAULT_EPSILON)
+            .unwrap_or(false)
+    }
+
+    /// How the sprite should be drawn right now
+    pub fn style(&self) -> HitEffectsStyle {
+        let progress = self.dissolve_progress().unwrap_or(0.0);
+        HitEffectsStyle {
+            alpha: 1.0 - progress,
+            scale: 1.0 + (self.config.dissolve_scale - 1.0) * progress,
+            flash: self.is_flashing().then_some(self.config.flash_color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dissolve_fades_and_scales_towards_the_end() {
+        let mut effects = HitEffects::new(HitEffectsConfig {
+            dissolve_duration: 1.0,
+            dissolve_scale: 0.5,
+            ..Default::default()
+        });
+        assert_eq!(effects.style(), HitEffectsStyle::NONE);
+
+        effects.start_dissolve();
+        effects.advance(0.5);
+        // Starting again doesn't restart it
+        effects.start_dissolve();
+        let style = effects.style();
+        assert_eq!(style.alpha, 0.5);
+        assert_eq!(style.scale, 0.75);
+        assert!(!effects.is_dissolved());
+
+        effects.advance(2.0);
+        assert_eq!(effects.dissolve_progress(), Some(1.0));
+        assert_eq!(effects.style().alpha, 0.0);
+        assert!(effects.is_dissolved());
+    }
+}
@@ -3,20 +3,36 @@ use core::{fmt::Debug, marker::PhantomData};
 use std::{collections::HashMap, hash::Hash, path::PathBuf, time::Instant};
 
 use derivative::Derivative;
-use graphics::{Image, ImageSize, Transformed};
+use graphics::{draw_state::Blend, types::Color, Image, ImageSize, Transformed};
 use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
 use shapes::{Point, Size};
 
-use self::sprite_serde::ImageDef;
-use super::{registry::TextureRegistry, Camera, View};
+use self::{
+    hit_effects::{HitEffects, HitEffectsStyle},
+    smear::{MotionSmear, SmearScale},
+    sprite_serde::ImageDef,
+    trail::{Trail, TrailConfig, TrailDraw},
+};
+use super::{
+    impact::{FrameImpact, Impact, ImpactQueue},
+    registry::TextureRegistry,
+    Camera, View,
+};
 use crate::{
     error::{AnimationError, RenderError, RenderResult},
-    types::{physbox::HasBox, Axis},
+    scene::{GameFlags, QueuedSpawn, SpawnQueue},
+    types::{physbox::HasBox, Axis, Velocity},
     ScarabResult,
 };
 
+/// Flashing when hit and dissolving on death
+pub mod hit_effects;
+/// Stretching sprites along their velocity
+pub mod smear;
 mod sprite_serde;
+/// Afterimage trails drawn behind animated sprites
+pub mod trail;
 
 #[derive(Derivative, Clone, Serialize, Deserialize)]
 #[derivative(Debug)]
@@ -29,6 +45,9 @@ pub struct SpriteView {
     #[serde(with = "ImageDef")]
     image: Image,
     texture_path: PathBuf,
+    /// The texture drawn instead when `texture_path` isn't loaded, before the registry's defaults
+    #[serde(default)]
+    fallback_texture: Option<PathBuf>,
 }
 
 impl SpriteView {
@@ -42,9 +61,21 @@ impl SpriteView {
                 .rect([0.0, 0.0, sprite_size.w, sprite_size.h])
                 .src_rect([0.0, 0.0, sprite_size.w, sprite_size.h]),
             texture_path,
+            fallback_texture: None,
         })
     }
 
+    /// Sets the texture drawn when the sprite's texture isn't loaded, instead of
+    /// the registry's default (see [TextureRegistry::get_or_fallback])
+    pub fn set_fallback_texture(&mut self, fallback_texture: Option<PathBuf>) {
+        self.fallback_texture = fallback_texture;
+    }
+
+    /// The texture drawn when the sprite's texture isn't loaded
+    pub fn get_fallback_texture(&self) -> Option<&PathBuf> {
+        self.fallback_texture.as_ref()
+    }
+
     fn set_src_rect_pos(&mut self, new_pos: Point) {
         if let Some(rect) = self.image.source_rectangle.as_mut() {
             rect[0] = new_pos.x;
@@ -52,9 +83,13 @@ impl SpriteView {
         }
     }
 
+    /// Draws the sprite over `viewed`'s box in the given style, smeared by `smear`
+    #[allow(clippy::too_many_arguments)]
     fn render<V: HasBox>(
         &mut self,
         viewed: &V,
+        style: HitEffectsStyle,
+        smear: SmearScale,
         _args: &RenderArgs,
         camera: &Camera,
         ctx: graphics::Context,
@@ -63,21 +98,78 @@ impl SpriteView {
     ) -> RenderResult<()> {
         if let Some((transform, _rect)) = camera.box_renderables(viewed.get_box(), ctx) {
             let scale_factor = camera.points_per_pixel();
+            let mut transform = transform;
+            if style.scale != 1.0 || !smear.is_none() {
+                // Scaled about the center of the box
+                let size = viewed.get_box().size();
+                let (cx, cy) = (size.w * scale_factor / 2.0, size.h * scale_factor / 2.0);
+                transform = transform
+                    .trans(cx, cy)
+                    .scale(style.scale, style.scale)
+                    // Stretched along the direction of motion without turning the sprite
+                    .rot_rad(smear.angle)
+                    .scale(smear.stretch, smear.squash)
+                    .rot_rad(-smear.angle)
+                    .trans(-cx, -cy);
+            }
             let transform = transform
                 .trans_pos(self.pos * -scale_factor)
                 .scale(scale_factor, scale_factor);
 
-            self.image.draw(
-                texture_registry.get_or_default(&self.texture_path),
-                &ctx.draw_state,
-                transform,
-                gl,
-            );
+            let texture = texture_registry
+                .get_or_fallback(&self.texture_path, self.fallback_texture.as_ref());
+            let mut image = self.image;
+            if style.alpha < 1.0 {
+                image.color = Some([1.0, 1.0, 1.0, style.alpha as f32]);
+            }
+            image.draw(texture, &ctx.draw_state, transform, gl);
+            if let Some([r, g, b, a]) = style.flash {
+                // Adding the flash over the sprite brightens it without changing its shape
+                image.color = Some([r, g, b, a * style.alpha as f32]);
+                image.draw(texture, &ctx.draw_state.blend(Blend::Add), transform, gl);
+            }
         }
         Ok(())
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Determines what a [SpriteAnimation] does after it shows its last frame
+pub enum PlayMode {
+    /// Start again from the first frame
+    #[default]
+    Loop,
+    /// Stay on the last frame
+    Once,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// An effect spawned when a [SpriteAnimation] enters one of its frames, i.e. a slash on an
+/// attack's active frame (see [AnimationStateMachine::set_spawn_queue])
+pub struct FrameEffect {
+    /// The frame that spawns the effect, counting from 0
+    pub frame: usize,
+    /// The [prefab](crate::scene::Scene::register_prefab) spawned
+    pub prefab: String,
+    /// The socket on the viewed type that the effect is centered on
+    /// (see [AnimationStates::socket_position])
+    pub socket: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Ties whether an [AnimationStateMachine] is drawn, and how, to one of the
+/// [game's flags](crate::scene::Scene::game_flags), i.e. a secret wall that disappears
+/// once a switch is pulled (see [AnimationStateMachine::set_flag_binding])
+pub struct FlagBinding {
+    /// The name of the flag
+    pub flag: String,
+    /// Which value of the flag the sprite is drawn for, hidden for the other.
+    /// `None` draws it whatever the flag is
+    pub visible_when: Option<bool>,
+    /// The color added over the sprite while the flag is set
+    pub tint: Option<Color>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A view type for displaying an animation across a single sprite map
 /// Should generally be used wrapped by an [AnimationStateMachine]
@@ -92,8 +184,25 @@ pub struct SpriteAnimation {
     milliseconds_per_frame: f64,
     /// The axis within the sprite map that adding to gets to the next frame
     animation_direction: Axis,
-    /// The timestamp at which the last frame was set
+    /// What the animation does after its last frame
+    #[serde(default)]
+    play_mode: PlayMode,
+    /// Whether the animation has gone past its last frame since it was last started
+    #[serde(default)]
+    completed: bool,
+    /// The effects spawned when the animation enters their frames
+    #[serde(default)]
+    frame_effects: Vec<FrameEffect>,
+    /// The impacts triggered when the animation enters their frames
+    #[serde(default)]
+    frame_impacts: Vec<FrameImpact>,
+    /// Whether the animation has been started but hasn't advanced since, so it's still to
+    /// enter its first frame
     #[serde(skip)]
+    starting: bool,
+    /// The timestamp at which the last frame was set.
+    /// Saved as the time spent on the current frame so loading resumes mid-frame
+    #[serde(with = "sprite_serde::elapsed_instant")]
     #[serde(default = "Instant::now")]
     last_update: Instant,
 }
@@ -139,6 +248,11 @@ impl SpriteAnimation {
             frame_num: 0,
             milliseconds_per_frame,
             animation_direction,
+            play_mode: PlayMode::default(),
+            completed: false,
+            frame_effects: Vec::new(),
+            frame_impacts: Vec::new(),
+            starting: true,
             last_update: Instant::now(),
         })
     }
@@ -151,42 +265,145 @@ impl SpriteAnimation {
             frame_num: 0,
             milliseconds_per_frame: 1000.0,
             animation_direction: Axis::X,
+            play_mode: PlayMode::default(),
+            completed: false,
+            frame_effects: Vec::new(),
+            frame_impacts: Vec::new(),
+            starting: true,
             last_update: Instant::now(),
         }
     }
 
+    /// Sets the texture drawn when the animation's sprite map isn't loaded, instead of
+    /// the registry's default
+    pub fn set_fallback_texture(&mut self, fallback_texture: Option<PathBuf>) {
+        self.sprite.set_fallback_texture(fallback_texture);
+    }
+
+    /// Sets what the animation does after its last frame
+    pub fn set_play_mode(&mut self, play_mode: PlayMode) {
+        self.play_mode = play_mode;
+    }
+
+    /// What the animation does after its last frame
+    pub fn get_play_mode(&self) -> PlayMode {
+        self.play_mode
+    }
+
+    /// Spawns `effect` whenever the animation enters its frame
+    pub fn add_frame_effect(&mut self, effect: FrameEffect) {
+        self.frame_effects.push(effect);
+    }
+
+    /// The effects spawned when the animation enters their frames
+    pub fn frame_effects(&self) -> &[FrameEffect] {
+        &self.frame_effects
+    }
+
+    /// Triggers `impact` whenever the animation enters its frame
+    pub fn add_frame_impact(&mut self, impact: FrameImpact) {
+        self.frame_impacts.push(impact);
+    }
+
+    /// The impacts triggered when the animation enters their frames
+    pub fn frame_impacts(&self) -> &[FrameImpact] {
+        &self.frame_impacts
+    }
+
+    /// Whether the animation has gone past its last frame since it was started.
+    /// For [PlayMode::Loop] this is the first time it wraps around to the beginning.
+    /// Static frames never complete
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
     /// Prepares the animation to be started again.
     fn reset(&mut self) {
         self.frame_num = 0;
+        self.completed = false;
+        self.starting = true;
         self.last_update = Instant::now()
     }
 
-    fn render<V: HasBox>(
-        &mut self,
-        viewed: &V,
-        args: &RenderArgs,
-        camera: &Camera,
-        ctx: graphics::Context,
-        texture_registry: &TextureRegistry,
-        gl: &mut opengl_graphics::GlGraphics,
-    ) -> RenderResult<()> {
-        // args.ext_dt is a liar, so we calculate our own dt
-        let now = Instant::now();
-
+    /// Moves the animation to the frame that should be showing at `now`, returning the frames
+    /// it entered on the way in order
+    fn advance(&mut self, now: Instant) -> Vec<usize> {
+        let mut entered = Vec::new();
+        if self.starting {
+            self.starting = false;
+            entered.push(self.frame_num);
+        }
         let num_new_frames =
             ((now - self.last_update).as_millis() / self.milliseconds_per_frame as u128) as usize;
         if num_new_frames > 0 && self.frames_in_sprite_map > 0 {
             self.last_update = now;
-            self.frame_num = (self.frame_num + num_new_frames) % self.frames_in_sprite_map;
+            let next_frame = self.frame_num + num_new_frames;
+            // Skipping more than a loop's worth of frames only enters each of them once
+            let skipped = num_new_frames.saturating_sub(self.frames_in_sprite_map);
+            entered.extend(
+                (self.frame_num + skipped + 1..=next_frame).filter_map(|frame| {
+                    match self.play_mode {
+                        PlayMode::Loop => Some(frame % self.frames_in_sprite_map),
+                        PlayMode::Once => (frame < self.frames_in_sprite_map).then_some(frame),
+                    }
+                }),
+            );
+            if next_frame >= self.frames_in_sprite_map {
+                self.completed = true;
+            }
+            self.frame_num = match self.play_mode {
+                PlayMode::Loop => next_frame % self.frames_in_sprite_map,
+                PlayMode::Once => usize::min(next_frame, self.frames_in_sprite_map - 1),
+            };
             let new_pos = match self.animation_direction {
                 Axis::X => [self.frame_num as f64 * self.sprite.sprite_size.w, 0.0].into(),
                 Axis::Y => [0.0, self.frame_num as f64 * self.sprite.sprite_size.h].into(),
             };
             self.sprite.set_src_rect_pos(new_pos)
         }
+        entered
+    }
+
+    /// The impacts triggered by entering `frames`, in order
+    fn impacts_entered(&self, frames: &[usize]) -> Vec<Impact> {
+        frames
+            .iter()
+            .flat_map(|frame| self.frame_impacts.iter().filter(move |i| i.frame == *frame))
+            .map(|i| i.impact)
+            .collect()
+    }
+
+    /// The effects spawned by entering `frames`, in order
+    fn effects_entered(&self, frames: &[usize]) -> Vec<FrameEffect> {
+        frames
+            .iter()
+            .flat_map(|frame| self.frame_effects.iter().filter(move |e| e.frame == *frame))
+            .cloned()
+            .collect()
+    }
 
-        self.sprite
-            .render(viewed, args, camera, ctx, texture_registry, gl)
+    #[allow(clippy::too_many_arguments)]
+    fn render<V: HasBox>(
+        &mut self,
+        viewed: &V,
+        style: HitEffectsStyle,
+        smear: SmearScale,
+        args: &RenderArgs,
+        camera: &Camera,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+    ) -> RenderResult<()> {
+        self.sprite.render(
+            viewed,
+            style,
+            smear,
+            args,
+            camera,
+            ctx,
+            texture_registry,
+            gl,
+        )
     }
 }
 
@@ -195,34 +412,238 @@ impl SpriteAnimation {
 pub struct AnimationStateMachine<S: AnimationStates> {
     current_state: S,
     animations: HashMap<S, SpriteAnimation>,
+    /// The state to return to once the current one-shot animation completes
+    #[serde(default = "Option::default")]
+    oneshot_return: Option<S>,
+    /// Shown in place of any state that doesn't have an animation
+    #[serde(default)]
+    fallback: Option<SpriteAnimation>,
+    /// Afterimages of the current frame drawn behind the viewed object
+    #[serde(default)]
+    trail: Option<Trail>,
+    /// Stretches the sprite along the viewed object's velocity
+    #[serde(default)]
+    motion_smear: Option<MotionSmear>,
+    /// Where the animations' [FrameEffect]s are queued to spawn, they aren't spawned without one
+    #[serde(skip)]
+    spawn_queue: Option<SpawnQueue>,
+    /// Where the animations' [FrameImpact]s are queued, they aren't triggered without one
+    #[serde(skip)]
+    impact_queue: Option<ImpactQueue>,
+    /// The game flag that the sprite's visibility and tint depend on
+    #[serde(default)]
+    flag_binding: Option<FlagBinding>,
+    /// Where the flag binding's flag is read from, it's ignored without them
+    #[serde(skip)]
+    game_flags: Option<GameFlags>,
+    /// The frame effects that couldn't be spawned since they were last taken
+    #[serde(skip)]
+    failed_effects: Vec<(FrameEffect, AnimationError)>,
 }
 
 impl<S: AnimationStates> AnimationStateMachine<S> {
     /// The 'animations' must have an entry for 'initial_state'
     pub fn new(initial_state: S, animations: HashMap<S, SpriteAnimation>) -> ScarabResult<Self> {
-        let _ = animations
-            .get(&initial_state)
-            .ok_or_else(|| AnimationError::NoAnimationForState(format!("{:?}", initial_state)));
+        animations.get(&initial_state).ok_or_else(|| {
+            RenderError::from(AnimationError::NoAnimationForState(format!(
+                "{:?}",
+                initial_state
+            )))
+        })?;
 
         Ok(Self {
             current_state: initial_state,
             animations,
+            oneshot_return: None,
+            fallback: None,
+            trail: None,
+            motion_smear: None,
+            spawn_queue: None,
+            impact_queue: None,
+            flag_binding: None,
+            game_flags: None,
+            failed_effects: Vec::new(),
         })
     }
 
+    /// Creates a state machine that shows `fallback` for any state without an animation
+    /// (i.e. a "missing" placeholder) instead of failing, so gaps in the data are obvious
+    /// without stopping the game
+    pub fn new_with_fallback(
+        initial_state: S,
+        animations: HashMap<S, SpriteAnimation>,
+        fallback: SpriteAnimation,
+    ) -> Self {
+        Self {
+            current_state: initial_state,
+            animations,
+            oneshot_return: None,
+            fallback: Some(fallback),
+            trail: None,
+            motion_smear: None,
+            spawn_queue: None,
+            impact_queue: None,
+            flag_binding: None,
+            game_flags: None,
+            failed_effects: Vec::new(),
+        }
+    }
+
+    /// Sets the animation shown for states without an animation. `None` makes them an error
+    pub fn set_fallback(&mut self, fallback: Option<SpriteAnimation>) {
+        self.fallback = fallback;
+    }
+
+    /// Whether the current state has no animation and the fallback is shown instead
+    pub fn is_showing_fallback(&self) -> bool {
+        !self.animations.contains_key(&self.current_state) && self.fallback.is_some()
+    }
+
+    /// The animation for the current state, or the fallback if it doesn't have one
+    fn current_animation_mut(&mut self) -> Option<&mut SpriteAnimation> {
+        match self.animations.get_mut(&self.current_state) {
+            Some(animation) => Some(animation),
+            None => self.fallback.as_mut(),
+        }
+    }
+
+    /// Draws faded copies of the current frame at the viewed object's previous positions.
+    /// `None` removes the trail
+    pub fn set_trail(&mut self, trail: Option<TrailConfig>) {
+        self.trail = trail.map(Trail::new);
+    }
+
+    /// The afterimage trail drawn behind the viewed object
+    pub fn trail(&self) -> Option<&Trail> {
+        self.trail.as_ref()
+    }
+
+    /// The afterimage trail drawn behind the viewed object
+    pub fn trail_mut(&mut self) -> Option<&mut Trail> {
+        self.trail.as_mut()
+    }
+
+    /// Stretches the sprite along the viewed object's [velocity](AnimationStates::velocity)
+    /// the faster it moves. `None` removes the smear
+    pub fn set_motion_smear(&mut self, motion_smear: Option<MotionSmear>) {
+        self.motion_smear = motion_smear;
+    }
+
+    /// How the sprite is stretched along the viewed object's velocity
+    pub fn motion_smear(&self) -> Option<&MotionSmear> {
+        self.motion_smear.as_ref()
+    }
+
+    /// Queues the animations' [FrameEffect]s to `spawn_queue` as their frames are entered,
+    /// usually a [Scene::spawn_queue](crate::scene::Scene::spawn_queue). `None` stops spawning them
+    pub fn set_spawn_queue(&mut self, spawn_queue: Option<SpawnQueue>) {
+        self.spawn_queue = spawn_queue;
+    }
+
+    /// Takes the frame effects that couldn't be spawned since the last time they were taken,
+    /// in the order their frames were entered, along with why each failed
+    pub fn take_failed_effects(&mut self) -> Vec<(FrameEffect, AnimationError)> {
+        std::mem::take(&mut self.failed_effects)
+    }
+
+    /// Queues the animations' [FrameImpact]s to `impact_queue` as their frames are entered,
+    /// for the game to [apply](ImpactQueue::apply). `None` stops triggering them
+    pub fn set_impact_queue(&mut self, impact_queue: Option<ImpactQueue>) {
+        self.impact_queue = impact_queue;
+    }
+
+    /// Shows, hides or tints the sprite depending on one of the `game_flags` (see
+    /// [AnimationStateMachine::set_game_flags]), checked every render. `None` removes the binding
+    pub fn set_flag_binding(&mut self, flag_binding: Option<FlagBinding>) {
+        self.flag_binding = flag_binding;
+    }
+
+    /// The game flag that the sprite's visibility and tint depend on
+    pub fn flag_binding(&self) -> Option<&FlagBinding> {
+        self.flag_binding.as_ref()
+    }
+
+    /// Reads the flag binding's flag from `game_flags`, usually a
+    /// [Scene::game_flags](crate::scene::Scene::game_flags). Needs to be set again after loading
+    pub fn set_game_flags(&mut self, game_flags: Option<GameFlags>) {
+        self.game_flags = game_flags;
+    }
+
+    /// The value of the flag binding's flag, None without a binding or the flags to read it from
+    fn bound_flag(&self) -> Option<bool> {
+        self.flag_binding
+            .as_ref()
+            .zip(self.game_flags.as_ref())
+            .map(|(binding, flags)| flags.get(&binding.flag))
+    }
+
+    /// Whether the sprite is drawn given the current value of its bound flag.
+    /// Always true without a flag binding
+    pub fn is_visible(&self) -> bool {
+        let visible_when = self.flag_binding.as_ref().and_then(|b| b.visible_when);
+        match (visible_when, self.bound_flag()) {
+            (Some(visible_when), Some(flag)) => flag == visible_when,
+            _ => true,
+        }
+    }
+
+    /// The color added over the sprite because its bound flag is set, if any
+    pub fn flag_tint(&self) -> Option<Color> {
+        self.flag_binding
+            .as_ref()
+            .and_then(|b| b.tint)
+            .filter(|_| self.bound_flag() == Some(true))
+    }
+
+    /// The smear to draw `viewed` with this frame
+    fn smear_scale(&self, viewed: &S::Viewed) -> SmearScale {
+        self.motion_smear
+            .zip(self.current_state.velocity(viewed))
+            .map(|(smear, velocity)| smear.scale(velocity))
+            .unwrap_or(SmearScale::NONE)
+    }
+
+    /// The draws of the current frame for `viewed` at `now`, including the trail's ghosts at
+    /// previous positions. Records `viewed`'s current position for the trail when it's due
+    fn draws(&mut self, viewed: &S::Viewed, now: Instant) -> Vec<TrailDraw> {
+        let current = *viewed.get_box();
+        match self.trail.as_mut() {
+            Some(trail) => {
+                let draws = trail.draws(&current);
+                trail.record(*current.pos(), now);
+                draws
+            }
+            None => vec![TrailDraw {
+                physbox: current,
+                alpha: 1.0,
+            }],
+        }
+    }
+
     /// Sets the SpriteAnimation for a given state
     pub fn set_state_animation(&mut self, state: S, animation: SpriteAnimation) {
         self.animations.insert(state, animation);
     }
 
-    /// Sets the current state to new_state.
-    /// Fails if there is no animation for new_state
+    /// The state whose animation is currently rendered
+    pub fn current_state(&self) -> &S {
+        &self.current_state
+    }
+
+    /// Sets the current state to new_state, cancelling any pending one-shot return.
+    /// Fails if there is no animation for new_state and no fallback. Whether the fallback is
+    /// shown instead can be checked with [AnimationStateMachine::is_showing_fallback]
     pub fn set_current_state(&mut self, new_state: S) -> Result<(), AnimationError> {
+        self.oneshot_return = None;
         if self.animations.contains_key(&new_state) {
             let new_animation = self.animations.get_mut(&new_state).unwrap();
             new_animation.reset();
             self.current_state = new_state;
             Ok(())
+        } else if let Some(fallback) = self.fallback.as_mut() {
+            fallback.reset();
+            self.current_state = new_state;
+            Ok(())
         } else {
             Err(AnimationError::NoAnimationForState(format!(
                 "{:?}",
@@ -230,6 +651,89 @@ impl<S: AnimationStates> AnimationStateMachine<S> {
             )))
         }
     }
+
+    /// Plays the animation for `state` from its start, then returns to the current state once it
+    /// [completes](SpriteAnimation::is_completed). While the one-shot plays, [AnimationStates::next_state]
+    /// is not consulted. If a one-shot is already playing, the new one returns to the original state.
+    /// Fails if there is no animation for `state`
+    pub fn play_oneshot(&mut self, state: S) -> Result<(), AnimationError> {
+        let return_state = self.oneshot_return.take();
+        let previous_state = core::mem::replace(&mut self.current_state, state);
+        let return_state = return_state.unwrap_or(previous_state);
+
+        match self.animations.get_mut(&self.current_state) {
+            Some(animation) => {
+                animation.reset();
+                self.oneshot_return = Some(return_state);
+                Ok(())
+            }
+            None => {
+                let err = AnimationError::NoAnimationForState(format!("{:?}", self.current_state));
+                self.current_state = return_state;
+                Err(err)
+            }
+        }
+    }
+
+    /// Chooses the next state for `viewed` and advances the current animation to `now`
+    fn update(&mut self, viewed: &S::Viewed, now: Instant) {
+        let next_state = match self.oneshot_return.take() {
+            Some(return_state) => {
+                let completed = self
+                    .animations
+                    .get(&self.current_state)
+                    .map(SpriteAnimation::is_completed)
+                    .unwrap_or(true);
+                if completed {
+                    Some(return_state)
+                } else {
+                    self.oneshot_return = Some(return_state);
+                    None
+                }
+            }
+            None => self.current_state.next_state(viewed),
+        };
+
+        next_state
+            .map_or(Ok(()), |s| self.set_current_state(s))
+            .unwrap_or_else(|e| {
+                println!("Error rendering animated sprite for {:?}: {:}", self, e);
+            });
+
+        let (effects, impacts) = match self.current_animation_mut() {
+            Some(animation) => {
+                let entered = animation.advance(now);
+                (
+                    animation.effects_entered(&entered),
+                    animation.impacts_entered(&entered),
+                )
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+        if let Some(impact_queue) = self.impact_queue.as_ref() {
+            for impact in impacts {
+                impact_queue.push(impact);
+            }
+        }
+        if let Some(spawn_queue) = self.spawn_queue.as_ref() {
+            for effect in effects {
+                match self.current_state.socket_position(viewed, &effect.socket) {
+                    Some(center) => spawn_queue.push(QueuedSpawn {
+                        prefab: effect.prefab,
+                        center,
+                    }),
+                    None => {
+                        let error = AnimationError::NoSocketForEffect(
+                            effect.socket.clone(),
+                            effect.prefab.clone(),
+                            format!("{:?}", self.current_state),
+                        );
+                        self.failed_effects.push((effect, error));
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl<E: HasBox> AnimationStateMachine<StaticAnimation<E>> {
@@ -241,6 +745,15 @@ impl<E: HasBox> AnimationStateMachine<StaticAnimation<E>> {
         Self {
             current_state,
             animations,
+            oneshot_return: None,
+            fallback: None,
+            trail: None,
+            motion_smear: None,
+            spawn_queue: None,
+            impact_queue: None,
+            flag_binding: None,
+            game_flags: None,
+            failed_effects: Vec::new(),
         }
     }
 }
@@ -256,6 +769,23 @@ where
     /// Determines the animation state to be rendered based on the status of the viewed type.
     /// if `None` the animation state should not change
     fn next_state(&self, viewed: &Self::Viewed) -> Option<Self>;
+
+    /// The viewed type's hit effects to draw the animation with, if it has any
+    /// (i.e. [Entity::hit_effects](crate::gameobject::entity::Entity::hit_effects))
+    fn hit_effects<'a>(&self, _viewed: &'a Self::Viewed) -> Option<&'a HitEffects> {
+        None
+    }
+
+    /// The viewed type's velocity, for [motion smears](AnimationStateMachine::set_motion_smear)
+    fn velocity(&self, _viewed: &Self::Viewed) -> Option<Velocity> {
+        None
+    }
+
+    /// Where the viewed type's socket `name` is in the world, for spawning [FrameEffect]s
+    /// (i.e. [Entity::socket_position](crate::gameobject::entity::Entity::socket_position))
+    fn socket_position(&self, _viewed: &Self::Viewed, _name: &str) -> Option<Point> {
+        None
+    }
 }
 
 #[derive(Derivative, Copy, Serialize, Deserialize)]
@@ -294,14 +824,422 @@ impl<S: AnimationStates> View for AnimationStateMachine<S> {
         texture_registry: &TextureRegistry,
         gl: &mut opengl_graphics::GlGraphics,
     ) -> RenderResult<()> {
-        self.current_state
-            .next_state(viewed)
-            .map_or(Ok(()), |s| self.set_current_state(s))
-            .unwrap_or_else(|e| {
-                println!("Error rendering animated sprite for {:?}: {:}", self, e);
+        // args.ext_dt is a liar, so we calculate our own dt
+        let now = Instant::now();
+        self.update(viewed, now);
+        if !self.is_visible() {
+            return Ok(());
+        }
+
+        let draws = self.draws(viewed, now);
+        let smear = self.smear_scale(viewed);
+        let style = self
+            .current_state
+            .hit_effects(viewed)
+            .map(HitEffects::style)
+            .unwrap_or(HitEffectsStyle::NONE);
+        let tint = self.flag_tint();
+        let state = format!("{:?}", self.current_state);
+        let animation = self
+            .current_animation_mut()
+            .ok_or(AnimationError::NoAnimationForState(state))?;
+        let last = draws.len().saturating_sub(1);
+        for (i, draw) in draws.into_iter().enumerate() {
+            let style = HitEffectsStyle {
+                alpha: style.alpha * draw.alpha,
+                // Only the sprite itself flashes, not its trail
+                flash: style.flash.filter(|_| i == last).or(tint),
+                ..style
+            };
+            animation.render(
+                &draw.physbox,
+                style,
+                smear,
+                args,
+                camera,
+                ctx,
+                texture_registry,
+                gl,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        gameobject::entity::registry::test::TestEntity,
+        rendering::impact::{CameraShake, Impact},
+        scene::test::test_scene,
+        timestep::FixedTimestep,
+        types::physbox::PhysBox,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestStates {
+        Idle,
+        Attack,
+    }
+
+    impl AnimationStates for TestStates {
+        type Viewed = PhysBox;
+
+        fn next_state(&self, _viewed: &Self::Viewed) -> Option<Self> {
+            None
+        }
+    }
+
+    fn test_animation(frames_in_sprite_map: usize, play_mode: PlayMode) -> SpriteAnimation {
+        let mut animation = SpriteAnimation::new_static_frame(
+            SpriteView::new([0.0, 0.0].into(), [8.0, 8.0].into(), "test.png".into()).unwrap(),
+        );
+        animation.frames_in_sprite_map = frames_in_sprite_map;
+        animation.milliseconds_per_frame = 10.0;
+        animation.set_play_mode(play_mode);
+        animation
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    struct SwordStates;
+
+    impl AnimationStates for SwordStates {
+        type Viewed = TestEntity;
+
+        fn next_state(&self, _viewed: &Self::Viewed) -> Option<Self> {
+            None
+        }
+
+        fn socket_position(&self, viewed: &Self::Viewed, name: &str) -> Option<Point> {
+            viewed.0.socket_position(name)
+        }
+    }
+
+    fn test_state_machine() -> AnimationStateMachine<TestStates> {
+        let mut animations = HashMap::new();
+        animations.insert(TestStates::Idle, test_animation(4, PlayMode::Loop));
+        animations.insert(TestStates::Attack, test_animation(3, PlayMode::Once));
+        AnimationStateMachine::new(TestStates::Idle, animations).unwrap()
+    }
+
+    #[test]
+    fn game_flag_shows_hidden_wall_without_touching_its_entity() {
+        let mut scene = test_scene();
+        let mut wall = TestEntity::with_box([10.0, 10.0, 5.0, 5.0]);
+        wall.0.set_max_velocity(100.0).unwrap();
+        wall.0.set_velocity([10.0, 0.0].into());
+        let id = scene.register_entity(wall).unwrap();
+
+        let mut asm = test_state_machine();
+        asm.set_flag_binding(Some(FlagBinding {
+            flag: "switch".to_string(),
+            visible_when: Some(true),
+            tint: Some([0.0, 0.0, 1.0, 1.0]),
+        }));
+        // Unbound until it has the flags to read from
+        assert!(asm.is_visible());
+        asm.set_game_flags(Some(scene.game_flags()));
+        assert!(!asm.is_visible());
+        assert_eq!(asm.flag_tint(), None);
+
+        let mut drawn = vec![];
+        for _ in 0..3 {
+            scene.game_flags().toggle("switch");
+            drawn.push((asm.is_visible(), asm.flag_tint()));
+            scene.tick_entities(0.5).unwrap();
+        }
+        assert_eq!(
+            drawn,
+            vec![
+                (true, Some([0.0, 0.0, 1.0, 1.0])),
+                (false, None),
+                (true, Some([0.0, 0.0, 1.0, 1.0])),
+            ]
+        );
+        // The wall kept moving the whole time
+        let wall = scene.entities().get_by_id(id).unwrap();
+        assert_eq!(*wall.get_box().pos(), [25.0, 10.0].into());
+        assert_eq!(wall.0.get_velocity(), [10.0, 0.0].into());
+
+        // The flags are saved with the scene
+        let loaded: GameFlags =
+            rmp_serde::from_slice(&rmp_serde::to_vec(&scene.game_flags()).unwrap()).unwrap();
+        assert!(loaded.get("switch"));
+        assert!(!loaded.get("lever"));
+    }
+
+    #[test]
+    fn once_animation_stops_on_last_frame() {
+        let mut animation = test_animation(3, PlayMode::Once);
+        animation.reset();
+        let start = animation.last_update;
+
+        animation.advance(start + Duration::from_millis(15));
+        assert_eq!(animation.frame_num, 1);
+        assert!(!animation.is_completed());
+
+        animation.advance(start + Duration::from_millis(100));
+        assert_eq!(animation.frame_num, 2);
+        assert!(animation.is_completed());
+    }
+
+    #[test]
+    fn loop_animation_wraps_and_completes() {
+        let mut animation = test_animation(3, PlayMode::Loop);
+        animation.reset();
+        let start = animation.last_update;
+
+        animation.advance(start + Duration::from_millis(45));
+        assert_eq!(animation.frame_num, 1);
+        assert!(animation.is_completed());
+    }
+
+    #[test]
+    fn entering_active_frame_spawns_one_effect_at_socket() {
+        let mut scene = test_scene();
+        scene.register_prefab("slash", || TestEntity::with_box([0.0, 0.0, 4.0, 2.0]));
+        let mut swordsman = TestEntity::with_box([10.0, 20.0, 8.0, 8.0]);
+        swordsman.0.set_socket("blade", [12.0, 3.0]);
+        let id = scene.register_entity(swordsman).unwrap();
+
+        let mut attack = test_animation(3, PlayMode::Once);
+        attack.add_frame_effect(FrameEffect {
+            frame: 1,
+            prefab: "slash".into(),
+            socket: "blade".into(),
+        });
+        let mut animations = HashMap::new();
+        animations.insert(SwordStates, attack);
+        let mut asm = AnimationStateMachine::new(SwordStates, animations).unwrap();
+        asm.set_spawn_queue(Some(scene.spawn_queue()));
+        let start = asm.animations[&SwordStates].last_update;
+
+        // Starts on frame 0, enters frame 1, stays on it, then plays through the rest
+        for ms in [5, 15, 18, 25, 100] {
+            let swordsman = scene.entities().get_by_id(id).unwrap();
+            asm.update(swordsman, start + Duration::from_millis(ms));
+        }
+        assert_eq!(scene.spawn_queue().len(), 1);
+
+        scene.tick_entities(0.01).unwrap();
+        assert!(scene.spawn_queue().is_empty());
+        assert_eq!(scene.entities().len(), 2);
+        let (_, slash) = scene
+            .entities()
+            .iter_with_ids()
+            .find(|(other, _)| *other != id)
+            .unwrap();
+        assert_eq!(slash.get_box().center(), [22.0, 23.0].into());
+    }
+
+    #[test]
+    fn effect_at_missing_socket_is_recorded_instead_of_spawned() {
+        let swordsman = TestEntity::with_box([10.0, 20.0, 8.0, 8.0]);
+        let mut attack = test_animation(3, PlayMode::Once);
+        let effect = FrameEffect {
+            frame: 1,
+            prefab: "slash".into(),
+            socket: "blade".into(),
+        };
+        attack.add_frame_effect(effect.clone());
+        let mut animations = HashMap::new();
+        animations.insert(SwordStates, attack);
+        let mut asm = AnimationStateMachine::new(SwordStates, animations).unwrap();
+        let spawn_queue = SpawnQueue::default();
+        asm.set_spawn_queue(Some(spawn_queue.clone()));
+        let start = asm.animations[&SwordStates].last_update;
+
+        asm.update(&swordsman, start + Duration::from_millis(15));
+
+        assert!(spawn_queue.is_empty());
+        assert_eq!(
+            asm.take_failed_effects(),
+            vec![(
+                effect,
+                AnimationError::NoSocketForEffect(
+                    "blade".into(),
+                    "slash".into(),
+                    "SwordStates".into()
+                )
+            )]
+        );
+        assert!(asm.take_failed_effects().is_empty());
+    }
+
+    #[test]
+    fn impact_frame_shakes_and_stops_once_while_held() {
+        let viewed = PhysBox::new([0.0, 0.0, 1.0, 1.0]).unwrap();
+        let shake = CameraShake {
+            amplitude: 3.0,
+            duration: 0.2,
+            frequency: 30.0,
+        };
+        let mut asm = test_state_machine();
+        asm.animations
+            .get_mut(&TestStates::Attack)
+            .unwrap()
+            .add_frame_impact(FrameImpact {
+                frame: 2,
+                impact: Impact {
+                    shake: Some(shake),
+                    hitstop: Some(0.1),
+                },
             });
+        let queue = ImpactQueue::default();
+        asm.set_impact_queue(Some(queue.clone()));
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [100.0, 50.0],
+        );
+        let mut timestep = FixedTimestep::from_ups(60).unwrap();
+
+        asm.set_current_state(TestStates::Attack).unwrap();
+        let start = asm.animations[&TestStates::Attack].last_update;
+        let mut triggered = 0;
+        // Enters the last frame at 25ms, then holds it
+        for ms in [5, 15, 25, 28, 40, 100] {
+            asm.update(&viewed, start + Duration::from_millis(ms));
+            triggered += queue.len();
+            queue.apply(&mut camera, &mut timestep);
+        }
+        assert_eq!(triggered, 1);
+        assert_eq!(camera.get_shake(), Some(&shake));
+        assert_ne!(camera.shake_offset(), [0.0, 0.0]);
+        assert_eq!(timestep.hitstop_remaining(), 0.1);
+
+        // Frozen for the hitstop, then simulating again
+        assert_eq!(timestep.advance(0.05), 0);
+        assert_eq!(timestep.advance(0.05), 0);
+        assert_eq!(timestep.advance(0.05), 3);
+        camera.tick_shake(0.2);
+        assert_eq!(camera.get_shake(), None);
+        assert_eq!(camera.shake_offset(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn oneshot_plays_to_completion_then_returns() {
+        let viewed = PhysBox::new([0.0, 0.0, 1.0, 1.0]).unwrap();
+        let mut asm = test_state_machine();
+
+        asm.play_oneshot(TestStates::Attack).unwrap();
+        let start = asm.animations[&TestStates::Attack].last_update;
+        assert_eq!(asm.current_state(), &TestStates::Attack);
+
+        asm.update(&viewed, start + Duration::from_millis(15));
+        assert_eq!(asm.current_state(), &TestStates::Attack);
+
+        // Reaches the end of the one-shot
+        asm.update(&viewed, start + Duration::from_millis(50));
+        assert_eq!(asm.current_state(), &TestStates::Attack);
+        assert!(asm.animations[&TestStates::Attack].is_completed());
+
+        asm.update(&viewed, start + Duration::from_millis(55));
+        assert_eq!(asm.current_state(), &TestStates::Idle);
+    }
+
+    #[test]
+    fn oneshot_for_missing_state_fails_without_changing_state() {
+        let mut animations = HashMap::new();
+        animations.insert(TestStates::Idle, test_animation(4, PlayMode::Loop));
+        let mut asm = AnimationStateMachine::new(TestStates::Idle, animations).unwrap();
+
+        assert!(asm.play_oneshot(TestStates::Attack).is_err());
+        assert_eq!(asm.current_state(), &TestStates::Idle);
+    }
+
+    #[test]
+    fn missing_state_shows_fallback() {
+        let viewed = PhysBox::new([0.0, 0.0, 1.0, 1.0]).unwrap();
+        let mut animations = HashMap::new();
+        animations.insert(TestStates::Idle, test_animation(4, PlayMode::Loop));
+        let mut asm = AnimationStateMachine::new_with_fallback(
+            TestStates::Idle,
+            animations,
+            test_animation(2, PlayMode::Loop),
+        );
+        assert!(!asm.is_showing_fallback());
+
+        asm.set_current_state(TestStates::Attack).unwrap();
+        assert_eq!(asm.current_state(), &TestStates::Attack);
+        assert!(asm.is_showing_fallback());
+
+        // The fallback is what's animated
+        let start = asm.fallback.as_ref().unwrap().last_update;
+        asm.update(&viewed, start + Duration::from_millis(15));
+        assert_eq!(asm.fallback.as_ref().unwrap().frame_num, 1);
+        assert_eq!(asm.animations[&TestStates::Idle].frame_num, 0);
+    }
+
+    #[test]
+    fn missing_state_without_fallback_fails() {
+        let mut asm = test_state_machine();
+        asm.animations.remove(&TestStates::Attack);
+
+        assert!(asm.set_current_state(TestStates::Attack).is_err());
+        assert_eq!(asm.current_state(), &TestStates::Idle);
+        assert!(AnimationStateMachine::new(TestStates::Attack, HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn trail_draws_ghosts_at_prior_positions() {
+        let mut asm = test_state_machine();
+        asm.set_trail(Some(TrailConfig {
+            length: 5,
+            fade: 0.5,
+            spacing: 0.01,
+        }));
+
+        let start = Instant::now();
+        let mut viewed = PhysBox::new([0.0, 0.0, 8.0, 8.0]).unwrap();
+        let mut draws = Vec::new();
+        for x in 0..8 {
+            viewed.set_pos([x as f64 * 10.0, 5.0].into());
+            draws = asm.draws(&viewed, start + Duration::from_millis(x * 10));
+        }
+
+        assert_eq!(draws.len(), 6);
+        let (ghosts, main) = draws.split_at(5);
+        assert_eq!(main[0].physbox, viewed);
+        assert_eq!(main[0].alpha, 1.0);
+
+        let ghost_xs: Vec<f64> = ghosts.iter().map(|d| d.physbox.pos().x).collect();
+        assert_eq!(ghost_xs, vec![20.0, 30.0, 40.0, 50.0, 60.0]);
+        assert!(ghosts.windows(2).all(|w| w[0].alpha < w[1].alpha));
+        assert_eq!(ghosts[4].alpha, 0.5);
+        assert!(ghosts.iter().all(|d| d.physbox.size() == viewed.size()));
+    }
+
+    #[test]
+    fn playback_resumes_after_reload() {
+        let viewed = PhysBox::new([0.0, 0.0, 1.0, 1.0]).unwrap();
+        let mut asm = test_state_machine();
+        asm.play_oneshot(TestStates::Attack).unwrap();
+        // 15ms into the attack is 5ms into its second frame
+        let start = Instant::now() - Duration::from_millis(15);
+        asm.animations
+            .get_mut(&TestStates::Attack)
+            .unwrap()
+            .last_update = start;
+        asm.update(&viewed, Instant::now());
+        let saved_frame_start = asm.animations[&TestStates::Attack].last_update;
+        let elapsed_in_frame = saved_frame_start.elapsed();
+
+        let buf = rmp_serde::to_vec(&asm).unwrap();
+        let loaded: AnimationStateMachine<TestStates> = rmp_serde::from_slice(&buf).unwrap();
 
-        let animation = self.animations.get_mut(&self.current_state).unwrap();
-        animation.render(viewed, args, camera, ctx, texture_registry, gl)
+        assert_eq!(loaded.current_state(), &TestStates::Attack);
+        assert_eq!(loaded.oneshot_return, Some(TestStates::Idle));
+        let attack = &loaded.animations[&TestStates::Attack];
+        assert_eq!(attack.frame_num, 1);
+        assert_eq!(
+            attack.sprite.image.source_rectangle,
+            Some([8.0, 0.0, 8.0, 8.0])
+        );
+        let loaded_elapsed = attack.last_update.elapsed();
+        assert!(loaded_elapsed >= elapsed_in_frame);
+        assert!(loaded_elapsed - elapsed_in_frame < Duration::from_millis(5));
     }
 }
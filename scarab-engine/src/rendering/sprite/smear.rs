@@ -0,0 +1,103 @@
+use graphics::types::{Scalar, Vec2d};
+use serde::{Deserialize, Serialize};
+
+use crate::types::Velocity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Stretches a sprite along its velocity and squashes it across, to look blurred by its motion
+pub struct MotionSmear {
+    /// Speeds at or below this aren't smeared at all
+    pub min_speed: Scalar,
+    /// How much longer the sprite gets for each unit of speed above `min_speed`
+    pub stretch_per_speed: Scalar,
+    /// The most the sprite can be stretched by, i.e. 2 is twice as long
+    pub max_stretch: Scalar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The non-uniform scale to draw a smeared sprite with, about its center
+pub struct SmearScale {
+    /// The direction of motion in radians, clockwise from the positive x axis
+    pub angle: Scalar,
+    /// The scale along the direction of motion
+    pub stretch: Scalar,
+    /// The scale across the direction of motion
+    pub squash: Scalar,
+}
+
+impl SmearScale {
+    /// Drawn as normal
+    pub const NONE: Self = Self {
+        angle: 0.0,
+        stretch: 1.0,
+        squash: 1.0,
+    };
+
+    /// Whether this doesn't change the sprite at all
+    pub fn is_none(&self) -> bool {
+        self.stretch == 1.0 && self.squash == 1.0
+    }
+
+    /// Scales `v`, an offset from the sprite's center, the same way the sprite is drawn
+    pub fn apply(&self, [x, y]: Vec2d) -> Vec2d {
+        let (sin, cos) = self.angle.sin_cos();
+        // Into the frame of the motion, scale, then back out
+        let along = (x * cos + y * sin) * self.stretch;
+        let across = (-x * sin + y * cos) * self.squash;
+        [along * cos - across * sin, along * sin + across * cos]
+    }
+}
+
+impl MotionSmear {
+    /// The scale for a sprite moving at `velocity`. The sprite keeps the same area, so it
+    /// squashes across its motion as much as it stretches along it
+    pub fn scale(&self, velocity: Velocity) -> SmearScale {
+        let speed = velocity.magnitude();
+        if speed <= self.min_speed {
+            return SmearScale::NONE;
+        }
+        let stretch = (1.0 + (speed - self.min_speed) * self.stretch_per_speed)
+            .min(self.max_stretch)
+            .max(1.0);
+        SmearScale {
+            angle: velocity.y.atan2(velocity.x),
+            stretch,
+            squash: 1.0 / stretch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::ApproxEq;
+
+    fn length([x, y]: Vec2d) -> Scalar {
+        f64::hypot(x, y)
+    }
+
+    #[test]
+    fn fast_sprites_stretch_along_velocity_and_stopped_ones_dont() {
+        let smear = MotionSmear {
+            min_speed: 10.0,
+            stretch_per_speed: 0.01,
+            max_stretch: 2.0,
+        };
+        let velocity: Velocity = [60.0, 80.0].into();
+        let direction = [0.6, 0.8];
+        let across = [-0.8, 0.6];
+
+        let scale = smear.scale(velocity);
+        // 100 speed is 90 over the minimum
+        assert!(length(scale.apply(direction)).approx_eq(&1.9, 1e-9));
+        assert!(length(scale.apply(across)).approx_eq(&(1.0 / 1.9), 1e-9));
+        // Still pointing the same way, just longer
+        let stretched = scale.apply(direction);
+        assert!((stretched[0] / stretched[1]).approx_eq(&0.75, 1e-9));
+
+        assert_eq!(smear.scale([1000.0, 0.0].into()).stretch, 2.0);
+        let stopped = smear.scale([0.0, 0.0].into());
+        assert!(stopped.is_none());
+        assert_eq!(stopped.apply(direction), direction);
+    }
+}
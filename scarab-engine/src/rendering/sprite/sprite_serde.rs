@@ -1,9 +1,11 @@
+use std::time::{Duration, Instant};
+
 use graphics::{
     types::{Color, Rectangle, SourceRectangle},
     Image,
 };
 use opengl_graphics::{Filter, TextureSettings, Wrap};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Serialize, Deserialize)]
 #[serde(remote = "Image")]
@@ -101,3 +103,20 @@ impl From<WrapDef> for Wrap {
         }
     }
 }
+
+/// (De)serializes an `Instant` as the time elapsed since it, so timers pick up where they
+/// left off when loaded
+pub mod elapsed_instant {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(instant: &Instant, serializer: S) -> Result<S::Ok, S::Error> {
+        instant.elapsed().as_secs_f64().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Instant, D::Error> {
+        let elapsed =
+            Duration::try_from_secs_f64(f64::deserialize(deserializer)?).unwrap_or(Duration::ZERO);
+        let now = Instant::now();
+        Ok(now.checked_sub(elapsed).unwrap_or(now))
+    }
+}
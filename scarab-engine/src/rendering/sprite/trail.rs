@@ -0,0 +1,127 @@
+use std::{collections::VecDeque, time::Instant};
+
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+use crate::types::physbox::PhysBox;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// How an afterimage trail is recorded and drawn
+pub struct TrailConfig {
+    /// The number of faded copies drawn behind the sprite
+    pub length: usize,
+    /// The opacity in [0, 1] of the newest copy. Each older copy fades linearly towards transparent
+    pub fade: f64,
+    /// The seconds between recorded positions, so the copies are as far apart at any frame rate.
+    /// 0 records every update
+    pub spacing: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A single copy of a sprite to draw for a trail
+pub struct TrailDraw {
+    /// Where to draw the sprite
+    pub physbox: PhysBox,
+    /// The opacity to draw the sprite with
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Records the previous positions of a viewed object to draw faded copies of its sprite at,
+/// i.e. afterimages behind a dash
+pub struct Trail {
+    config: TrailConfig,
+    /// The recorded positions, newest first
+    #[serde(skip)]
+    positions: VecDeque<Point>,
+    /// When the last position was recorded
+    #[serde(skip)]
+    last_record: Option<Instant>,
+}
+
+impl Trail {
+    /// Creates a trail with no recorded positions yet
+    pub fn new(config: TrailConfig) -> Self {
+        Self {
+            config,
+            positions: VecDeque::with_capacity(config.length),
+            last_record: None,
+        }
+    }
+
+    /// How the trail is recorded and drawn
+    pub fn config(&self) -> &TrailConfig {
+        &self.config
+    }
+
+    /// Records the viewed object's position at `now` if it's been `spacing` seconds since the
+    /// last one
+    pub fn record(&mut self, pos: Point, now: Instant) {
+        if let Some(last_record) = self.last_record {
+            if now.saturating_duration_since(last_record).as_secs_f64() < self.config.spacing {
+                return;
+            }
+        }
+        self.last_record = Some(now);
+        self.positions.push_front(pos);
+        self.positions.truncate(self.config.length);
+    }
+
+    /// Forgets all of the recorded positions (i.e. after a teleport)
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.last_record = None;
+    }
+
+    /// The draws for the sprite at `current`, from the oldest ghost to the fully opaque sprite
+    /// itself last so that it's drawn on top
+    pub fn draws(&self, current: &PhysBox) -> Vec<TrailDraw> {
+        let length = self.config.length.max(1) as f64;
+        let mut draws: Vec<TrailDraw> = self
+            .positions
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, pos)| {
+                let mut physbox = *current;
+                physbox.set_pos(*pos);
+                TrailDraw {
+                    physbox,
+                    alpha: self.config.fade.clamp(0.0, 1.0) * (length - i as f64) / length,
+                }
+            })
+            .collect();
+        draws.push(TrailDraw {
+            physbox: *current,
+            alpha: 1.0,
+        });
+        draws
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn spacing_is_the_same_at_any_frame_rate() {
+        let start = Instant::now();
+        for frame_ms in [5, 10] {
+            let mut trail = Trail::new(TrailConfig {
+                length: 3,
+                fade: 0.5,
+                spacing: 0.03,
+            });
+            // Moving one unit every millisecond
+            for ms in (0..=100).step_by(frame_ms) {
+                let now = start + Duration::from_millis(ms);
+                trail.record([ms as f64, 0.0].into(), now);
+            }
+
+            let xs: Vec<f64> = trail.positions.iter().map(|p| p.x).collect();
+            assert_eq!(xs, vec![90.0, 60.0, 30.0], "{}ms frames", frame_ms);
+        }
+    }
+}
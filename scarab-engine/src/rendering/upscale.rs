@@ -0,0 +1,345 @@
+use std::ptr;
+
+use gl::types::GLuint;
+use graphics::{Context, DrawState, Image, Transformed, Viewport};
+use opengl_graphics::{Filter, GlGraphics, Texture, TextureSettings};
+use piston::RenderArgs;
+use serde::{Deserialize, Serialize};
+
+use super::Camera;
+use crate::{
+    error::{RenderError, RenderResult},
+    types::physbox::PhysBox,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How the internal resolution is sampled when it's scaled up to the window
+pub enum UpscaleFilter {
+    /// Each pixel becomes a crisp block, for pixel art
+    #[default]
+    Nearest,
+    /// Pixels are blended together, for smooth art
+    Linear,
+}
+
+impl From<UpscaleFilter> for Filter {
+    fn from(value: UpscaleFilter) -> Self {
+        match value {
+            UpscaleFilter::Nearest => Filter::Nearest,
+            UpscaleFilter::Linear => Filter::Linear,
+        }
+    }
+}
+
+/// A texture sampler whose filtering can be changed after it's created
+pub trait Sampler {
+    /// Sets the filter used when the sampler is minified and magnified
+    fn set_filter(&mut self, filter: UpscaleFilter);
+}
+
+impl Sampler for Texture {
+    fn set_filter(&mut self, filter: UpscaleFilter) {
+        let filter = match filter {
+            UpscaleFilter::Nearest => gl::NEAREST,
+            UpscaleFilter::Linear => gl::LINEAR,
+        } as i32;
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.get_id());
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter);
+        }
+    }
+}
+
+/// An offscreen framebuffer with a texture to draw into
+struct RenderTarget {
+    framebuffer: GLuint,
+    texture: Texture,
+}
+
+impl RenderTarget {
+    fn new([w, h]: [u32; 2]) -> RenderResult<Self> {
+        let mut texture_id = 0;
+        let mut framebuffer = 0;
+        let status = unsafe {
+            gl::GenTextures(1, &mut texture_id);
+            gl::BindTexture(gl::TEXTURE_2D, texture_id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as i32,
+                w as i32,
+                h as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                ptr::null(),
+            );
+
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture_id,
+                0,
+            );
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status
+        };
+
+        // Made before checking the status so the texture is cleaned up either way
+        let target = Self {
+            framebuffer,
+            texture: Texture::new(texture_id, w, h),
+        };
+        if status == gl::FRAMEBUFFER_COMPLETE {
+            Ok(target)
+        } else {
+            Err(RenderError::RenderTarget(format!(
+                "framebuffer incomplete ({:#x})",
+                status
+            )))
+        }
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+        }
+    }
+}
+
+fn default_integer_scaling() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize)]
+/// Renders the game at a fixed internal resolution, then scales it up to the window. With
+/// integer scaling (see [Camera::set_integer_scaling]) it's scaled by the largest whole number
+/// that fits, letterboxing the rest.
+/// The camera used while rendering should have the internal resolution as its window size
+pub struct InternalResolution {
+    size: [u32; 2],
+    filter: UpscaleFilter,
+    /// Whether the upscale is limited to whole numbers
+    #[serde(default = "default_integer_scaling")]
+    integer_scaling: bool,
+    /// The filter last applied to the target's sampler
+    #[serde(skip)]
+    applied_filter: Option<UpscaleFilter>,
+    #[serde(skip)]
+    target: Option<RenderTarget>,
+}
+
+impl InternalResolution {
+    /// Renders at `size` pixels, integer scaling up to the window with `filter`
+    pub fn new(size: [u32; 2], filter: UpscaleFilter) -> Self {
+        Self {
+            size,
+            filter,
+            integer_scaling: true,
+            applied_filter: None,
+            target: None,
+        }
+    }
+
+    /// Renders at the size of `camera`'s view, scaling up to the window the same way the camera
+    /// would: by whole numbers if it's [integer scaling](Camera::set_integer_scaling).
+    /// The camera should then be given the internal resolution as its window size
+    pub fn for_camera(camera: &Camera, filter: UpscaleFilter) -> Self {
+        let size = camera.get_box().size();
+        Self {
+            integer_scaling: camera.is_integer_scaling(),
+            ..Self::new([size.w.round() as u32, size.h.round() as u32], filter)
+        }
+    }
+
+    /// Sets whether the upscale is limited to whole numbers, otherwise it fills as much of the
+    /// window as it can while keeping its aspect ratio
+    pub fn set_integer_scaling(&mut self, integer_scaling: bool) {
+        self.integer_scaling = integer_scaling;
+    }
+
+    /// Whether the upscale is limited to whole numbers
+    pub fn is_integer_scaling(&self) -> bool {
+        self.integer_scaling
+    }
+
+    /// The size in pixels that everything is rendered at before scaling
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// The filter used when scaling up to the window
+    pub fn filter(&self) -> UpscaleFilter {
+        self.filter
+    }
+
+    /// Changes the filter used when scaling up to the window, taking effect on the next render
+    pub fn set_filter(&mut self, filter: UpscaleFilter) {
+        self.filter = filter;
+    }
+
+    /// The texture settings for the sampler of the final upscale
+    pub fn sampler_settings(&self) -> TextureSettings {
+        TextureSettings::new().filter(self.filter.into())
+    }
+
+    /// Applies the configured filter to `sampler` if it hasn't been already.
+    /// Returns whether the sampler was changed
+    pub fn sync_sampler<S: Sampler>(&mut self, sampler: &mut S) -> bool {
+        if self.applied_filter == Some(self.filter) {
+            return false;
+        }
+        sampler.set_filter(self.filter);
+        self.applied_filter = Some(self.filter);
+        true
+    }
+
+    /// Where the internal resolution is drawn in a window of `window_size` points: scaled up
+    /// (by the largest whole number that fits when integer scaling) and centered between the
+    /// gutters of a [Camera::letterbox]. Windows smaller than the internal resolution aren't
+    /// scaled down
+    pub fn destination_rect(&self, window_size: [f64; 2]) -> [f64; 4] {
+        let [w, h] = [self.size[0] as f64, self.size[1] as f64];
+        // Only the size matters, the sizes are whole pixels so at least 1x1 unless they're 0
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, w.max(1.0), h.max(1.0)]).unwrap(),
+            window_size,
+        );
+        camera.set_integer_scaling(self.integer_scaling);
+        let [gutter_w, gutter_h] = camera.letterbox();
+        let scale = camera.points_per_pixel();
+        [gutter_w, gutter_h, w * scale, h * scale]
+    }
+
+    /// Renders `draw` at the internal resolution, then scales the result up into the
+    /// [destination rect](InternalResolution::destination_rect) in the window
+    pub fn render<F>(&mut self, args: &RenderArgs, gl: &mut GlGraphics, draw: F) -> RenderResult<()>
+    where
+        F: FnOnce(Context, &mut GlGraphics) -> RenderResult<()>,
+    {
+        let mut target = match self.target.take() {
+            Some(target) => target,
+            None => {
+                // A new texture starts out with GL's default filtering
+                self.applied_filter = None;
+                RenderTarget::new(self.size)?
+            }
+        };
+        self.sync_sampler(&mut target.texture);
+
+        let [w, h] = self.size;
+        let internal_viewport = Viewport {
+            rect: [0, 0, w as i32, h as i32],
+            draw_size: self.size,
+            window_size: [w as f64, h as f64],
+        };
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target.framebuffer);
+        }
+        let drawn = gl.draw(internal_viewport, |ctx, gl| draw(ctx, gl));
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        let [x, y, w, h] = self.destination_rect(args.window_size);
+        gl.draw(args.viewport(), |ctx, gl| {
+            // Framebuffer textures are stored bottom row first
+            let transform = ctx.transform.trans(0.0, 2.0 * y + h).flip_v();
+            Image::new().rect([x, y, w, h]).draw(
+                &target.texture,
+                &DrawState::default(),
+                transform,
+                gl,
+            );
+        });
+        self.target = Some(target);
+        drawn
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingSampler {
+        filters: Vec<UpscaleFilter>,
+    }
+
+    impl Sampler for RecordingSampler {
+        fn set_filter(&mut self, filter: UpscaleFilter) {
+            self.filters.push(filter);
+        }
+    }
+
+    #[test]
+    fn upscale_is_whole_number_and_letterboxed() {
+        let resolution = InternalResolution::new([320, 180], UpscaleFilter::Nearest);
+        // 2.5x would fit, so 2x is used with the rest left as gutters
+        assert_eq!(
+            resolution.destination_rect([800.0, 600.0]),
+            [80.0, 120.0, 640.0, 360.0]
+        );
+        assert_eq!(
+            resolution.destination_rect([1000.5, 563.0]),
+            [20.25, 11.5, 960.0, 540.0]
+        );
+        assert_eq!(
+            resolution.destination_rect([320.0, 180.0]),
+            [0.0, 0.0, 320.0, 180.0]
+        );
+    }
+
+    #[test]
+    fn upscale_follows_camera_integer_scaling() {
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 320.0, 180.0]).unwrap(),
+            [800.0, 600.0],
+        );
+        camera.set_integer_scaling(true);
+        let mut resolution = InternalResolution::for_camera(&camera, UpscaleFilter::Nearest);
+        assert_eq!(resolution.size(), [320, 180]);
+        // The same scale and gutters as the camera would pick rendering to the window itself
+        let [x, y, w, h] = resolution.destination_rect([800.0, 600.0]);
+        assert_eq!([x, y], camera.letterbox());
+        assert_eq!(w / 320.0, camera.points_per_pixel());
+        assert_eq!([w, h], [640.0, 360.0]);
+
+        resolution.set_integer_scaling(false);
+        assert_eq!(
+            resolution.destination_rect([800.0, 600.0]),
+            [0.0, 75.0, 800.0, 450.0]
+        );
+    }
+
+    #[test]
+    fn configured_filter_applied_to_upscale_sampler() {
+        let mut resolution = InternalResolution::new([320, 180], UpscaleFilter::Nearest);
+        let (min, mag) = resolution.sampler_settings().get_filter();
+        assert!(matches!((min, mag), (Filter::Nearest, Filter::Nearest)));
+
+        let mut sampler = RecordingSampler::default();
+        assert!(resolution.sync_sampler(&mut sampler));
+        // Unchanged, so the sampler isn't touched again
+        assert!(!resolution.sync_sampler(&mut sampler));
+        assert_eq!(sampler.filters, vec![UpscaleFilter::Nearest]);
+
+        resolution.set_filter(UpscaleFilter::Linear);
+        let (min, mag) = resolution.sampler_settings().get_filter();
+        assert!(matches!((min, mag), (Filter::Linear, Filter::Linear)));
+        assert!(resolution.sync_sampler(&mut sampler));
+        assert_eq!(
+            sampler.filters,
+            vec![UpscaleFilter::Nearest, UpscaleFilter::Linear]
+        );
+    }
+}
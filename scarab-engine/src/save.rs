@@ -0,0 +1,164 @@
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::SaveError, ScarabResult};
+
+/// The first bytes of gzip compressed data
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The first bytes of a zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// How save data is compressed before it's written. Gzip and zstd each need the feature of the
+/// same name, and fail with [SaveError::Unsupported] without it
+pub enum SaveFormat {
+    #[default]
+    /// Written as it was serialized
+    Uncompressed,
+    /// Compressed with gzip, which is quick and widely supported
+    Gzip,
+    /// Compressed with zstd, which compresses better and decompresses faster than gzip
+    Zstd,
+}
+
+impl SaveFormat {
+    /// Works out how `data` was compressed from its header. Anything that doesn't start with a
+    /// gzip or zstd header is taken to be uncompressed
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(&GZIP_MAGIC) {
+            Self::Gzip
+        } else if data.starts_with(&ZSTD_MAGIC) {
+            Self::Zstd
+        } else {
+            Self::Uncompressed
+        }
+    }
+
+    /// Compresses the serialized save `data` in this format
+    pub fn compress(self, data: &[u8]) -> ScarabResult<Vec<u8>> {
+        match self {
+            Self::Uncompressed => Ok(data.to_vec()),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder
+                    .write_all(data)
+                    .and_then(|_| encoder.finish())
+                    .map_err(|e| SaveError::Compression(self, e.to_string()).into())
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(|e| SaveError::Compression(self, e.to_string()).into()),
+            #[allow(unreachable_patterns)]
+            _ => Err(SaveError::Unsupported(self).into()),
+        }
+    }
+
+    /// Decompresses `data` that was compressed in this format
+    pub fn decompress(self, data: &[u8]) -> ScarabResult<Vec<u8>> {
+        match self {
+            Self::Uncompressed => Ok(data.to_vec()),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => {
+                let mut decompressed = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .read_to_end(&mut decompressed)
+                    .map(|_| decompressed)
+                    .map_err(|e| SaveError::Decompression(self, e.to_string()).into())
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd::decode_all(data)
+                .map_err(|e| SaveError::Decompression(self, e.to_string()).into()),
+            #[allow(unreachable_patterns)]
+            _ => Err(SaveError::Unsupported(self).into()),
+        }
+    }
+}
+
+/// Decompresses save `data` in whichever format its header says it's in,
+/// leaving it as it is if it isn't compressed
+pub fn decompress(data: &[u8]) -> ScarabResult<Vec<u8>> {
+    SaveFormat::detect(data).decompress(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{
+        gameobject::{
+            entity::registry::test::TestEntity,
+            field::{CellColorView, Field, FieldColorView},
+        },
+        scene::Scene,
+        types::{NO_SOLIDITY, SOLID},
+    };
+
+    /// A serialized scene with a large field and lots of entities
+    fn large_save() -> Vec<u8> {
+        let solidities: Vec<_> = (0..1600)
+            .map(|i| if i % 7 == 0 { SOLID } else { NO_SOLIDITY })
+            .collect();
+        let field =
+            Field::from_grid([0.0, 0.0].into(), [10.0, 10.0].into(), 40, &solidities).unwrap();
+        let color_view = CellColorView { color: [1.0; 4] };
+        let mut scene = Scene::new(
+            field,
+            FieldColorView {
+                solid_view: color_view.clone(),
+                air_view: color_view.clone(),
+                default_view: color_view,
+            },
+        );
+        for i in 0..200 {
+            let (x, y) = ((i % 20) as f64 * 20.0 + 1.0, (i / 20) as f64 * 20.0 + 1.0);
+            scene
+                .register_entity(TestEntity::with_box([x, y, 5.0, 5.0]))
+                .unwrap();
+        }
+        rmp_serde::to_vec(&scene).unwrap()
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn assert_lossless(format: SaveFormat) {
+        let save = large_save();
+        let compressed = format.compress(&save).unwrap();
+        assert!(compressed.len() < save.len());
+        assert_eq!(SaveFormat::detect(&compressed), format);
+
+        let decompressed = decompress(&compressed).unwrap();
+        assert_eq!(decompressed, save);
+        let loaded: Scene<TestEntity, FieldColorView> =
+            rmp_serde::from_slice(&decompressed).unwrap();
+        assert_eq!(rmp_serde::to_vec(&loaded).unwrap(), save);
+    }
+
+    #[test]
+    fn uncompressed_saves_load_as_they_are() {
+        let save = large_save();
+        assert_eq!(SaveFormat::detect(&save), SaveFormat::Uncompressed);
+        assert_eq!(decompress(&save).unwrap(), save);
+        #[cfg(not(feature = "zstd"))]
+        assert!(matches!(
+            SaveFormat::Zstd.compress(&save),
+            Err(crate::ScarabError::SaveError(SaveError::Unsupported(
+                SaveFormat::Zstd
+            )))
+        ));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_save_is_smaller_and_round_trips() {
+        assert_lossless(SaveFormat::Gzip);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_save_is_smaller_and_round_trips() {
+        assert_lossless(SaveFormat::Zstd);
+    }
+}
@@ -1,22 +1,39 @@
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
-use graphics::Context;
+use graphics::{
+    types::{Scalar, Vec2d},
+    Context,
+};
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use shapes::Point;
 
 use crate::{
     effect::PendingEffect,
     gameobject::{
-        entity::registry::{EntityRegistry, RegisteredDebugEntity, RegisteredEntity},
-        field::Field,
+        entity::{
+            loot::LootDrop,
+            registry::{EntityId, EntityRegistry, RegisteredDebugEntity, RegisteredEntity},
+            spawn_table::{SpawnDefinition, SpawnDefinitions},
+            Attachment, WallImpact,
+        },
+        field::{CellId, Field},
+    },
+    input::{AimAssist, LockOn},
+    rendering::{
+        debug::DebugView,
+        fog::{FogOfWar, VisibilityLight},
+        registry::TextureRegistry,
+        Camera, View,
     },
-    rendering::{debug::DebugView, registry::TextureRegistry, Camera, View},
     types::{
-        physbox::{HasBox, HasBoxMut},
-        HasSolidity,
+        physbox::{HasBox, HasBoxMut, PhysBox},
+        shared::Shared,
+        HasHealth, HasSolidity, HasUuid, Health, SharedHealthId,
     },
-    ScarabResult,
+    PhysicsError, PhysicsResult, ScarabError, ScarabResult,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,6 +45,77 @@ pub struct Scene<E, V> {
     #[serde(skip)]
     #[serde(default = "Vec::new")]
     pending_effects: Vec<PendingEffect<E>>,
+    /// How often entities update depending on their distance from the camera
+    #[serde(default)]
+    update_lod: Option<UpdateLod>,
+    /// The time and number of ticks that entities with reduced updates have missed since
+    /// they last updated
+    #[serde(skip)]
+    lod_elapsed: HashMap<EntityId, (f64, u32)>,
+    /// Whether entities entering and exiting named regions of air cells emit [RegionEvent]s
+    #[serde(default)]
+    emit_region_events: bool,
+    /// How overlapping entities are pushed apart, `None` shifts them apart in a single pass
+    #[serde(default)]
+    overlap_push: Option<OverlapPush>,
+    /// The order that pairs of solid entities are shifted apart in when there's no overlap push
+    #[serde(default)]
+    pair_order: PairOrder,
+    /// Whether gravity is switched off everywhere, floating every airborne entity
+    #[serde(default)]
+    gravity_disabled: bool,
+    /// Whether entities on the same z layer are drawn in order of how low down they are
+    #[serde(default)]
+    y_sort: bool,
+    /// The regions where airborne entities float instead of falling, if any
+    #[serde(default)]
+    zero_gravity: Option<ZeroGravity>,
+    /// How entities ride the platforms they stand on, `None` doesn't carry them
+    #[serde(default)]
+    platform_riding: Option<PlatformRiding>,
+    /// Darkens the scene outside of the entities' visibility radii, if set
+    #[serde(default)]
+    fog_of_war: Option<FogOfWar>,
+    /// The named regions that each entity overlapped as of the last tick
+    #[serde(skip)]
+    entity_regions: HashMap<EntityId, Vec<String>>,
+    /// The region events since they were last taken
+    #[serde(skip)]
+    region_events: Vec<RegionEvent>,
+    /// The projectiles' wall impacts since they were last taken
+    #[serde(skip)]
+    wall_impacts: Vec<(EntityId, WallImpact)>,
+    /// The interactions since they were last taken
+    #[serde(skip)]
+    interactions: Vec<Interaction>,
+    /// Whether effects changing an entity's health emit [HealthChange]s
+    #[serde(default)]
+    emit_health_changes: bool,
+    /// The health changes since they were last taken
+    #[serde(skip)]
+    health_changes: Vec<HealthChange>,
+    /// The health pools shared by groups of entities
+    #[serde(default)]
+    shared_health: HashMap<SharedHealthId, Health>,
+    /// The id given to the next shared health pool added
+    #[serde(default)]
+    next_shared_health: usize,
+    /// Builds the entities spawned by name, i.e. effects spawned by animation frame events
+    // An explicit default, so loading a scene doesn't need its entities to implement Default
+    #[serde(skip, default = "HashMap::new")]
+    prefabs: HashMap<String, fn() -> E>,
+    /// The spawns queued from outside of the scene, spawned at the start of the next tick
+    #[serde(skip)]
+    spawn_queue: SpawnQueue,
+    /// The queued spawns that couldn't be spawned since they were last taken, and why
+    #[serde(skip)]
+    failed_spawns: Vec<(QueuedSpawn, ScarabError)>,
+    /// Named switches for the game's state, i.e. whether a lever has been pulled
+    #[serde(default)]
+    game_flags: GameFlags,
+    /// The variants of prefabs that can be spawned by name like the prefabs themselves
+    #[serde(skip)]
+    spawn_definitions: SpawnDefinitions,
 }
 
 impl<E, V> Scene<E, V>
@@ -42,6 +130,29 @@ where
             field_view,
             entity_registry: EntityRegistry::default(),
             pending_effects: Vec::default(),
+            update_lod: None,
+            lod_elapsed: HashMap::new(),
+            emit_region_events: false,
+            overlap_push: None,
+            pair_order: PairOrder::default(),
+            gravity_disabled: false,
+            y_sort: false,
+            zero_gravity: None,
+            platform_riding: None,
+            fog_of_war: None,
+            entity_regions: HashMap::new(),
+            region_events: Vec::new(),
+            wall_impacts: Vec::new(),
+            interactions: Vec::new(),
+            emit_health_changes: false,
+            health_changes: Vec::new(),
+            shared_health: HashMap::new(),
+            next_shared_health: 0,
+            prefabs: HashMap::new(),
+            spawn_queue: SpawnQueue::default(),
+            failed_spawns: Vec::new(),
+            game_flags: GameFlags::default(),
+            spawn_definitions: SpawnDefinitions::new(),
         }
     }
 
@@ -57,9 +168,12 @@ where
         self.field_view
             .render(&mut self.field, args, &camera, ctx, texture_registry, gl)?;
 
-        for registered_entity in &mut self.entity_registry {
-            registered_entity.render(args, camera, ctx, texture_registry, gl)?;
+        for i in self.render_indices() {
+            if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                registered_entity.render(args, camera, ctx, texture_registry, gl)?;
+            }
         }
+        self.render_fog_of_war(camera, ctx, gl);
         Ok(())
     }
 
@@ -88,83 +202,1343 @@ where
             gl,
         )?;
 
-        for registered_entity in &mut self.entity_registry {
-            registered_entity.render_with_info(
-                debug_options,
-                args,
-                camera,
-                ctx,
-                texture_registry,
-                gl,
-            )?;
+        for i in self.render_indices() {
+            if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                registered_entity.render_with_info(
+                    debug_options,
+                    args,
+                    camera,
+                    ctx,
+                    texture_registry,
+                    gl,
+                )?;
+            }
         }
+        self.render_fog_of_war(camera, ctx, gl);
         Ok(())
     }
 
-    /// Registers a new entity to the scene
-    pub fn register_entity(&mut self, to_register: E) -> ScarabResult<()> {
+    /// Draws entities on the same z layer in order of the bottoms of their boxes, so that those
+    /// lower down the screen are drawn in front, i.e. for top down games
+    pub fn set_y_sort(&mut self, y_sort: bool) {
+        self.y_sort = y_sort;
+    }
+
+    /// Whether entities on the same z layer are drawn in order of the bottoms of their boxes
+    pub fn is_y_sorted(&self) -> bool {
+        self.y_sort
+    }
+
+    /// The indices of the entities in the order they're drawn
+    fn render_indices(&self) -> Vec<usize> {
+        let mut order: Vec<(usize, EntityId, i32, Scalar)> = self
+            .entity_registry
+            .iter_with_ids()
+            .enumerate()
+            .map(|(i, (id, e))| {
+                let e = e.inner_entity();
+                let y = if self.y_sort {
+                    e.get_box().bottom_y()
+                } else {
+                    0.0
+                };
+                (i, id, e.get_z_layer(), y)
+            })
+            .collect();
+        // The sort is stable, but the ids break ties explicitly so that the order never depends
+        // on how the registry happens to store the entities
+        order.sort_by(|a, b| a.2.cmp(&b.2).then(a.3.total_cmp(&b.3)).then(a.1.cmp(&b.1)));
+        order.into_iter().map(|(i, ..)| i).collect()
+    }
+
+    /// The ids of the entities in the order they're drawn: by
+    /// [z layer](crate::gameobject::entity::Entity::set_z_layer), then (when
+    /// [y sorting](Scene::set_y_sort)) by the bottoms of their boxes. Entities that are level
+    /// with each other are always drawn in id order, oldest first, so that they don't flicker
+    /// over one another from frame to frame
+    pub fn render_order(&self) -> Vec<EntityId> {
+        self.render_indices()
+            .into_iter()
+            .filter_map(|i| self.entity_registry.id_of(i))
+            .collect()
+    }
+
+    fn render_fog_of_war(&self, camera: &Camera, ctx: Context, gl: &mut GlGraphics) {
+        if let Some(fog_of_war) = self.fog_of_war {
+            fog_of_war.render(&self.visibility_lights(), camera, ctx, gl);
+        }
+    }
+
+    /// Sets the fog of war drawn over the scene. `None` leaves everything visible
+    pub fn set_fog_of_war(&mut self, fog_of_war: Option<FogOfWar>) {
+        self.fog_of_war = fog_of_war;
+    }
+
+    /// The fog of war drawn over the scene
+    pub fn get_fog_of_war(&self) -> Option<&FogOfWar> {
+        self.fog_of_war.as_ref()
+    }
+
+    /// The areas visible through the fog of war around every entity with a visibility radius
+    pub fn visibility_lights(&self) -> Vec<VisibilityLight> {
+        self.entity_registry
+            .iter()
+            .filter_map(|e| {
+                e.inner_entity()
+                    .get_visibility_radius()
+                    .map(|radius| VisibilityLight {
+                        center: e.get_box().center(),
+                        radius,
+                    })
+            })
+            .collect()
+    }
+
+    /// Registers a new entity to the scene, returning its id
+    pub fn register_entity(&mut self, to_register: E) -> ScarabResult<EntityId> {
         self.entity_registry.register(to_register)
     }
 
+    /// Registers the entity to the scene, first moving it according to `placement`
+    pub fn spawn_entity(
+        &mut self,
+        mut to_register: E,
+        placement: SpawnPlacement,
+    ) -> ScarabResult<EntityId> {
+        if let SpawnPlacement::NearestClear(radius) = placement {
+            let physbox = *to_register.get_box();
+            let layers = to_register.inner_entity().get_collision_layers();
+            let obstacles: Vec<PhysBox> =
+                self.entity_registry.iter().map(|e| *e.get_box()).collect();
+            let pos = self
+                .field
+                .nearest_clear_position_avoiding(&physbox, layers, radius, &obstacles)
+                .ok_or(PhysicsError::NoClearPosition(*physbox.pos(), radius))?;
+            to_register.get_box_mut().set_pos(pos);
+        }
+        self.register_entity(to_register)
+    }
+
+    /// Registers `build` as the prefab `name`, replacing any prefab that already had the name
+    pub fn register_prefab<N: Into<String>>(&mut self, name: N, build: fn() -> E) {
+        self.prefabs.insert(name.into(), build);
+    }
+
+    /// Adds `definitions` so their variants can be spawned by name like prefabs, replacing any
+    /// with the same names. Fails without adding any of them if one is built from a prefab
+    /// that isn't registered or uses a behavior or animation that isn't one of the game's
+    pub fn register_spawn_definitions(
+        &mut self,
+        definitions: SpawnDefinitions,
+        behaviors: &[&str],
+        animations: &[&str],
+    ) -> ScarabResult<()> {
+        definitions.validate(|p| self.prefabs.contains_key(p), behaviors, animations)?;
+        self.spawn_definitions.extend(definitions);
+        Ok(())
+    }
+
+    /// The [spawn definition](Scene::register_spawn_definitions) `name`, for the behavior and
+    /// animation to give what it spawns
+    pub fn get_spawn_definition(&self, name: &str) -> Option<&SpawnDefinition> {
+        self.spawn_definitions.get(name)
+    }
+
+    /// Spawns a new instance of the prefab or spawn definition `name` centered on `center`,
+    /// returning its id. Fails if there's neither with the name
+    pub fn spawn_prefab(&mut self, name: &str, center: Point) -> ScarabResult<EntityId> {
+        let definition = self.spawn_definitions.get(name);
+        let prefab = definition.map(|d| d.prefab.as_str()).unwrap_or(name);
+        let build = self
+            .prefabs
+            .get(prefab)
+            .ok_or_else(|| ScarabError::UnknownPrefab(name.to_string()))?;
+        let mut entity = build();
+        if let Some(definition) = definition {
+            definition.stats.apply(entity.inner_entity_mut())?;
+        }
+        let size = *entity.get_box().size();
+        entity
+            .get_box_mut()
+            .set_pos([center.x - size.w / 2.0, center.y - size.h / 2.0].into());
+        self.spawn_entity(entity, SpawnPlacement::Exact)
+    }
+
+    /// A handle to the scene's spawn queue, for queueing spawns from outside of the scene
+    /// (i.e. from an [AnimationStateMachine](crate::rendering::sprite::AnimationStateMachine)).
+    /// The queue is spawned at the start of each tick
+    pub fn spawn_queue(&self) -> SpawnQueue {
+        self.spawn_queue.clone()
+    }
+
+    /// A handle to the scene's game state flags, for setting them from the game and reading
+    /// them from outside of the scene (i.e. an
+    /// [AnimationStateMachine](crate::rendering::sprite::AnimationStateMachine)'s flag binding).
+    /// The flags are saved with the scene
+    pub fn game_flags(&self) -> GameFlags {
+        self.game_flags.clone()
+    }
+
+    /// Spawns everything in the spawn queue in the order it was queued, returning the new ids.
+    /// A spawn that fails (i.e. naming a prefab that isn't registered) doesn't stop the rest,
+    /// it's skipped and kept for [Scene::take_failed_spawns]
+    pub fn spawn_queued(&mut self) -> Vec<EntityId> {
+        let mut spawned = Vec::new();
+        for spawn in self.spawn_queue.take() {
+            match self.spawn_prefab(&spawn.prefab, spawn.center) {
+                Ok(id) => spawned.push(id),
+                Err(e) => self.failed_spawns.push((spawn, e)),
+            }
+        }
+        spawned
+    }
+
+    /// Takes the queued spawns that failed since the last time they were taken, in the order
+    /// they were queued, along with why each failed
+    pub fn take_failed_spawns(&mut self) -> Vec<(QueuedSpawn, ScarabError)> {
+        std::mem::take(&mut self.failed_spawns)
+    }
+
+    /// Registers all of the entities to the scene, returning their ids in the same order
+    pub fn register_entities<I: IntoIterator<Item = E>>(
+        &mut self,
+        to_register: I,
+    ) -> ScarabResult<Vec<EntityId>> {
+        self.entity_registry.register_all(to_register)
+    }
+
+    /// Removes the entities with the given ids from the scene, returning them.
+    /// Ids that aren't registered are ignored
+    pub fn despawn_entities(&mut self, ids: &[EntityId]) -> Vec<E> {
+        self.remove_entities_where(|id, _entity| ids.contains(&id))
+    }
+
+    /// Removes every entity labeled with `tag` from the scene, returning them
+    pub fn despawn_tagged(&mut self, tag: &str) -> Vec<E> {
+        self.remove_entities_where(|_id, entity| entity.inner_entity().has_tag(tag))
+    }
+
+    /// Removes every entity whose health has run out, returning each along with the loot it dropped.
+    /// Entities with [hit effects](crate::gameobject::entity::Entity::set_hit_effects) aren't
+    /// removed until they've finished dissolving. Each of the loot's items is queued to spawn
+    /// as the prefab named after it, where the entity died (see [Scene::spawn_queued])
+    pub fn despawn_dead<R: Rng>(&mut self, rng: &mut R) -> Vec<(E, Option<LootDrop>)> {
+        let dead: Vec<(E, Option<LootDrop>)> = self
+            .remove_entities_where(|_id, entity| entity.inner_entity().is_ready_to_despawn())
+            .into_iter()
+            .map(|entity| {
+                let loot = entity.inner_entity().roll_loot(rng);
+                (entity, loot)
+            })
+            .collect();
+        for loot in dead.iter().filter_map(|(_entity, loot)| loot.as_ref()) {
+            for _ in 0..loot.quantity {
+                self.spawn_queue.push(QueuedSpawn {
+                    prefab: loot.item.clone(),
+                    center: loot.pos,
+                });
+            }
+        }
+        dead
+    }
+
+    /// Removes the entities that `should_remove` returns true for, along with anything the
+    /// scene was keeping track of for them
+    fn remove_entities_where<F: FnMut(EntityId, &E) -> bool>(
+        &mut self,
+        should_remove: F,
+    ) -> Vec<E> {
+        let removed = self.entity_registry.remove_where(should_remove);
+        let registry = &self.entity_registry;
+        self.lod_elapsed
+            .retain(|&id, _| registry.index_of(id).is_some());
+
+        // Despawning leaves every region the entity was in
+        let mut left: Vec<(EntityId, Vec<String>)> = Vec::new();
+        self.entity_regions.retain(|&id, regions| {
+            let kept = registry.index_of(id).is_some();
+            if !kept {
+                left.push((id, std::mem::take(regions)));
+            }
+            kept
+        });
+        left.sort_by_key(|(id, _)| *id);
+        for (entity, regions) in left {
+            self.region_events.extend(
+                regions
+                    .into_iter()
+                    .map(|region| RegionEvent::Exit { entity, region }),
+            );
+        }
+        removed
+    }
+
+    /// Gets a reference to the scene's entities
+    pub fn entities(&self) -> &EntityRegistry<E> {
+        &self.entity_registry
+    }
+
+    /// The ids of the entities whose centers are within `radius` of `center` and pass `filter`
+    pub fn entities_in_radius<F: FnMut(EntityId, &E) -> bool>(
+        &self,
+        center: Point,
+        radius: Scalar,
+        mut filter: F,
+    ) -> Vec<EntityId> {
+        self.entity_registry
+            .iter_with_ids()
+            .filter(|(id, entity)| {
+                let to_entity = entity.get_box().center() - center;
+                to_entity.x * to_entity.x + to_entity.y * to_entity.y <= radius * radius
+                    && filter(*id, entity)
+            })
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// The ids of the entities whose centers are within the cone reaching `range` from `apex`
+    /// in `direction`, spreading `half_angle` radians to either side, and that pass `filter`.
+    /// Entities straddling the cone's edge are only included if their center is within it
+    pub fn entities_in_cone<F: FnMut(EntityId, &E) -> bool>(
+        &self,
+        apex: Point,
+        direction: Vec2d,
+        half_angle: Scalar,
+        range: Scalar,
+        mut filter: F,
+    ) -> Vec<EntityId> {
+        let direction_len = f64::hypot(direction[0], direction[1]);
+        if direction_len == 0.0 {
+            return Vec::new();
+        }
+        let min_cos = half_angle.clamp(0.0, core::f64::consts::PI).cos();
+
+        self.entities_in_radius(apex, range, |id, entity| {
+            let to_entity = entity.get_box().center() - apex;
+            let distance = f64::hypot(to_entity.x, to_entity.y);
+            // An entity right at the apex can't be dodged
+            let in_cone = distance == 0.0
+                || (to_entity.x * direction[0] + to_entity.y * direction[1])
+                    / (distance * direction_len)
+                    >= min_cos;
+            in_cone && filter(id, entity)
+        })
+    }
+
+    /// The nearest [targetable](crate::gameobject::entity::Entity::set_targetable) entity to
+    /// `origin` besides itself whose center is within `radius` of its center and that passes `filter`
+    pub fn nearest_target<F: FnMut(EntityId, &E) -> bool>(
+        &self,
+        origin: EntityId,
+        radius: Scalar,
+        mut filter: F,
+    ) -> Option<EntityId> {
+        let center = self.entity_registry.get_by_id(origin)?.get_box().center();
+        let distance_to = |id: EntityId| {
+            self.entity_registry
+                .get_by_id(id)
+                .map(|e| {
+                    let to = e.get_box().center() - center;
+                    f64::hypot(to.x, to.y)
+                })
+                .unwrap_or(f64::INFINITY)
+        };
+        self.entities_in_radius(center, radius, |id, entity| {
+            id != origin && entity.inner_entity().is_targetable() && filter(id, entity)
+        })
+        .into_iter()
+        .min_by(|a, b| distance_to(*a).total_cmp(&distance_to(*b)))
+    }
+
+    /// Applies `aim_assist` to the `aim` direction from `origin`, using the centers of the
+    /// [targetable](crate::gameobject::entity::Entity::set_targetable) entities that pass
+    /// `filter` as targets
+    pub fn assist_aim<F: FnMut(EntityId, &E) -> bool>(
+        &self,
+        aim_assist: &mut AimAssist,
+        origin: Point,
+        aim: Vec2d,
+        mut filter: F,
+    ) -> Vec2d {
+        let candidates = self.entities_in_cone(
+            origin,
+            aim,
+            aim_assist.half_angle + aim_assist.stickiness,
+            aim_assist.range,
+            |id, entity| entity.inner_entity().is_targetable() && filter(id, entity),
+        );
+        aim_assist.apply(
+            origin,
+            aim,
+            candidates.into_iter().filter_map(|id| {
+                self.entity_registry
+                    .get_by_id(id)
+                    .map(|e| (id, e.get_box().center()))
+            }),
+        )
+    }
+
+    /// The cell on the screen at `screen_pos` (i.e. from a mouse click), if any.
+    /// None when the position is outside of the field
+    pub fn cell_at_screen(&self, screen_pos: Point, camera: &Camera) -> Option<CellId> {
+        self.field
+            .cell_at_pos(camera.screen_to_world(screen_pos))
+            .map(|cell| cell.id())
+    }
+
+    /// The entity on the screen at `screen_pos` (i.e. from a mouse click), counting those whose
+    /// boxes are within `radius` points of it so that small entities are easy to pick.
+    /// Of the entities within reach, the one with the nearest center wins, then the one drawn
+    /// on top (see [Scene::render_order])
+    pub fn pick_at(&self, screen_pos: Point, camera: &Camera, radius: Scalar) -> Option<EntityId> {
+        let pos = camera.screen_to_world(screen_pos);
+        let reach = radius.max(0.0) / camera.points_per_pixel();
+        self.render_indices()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(drawn, i)| {
+                let physbox = self.entity_registry.get_one(i)?.get_box();
+                let center = physbox.center();
+                (physbox.distance_to_pos(pos) <= reach).then(|| {
+                    let distance = f64::hypot(center.x - pos.x, center.y - pos.y);
+                    (distance, drawn, i)
+                })
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)))
+            .and_then(|(_, _, i)| self.entity_registry.id_of(i))
+    }
+
+    /// The entities that `origin` could lock onto, nearest first. Valid targets pass `filter`, are
+    /// alive, [targetable](crate::gameobject::entity::Entity::set_targetable), within range (and
+    /// the facing cone if it has one), in the camera's view and aren't hidden behind solid cells
+    pub fn lock_on_targets<F: FnMut(EntityId, &E) -> bool>(
+        &self,
+        lock_on: &LockOn,
+        origin: EntityId,
+        facing: Vec2d,
+        camera: &Camera,
+        mut filter: F,
+    ) -> Vec<EntityId> {
+        let origin_center = match self.entity_registry.get_by_id(origin) {
+            Some(e) => e.get_box().center(),
+            None => return Vec::new(),
+        };
+        let valid = |id: EntityId, entity: &E| {
+            id != origin
+                && !entity.get_health().is_dead()
+                && entity.inner_entity().is_targetable()
+                && entity.get_box().has_overlap(camera.get_box())
+                && self
+                    .field
+                    .has_line_of_sight(origin_center, entity.get_box().center())
+        };
+        let mut targets = match lock_on.half_angle {
+            Some(half_angle) => {
+                self.entities_in_cone(origin_center, facing, half_angle, lock_on.range, |id, e| {
+                    valid(id, e) && filter(id, e)
+                })
+            }
+            None => self.entities_in_radius(origin_center, lock_on.range, |id, e| {
+                valid(id, e) && filter(id, e)
+            }),
+        };
+
+        let distance_to = |id: &EntityId| {
+            self.entity_registry
+                .get_by_id(*id)
+                .map(|e| {
+                    let to = e.get_box().center() - origin_center;
+                    f64::hypot(to.x, to.y)
+                })
+                .unwrap_or(f64::INFINITY)
+        };
+        targets.sort_by(|a, b| distance_to(a).total_cmp(&distance_to(b)));
+        targets
+    }
+
+    /// Locks `origin` onto the nearest valid target (see [Scene::lock_on_targets]),
+    /// returning it. Breaks the lock if there are none
+    pub fn lock_on<F: FnMut(EntityId, &E) -> bool>(
+        &self,
+        lock_on: &mut LockOn,
+        origin: EntityId,
+        facing: Vec2d,
+        camera: &Camera,
+        filter: F,
+    ) -> Option<EntityId> {
+        let target = self
+            .lock_on_targets(lock_on, origin, facing, camera, filter)
+            .first()
+            .copied();
+        lock_on.set_target(target);
+        target
+    }
+
+    /// Moves the lock to the next furthest valid target, wrapping back around to the nearest.
+    /// Locks onto the nearest if nothing was locked
+    pub fn cycle_lock_on<F: FnMut(EntityId, &E) -> bool>(
+        &self,
+        lock_on: &mut LockOn,
+        origin: EntityId,
+        facing: Vec2d,
+        camera: &Camera,
+        filter: F,
+    ) -> Option<EntityId> {
+        let targets = self.lock_on_targets(lock_on, origin, facing, camera, filter);
+        let next = match lock_on
+            .target()
+            .and_then(|current| targets.iter().position(|t| *t == current))
+        {
+            Some(i) => targets.get((i + 1) % targets.len()),
+            None => targets.first(),
+        }
+        .copied();
+        lock_on.set_target(next);
+        next
+    }
+
+    /// Breaks the lock if its target has died, despawned, left the range or camera's view, or
+    /// gone out of sight. Should be called every tick while locked. Returns the target still locked
+    pub fn update_lock_on(
+        &self,
+        lock_on: &mut LockOn,
+        origin: EntityId,
+        camera: &Camera,
+    ) -> Option<EntityId> {
+        let target = lock_on.target()?;
+        let still_valid = self
+            .lock_on_targets(
+                &LockOn::new(lock_on.range, None),
+                origin,
+                [0.0, 0.0],
+                camera,
+                |id, _| id == target,
+            )
+            .contains(&target);
+        if !still_valid {
+            lock_on.set_target(None);
+        }
+        lock_on.target()
+    }
+
+    /// The direction from `origin` to its locked target, for keeping it facing the target
+    pub fn lock_on_direction(&self, lock_on: &LockOn, origin: EntityId) -> Option<Vec2d> {
+        let from = self.entity_registry.get_by_id(origin)?.get_box().center();
+        let to = self
+            .entity_registry
+            .get_by_id(lock_on.target()?)?
+            .get_box()
+            .center();
+        let distance = f64::hypot(to.x - from.x, to.y - from.y);
+        (distance > 0.0).then(|| [(to.x - from.x) / distance, (to.y - from.y) / distance])
+    }
+
+    /// The point halfway between `origin` and its locked target,
+    /// for the camera to follow so that both stay in view
+    pub fn lock_on_focus(&self, lock_on: &LockOn, origin: EntityId) -> Option<Point> {
+        let from = self.entity_registry.get_by_id(origin)?.get_box().center();
+        let to = self
+            .entity_registry
+            .get_by_id(lock_on.target()?)?
+            .get_box()
+            .center();
+        Some([(from.x + to.x) / 2.0, (from.y + to.y) / 2.0].into())
+    }
+
     /// Gets a reference to the scene's [Field]
     pub fn get_field(&self) -> &Field {
         &self.field
     }
 
+    /// Queues an effect to be processed on the next game tick
+    pub fn add_pending_effect(&mut self, effect: PendingEffect<E>) {
+        self.pending_effects.push(effect);
+    }
+
     /// Runs the physics update for all of the scene's entities
     pub fn tick_entities(&mut self, dt: f64) -> ScarabResult<()> {
+        self.run_tick(dt, None)
+    }
+
+    /// Keeps the entities with a [CameraConstraint](crate::gameobject::entity::CameraConstraint)
+    /// within the camera's current view. Should be called every tick after the camera moves
+    pub fn constrain_to_camera(&mut self, camera: &Camera) {
+        for registered_entity in self.entity_registry.iter_mut() {
+            registered_entity
+                .inner_entity_mut()
+                .constrain_to_view(camera.get_box());
+        }
+    }
+
+    /// Sets how often entities update depending on their distance from the focus given to
+    /// [Scene::tick_entities_around]. `None` updates every entity every tick
+    pub fn set_update_lod(&mut self, update_lod: Option<UpdateLod>) {
+        self.update_lod = update_lod;
+        self.lod_elapsed.clear();
+    }
+
+    /// How often entities update depending on their distance from the focus
+    pub fn get_update_lod(&self) -> Option<&UpdateLod> {
+        self.update_lod.as_ref()
+    }
+
+    /// Runs the physics update for the scene's entities, updating those far from `focus`
+    /// (usually the camera's center) less often according to the scene's [UpdateLod].
+    /// Entities with reduced updates are ticked with all the time they've missed so they don't
+    /// fall behind, and frozen entities don't update at all so they stay exactly where they are.
+    /// Platforms carrying riders always update so that their riders aren't left behind
+    pub fn tick_entities_around(&mut self, dt: f64, focus: Point) -> ScarabResult<()> {
+        let lod = self.update_lod.map(|update_lod| (update_lod, focus));
+        self.run_tick(dt, lod)
+    }
+
+    /// The tick shared by [Scene::tick_entities] and [Scene::tick_entities_around], updating
+    /// entities less often the further they are from the focus when there's an [UpdateLod]
+    fn run_tick(&mut self, dt: f64, lod: Option<(UpdateLod, Point)>) -> ScarabResult<()> {
+        self.spawn_queued();
+        self.update_floating();
+        let platforms = self.tick_platforms(dt)?;
+        for i in 0..self.entity_registry.len() {
+            if platforms.contains(&i) {
+                continue;
+            }
+            let entity_dt = match lod {
+                Some((update_lod, focus)) => match self.lod_dt(i, update_lod, focus, dt) {
+                    Some(entity_dt) => entity_dt,
+                    None => continue,
+                },
+                None => dt,
+            };
+            let mut args = GameTickArgs {
+                field: &self.field,
+                pending_effects: &mut self.pending_effects,
+                dt: entity_dt,
+            };
+            if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                registered_entity.game_tick(i, &mut args)?;
+            }
+        }
+        self.collect_wall_impacts();
+
+        self.handle_entity_collisions()?;
+
+        self.update_attachments();
+
+        self.update_riders();
+
+        self.handle_interaction_requests();
+
+        self.update_regions();
+
+        self.process_pending_effects(dt)?;
+
+        Ok(())
+    }
+
+    /// How long the entity at index `i` updates for this tick with the level of detail policy,
+    /// including any time it missed. None if it doesn't update this tick
+    fn lod_dt(&mut self, i: usize, update_lod: UpdateLod, focus: Point, dt: f64) -> Option<f64> {
+        let id = self.entity_registry.id_of(i)?;
+        let to_focus = self.entity_registry.get_one(i)?.get_box().center() - focus;
+        match update_lod.rate_at(f64::hypot(to_focus.x, to_focus.y)) {
+            UpdateRate::EveryTick => Some(
+                self.lod_elapsed
+                    .remove(&id)
+                    .map(|(elapsed, _)| elapsed)
+                    .unwrap_or(0.0)
+                    + dt,
+            ),
+            UpdateRate::Every(interval) => {
+                let (elapsed, ticks) = self.lod_elapsed.remove(&id).unwrap_or((0.0, 0));
+                let (elapsed, ticks) = (elapsed + dt, ticks + 1);
+                if ticks >= interval {
+                    Some(elapsed)
+                } else {
+                    self.lod_elapsed.insert(id, (elapsed, ticks));
+                    None
+                }
+            }
+            UpdateRate::Frozen => None,
+        }
+    }
+
+    /// Sets whether entities entering and exiting regions of named cells with no solidity emit
+    /// [RegionEvent]s. Unlike blocking collisions, these are for overlap with a region, such as
+    /// region based game logic
+    pub fn set_region_events(&mut self, emit_region_events: bool) {
+        self.emit_region_events = emit_region_events;
+        self.entity_regions.clear();
+        self.region_events.clear();
+    }
+
+    /// Sets whether effects that change an entity's health emit [HealthChange]s,
+    /// attributed to the effect's source
+    pub fn set_health_change_events(&mut self, emit_health_changes: bool) {
+        self.emit_health_changes = emit_health_changes;
+        self.health_changes.clear();
+    }
+
+    /// Takes the health changes emitted since the last time they were taken, in the order
+    /// they happened
+    pub fn take_health_changes(&mut self) -> Vec<HealthChange> {
+        std::mem::take(&mut self.health_changes)
+    }
+
+    /// Adds a health pool for a group of entities to share (see
+    /// [Entity::set_shared_health](crate::gameobject::entity::Entity::set_shared_health)).
+    /// Damage to any member reduces the pool, and all of its members die when it runs out
+    pub fn add_shared_health(&mut self, health: Health) -> SharedHealthId {
+        let id = SharedHealthId(self.next_shared_health);
+        self.next_shared_health += 1;
+        self.shared_health.insert(id, health);
+        id
+    }
+
+    /// The shared health pool with the given id, if it exists
+    pub fn shared_health(&self, id: SharedHealthId) -> Option<&Health> {
+        self.shared_health.get(&id)
+    }
+
+    /// Removes a shared health pool, returning it. Its members go back to using their own health
+    pub fn remove_shared_health(&mut self, id: SharedHealthId) -> Option<Health> {
+        self.shared_health.remove(&id)
+    }
+
+    /// Takes the region events emitted since the last time they were taken, in the order
+    /// they happened
+    pub fn take_region_events(&mut self) -> Vec<RegionEvent> {
+        std::mem::take(&mut self.region_events)
+    }
+
+    /// Takes the cells hit by projectiles since the last time they were taken, along with
+    /// the projectile that hit each one, in the order they were hit
+    pub fn take_wall_impacts(&mut self) -> Vec<(EntityId, WallImpact)> {
+        std::mem::take(&mut self.wall_impacts)
+    }
+
+    /// Moves the wall impacts from this tick's moves off of the entities and onto the scene
+    fn collect_wall_impacts(&mut self) {
+        for i in 0..self.entity_registry.len() {
+            let id = match self.entity_registry.id_of(i) {
+                Some(id) => id,
+                None => continue,
+            };
+            if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                let impacts = registered_entity.inner_entity_mut().take_wall_impacts();
+                self.wall_impacts
+                    .extend(impacts.into_iter().map(|impact| (id, impact)));
+            }
+        }
+    }
+
+    /// The nearest entity that `actor` is within range of interacting with, for showing an
+    /// interaction prompt. Distance is the gap between their boxes, so large interactables like
+    /// doors can be used from anywhere along them
+    pub fn nearest_interactable(&self, actor: EntityId) -> Option<EntityId> {
+        let actor_box = *self.entity_registry.get_by_id(actor)?.get_box();
+        self.entity_registry
+            .iter_with_ids()
+            .filter(|(id, _)| *id != actor)
+            .filter_map(|(id, e)| {
+                let interactable = e.inner_entity().get_interactable()?;
+                let gap = actor_box.gap_to(e.get_box());
+                (gap <= interactable.range).then_some((id, gap))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, _)| id)
+    }
+
+    /// Makes `actor` interact with the nearest interactable in range, emitting an [Interaction].
+    /// Returns the entity interacted with, if there was one
+    pub fn interact(&mut self, actor: EntityId) -> Option<EntityId> {
+        let target = self.nearest_interactable(actor)?;
+        let action = self
+            .entity_registry
+            .get_by_id(target)?
+            .inner_entity()
+            .get_interactable()?
+            .action
+            .clone();
+        self.interactions.push(Interaction {
+            actor,
+            target,
+            action,
+        });
+        Some(target)
+    }
+
+    /// Takes the interactions since the last call, in the order they happened
+    pub fn take_interactions(&mut self) -> Vec<Interaction> {
+        std::mem::take(&mut self.interactions)
+    }
+
+    fn handle_interaction_requests(&mut self) {
+        let requested: Vec<usize> = self
+            .entity_registry
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, e)| e.inner_entity_mut().take_interaction_request().then_some(i))
+            .collect();
+        for i in requested {
+            if let Some(actor) = self.entity_registry.id_of(i) {
+                self.interact(actor);
+            }
+        }
+    }
+
+    /// Switches gravity on or off for the whole scene. While it's off every airborne entity
+    /// floats, with the [ZeroGravity] drag if there is one
+    pub fn set_world_gravity(&mut self, enabled: bool) {
+        self.gravity_disabled = !enabled;
+    }
+
+    /// Whether gravity is switched on for the scene
+    pub fn has_world_gravity(&self) -> bool {
+        !self.gravity_disabled
+    }
+
+    /// Sets the regions where airborne entities float instead of falling. An entity straddling
+    /// a region's boundary floats only while its center is inside of the region. `None` has
+    /// no zero gravity regions
+    pub fn set_zero_gravity(&mut self, zero_gravity: Option<ZeroGravity>) -> PhysicsResult<()> {
+        if zero_gravity.as_ref().map(|z| z.drag < 0.0).unwrap_or(false) {
+            return Err(PhysicsError::Drag);
+        }
+        self.zero_gravity = zero_gravity;
+        Ok(())
+    }
+
+    /// The regions where airborne entities float instead of falling
+    pub fn get_zero_gravity(&self) -> Option<&ZeroGravity> {
+        self.zero_gravity.as_ref()
+    }
+
+    /// Sets how entities standing on platforms that
+    /// [carry riders](crate::gameobject::entity::Entity::set_carries_riders) ride them.
+    /// `None` doesn't track what entities are standing on, so platforms move out from under them
+    pub fn set_platform_riding(&mut self, riding: Option<PlatformRiding>) -> PhysicsResult<()> {
+        if riding.map(|r| r.tolerance < 0.0).unwrap_or(false) {
+            return Err(PhysicsError::RidingTolerance);
+        }
+        self.platform_riding = riding;
+        if riding.is_none() {
+            for registered_entity in self.entity_registry.iter_mut() {
+                registered_entity.inner_entity_mut().set_standing_on(None);
+            }
+        }
+        Ok(())
+    }
+
+    /// How entities ride the platforms they stand on
+    pub fn get_platform_riding(&self) -> Option<&PlatformRiding> {
+        self.platform_riding.as_ref()
+    }
+
+    /// Ticks the platforms that carry riders ahead of the other entities, moving their riders
+    /// along by as much as they moved so the riders' own moves start from where they're
+    /// carried to. Returns the indices of the platforms ticked, which is none without riding
+    fn tick_platforms(&mut self, dt: f64) -> ScarabResult<Vec<usize>> {
+        if self.platform_riding.is_none() {
+            return Ok(Vec::new());
+        }
         let mut args = GameTickArgs {
             field: &self.field,
             pending_effects: &mut self.pending_effects,
             dt,
         };
+        let mut moves = Vec::new();
         for (i, registered_entity) in self.entity_registry.iter_mut().enumerate() {
+            if !registered_entity.inner_entity().carries_riders() {
+                continue;
+            }
+            let start = *registered_entity.get_box().pos();
             registered_entity.game_tick(i, &mut args)?;
+            moves.push((i, *registered_entity.get_box().pos() - start));
         }
 
-        self.handle_entity_collisions()?;
+        let deltas: HashMap<EntityId, Point> = moves
+            .iter()
+            .filter_map(|&(i, delta)| self.entity_registry.id_of(i).map(|id| (id, delta)))
+            .collect();
+        for registered_entity in self.entity_registry.iter_mut() {
+            let entity = registered_entity.inner_entity_mut();
+            // A rider that's jumped is on its own
+            if entity.is_airborne() {
+                continue;
+            }
+            if let Some(delta) = entity.get_standing_on().and_then(|p| deltas.get(&p)) {
+                let pos = *entity.get_box().pos();
+                entity.get_box_mut().set_pos(pos + [delta.x, delta.y]);
+            }
+        }
+        Ok(moves.into_iter().map(|(i, _)| i).collect())
+    }
+
+    /// Finds the platform each entity is standing on. Entities on top of a platform within the
+    /// tolerance stand on it, landing if they were falling onto it, while those that jump are
+    /// left to fly and those that walk off or are left behind start falling
+    fn update_riders(&mut self) {
+        let tolerance = match self.platform_riding {
+            Some(riding) => riding.tolerance,
+            None => return,
+        };
+        let platforms: Vec<(EntityId, PhysBox)> = self
+            .entity_registry
+            .iter_with_ids()
+            .filter(|(_, e)| e.inner_entity().carries_riders())
+            .map(|(id, e)| (id, *e.get_box()))
+            .collect();
+        for registered_entity in self.entity_registry.iter_mut() {
+            let entity = registered_entity.inner_entity_mut();
+            if entity.carries_riders() || entity.get_attachment().is_some() {
+                continue;
+            }
+            let rider = *entity.get_box();
+            let rising = entity.is_airborne() && entity.get_velocity().y < 0.0;
+            let platform = if rising {
+                None
+            } else {
+                platforms.iter().find(|(_, platform)| {
+                    rider.right_x() > platform.left_x()
+                        && rider.left_x() < platform.right_x()
+                        && (platform.top_y() - rider.bottom_y()).abs() <= tolerance
+                })
+            };
+            match platform {
+                Some(&(id, platform)) => {
+                    if entity.is_airborne() {
+                        entity.land();
+                    }
+                    entity
+                        .get_box_mut()
+                        .set_pos([rider.left_x(), platform.top_y() - rider.size().h].into());
+                    entity.set_standing_on(Some(id));
+                }
+                None if entity.get_standing_on().is_some() => {
+                    entity.set_standing_on(None);
+                    if !entity.is_airborne() {
+                        entity.launch(0.0);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Floats the entities in zero gravity, and brings back gravity for the rest
+    fn update_floating(&mut self) {
+        if !self.gravity_disabled && self.zero_gravity.is_none() {
+            // Nothing can float, but whatever was floating before gravity came back has to fall
+            for registered_entity in self.entity_registry.iter_mut() {
+                registered_entity.inner_entity_mut().set_floating(None);
+            }
+            return;
+        }
+        let drag = self.zero_gravity.as_ref().map(|z| z.drag).unwrap_or(0.0);
+        for registered_entity in self.entity_registry.iter_mut() {
+            let center = registered_entity.get_box().center();
+            let in_region = || {
+                let zero_gravity = self.zero_gravity.as_ref()?;
+                let cell = self.field.cell_at_pos(center)?;
+                let name = cell
+                    .name()
+                    .filter(|_| !cell.get_solidity().has_solidity())?;
+                Some(zero_gravity.regions.iter().any(|r| r == name))
+            };
+            let floating = self.gravity_disabled || in_region().unwrap_or(false);
+            registered_entity
+                .inner_entity_mut()
+                .set_floating(floating.then_some(drag));
+        }
+    }
+
+    fn update_regions(&mut self) {
+        if !self.emit_region_events {
+            return;
+        }
+
+        let mut entity_regions = HashMap::new();
+        for (id, registered_entity) in self.entity_registry.iter_with_ids() {
+            let mut regions: Vec<String> = Vec::new();
+            for cell in self
+                .field
+                .cells_overlapping_box(registered_entity.get_box())
+            {
+                if let Some(name) = cell.name() {
+                    if !cell.get_solidity().has_solidity() && !regions.iter().any(|r| r == name) {
+                        regions.push(name.to_string());
+                    }
+                }
+            }
 
-        self.process_pending_effects()?;
+            let previous = self.entity_regions.remove(&id).unwrap_or_default();
+            // Exits come first so that moving between regions reads as leaving one
+            // and then entering the next
+            for region in previous.iter().filter(|r| !regions.contains(r)) {
+                self.region_events.push(RegionEvent::Exit {
+                    entity: id,
+                    region: region.clone(),
+                });
+            }
+            for region in regions.iter().filter(|r| !previous.contains(r)) {
+                self.region_events.push(RegionEvent::Enter {
+                    entity: id,
+                    region: region.clone(),
+                });
+            }
+            entity_regions.insert(id, regions);
+        }
+        self.entity_regions = entity_regions;
+    }
 
+    /// Sets how overlapping entities are pushed apart each tick. `None` shifts each one out of
+    /// the others in a single pass, which can leave crowds overlapping and jittering
+    pub fn set_overlap_push(&mut self, overlap_push: Option<OverlapPush>) -> PhysicsResult<()> {
+        if let Some(push) = overlap_push {
+            if !(push.strength > 0.0 && push.strength <= 1.0) || push.iterations == 0 {
+                return Err(PhysicsError::OverlapPush);
+            }
+        }
+        self.overlap_push = overlap_push;
         Ok(())
     }
 
-    // TODO! Find a way to pin the return type of this to a specific type within the registry
-    /// Optionally returns a mutable reference to the scene's player
-    pub fn player_mut(&mut self) -> Option<&mut E::Player> {
-        self.entity_registry.player_mut()
+    /// How overlapping entities are pushed apart each tick
+    pub fn get_overlap_push(&self) -> Option<&OverlapPush> {
+        self.overlap_push.as_ref()
     }
 
-    fn handle_entity_collisions(&mut self) -> ScarabResult<()> {
-        // This is kinda gross, but I don't really know how else to do it
-        // we'll see later how necessary it is to change
-        for this_index in 0..self.entity_registry.len() {
-            if let Some(this_one) = self.entity_registry.get_one(this_index) {
-                if !this_one.get_solidity().has_solidity() {
-                    continue;
-                }
+    /// Sets the order that pairs of solid entities are shifted apart in when there's no
+    /// overlap push
+    pub fn set_pair_order(&mut self, pair_order: PairOrder) {
+        self.pair_order = pair_order;
+    }
 
-                let this_one_box = *this_one.get_box();
+    /// The order that pairs of solid entities are shifted apart in
+    pub fn get_pair_order(&self) -> PairOrder {
+        self.pair_order
+    }
 
-                for other_index in 0..this_index {
-                    if this_index == other_index {
-                        continue;
-                    }
-                    if let Some(other_one) = self.entity_registry.get_one_mut(other_index) {
-                        if other_one.get_solidity().has_solidity() {
-                            other_one
-                                .get_box_mut()
-                                .shift_to_nonoverlapping(&this_one_box);
-                        }
-                    }
+    /// Every pair of solid entities as `(moved, fixed)`, in the order that `moved` is shifted
+    /// out of `fixed` when there's no overlap push. The order only depends on the
+    /// [PairOrder] and the entities, never on how they happen to be iterated.
+    /// Entities attached to one another aren't paired, and with [PlatformRiding] the platforms
+    /// are always fixed so that riders are pushed out of them instead of the other way around
+    pub fn collision_pairs(&self) -> Vec<(EntityId, EntityId)> {
+        let riding = self.platform_riding.is_some();
+        let mut solid: Vec<_> = self
+            .entity_registry
+            .iter_with_ids()
+            .filter(|(_, e)| e.get_solidity().has_solidity())
+            .map(|(id, e)| {
+                let parent = e.inner_entity().get_attachment().map(|a| a.parent);
+                let platform = riding && e.inner_entity().carries_riders();
+                (id, e.uuid(), parent, platform)
+            })
+            .collect();
+        match self.pair_order {
+            // Registry ids are already ascending
+            PairOrder::Registration => {}
+            PairOrder::Uuid => solid.sort_by_key(|&(_, uuid, _, _)| uuid),
+        }
+
+        let mut pairs = Vec::with_capacity(solid.len() * solid.len().saturating_sub(1) / 2);
+        for (later, &(fixed, _, fixed_parent, fixed_platform)) in solid.iter().enumerate() {
+            for &(moved, _, moved_parent, moved_platform) in &solid[..later] {
+                if moved_parent == Some(fixed) || fixed_parent == Some(moved) {
+                    continue;
+                }
+                if moved_platform && !fixed_platform {
+                    pairs.push((fixed, moved));
+                } else {
+                    pairs.push((moved, fixed));
                 }
             }
         }
-        Ok(())
+        pairs
     }
 
-    fn process_pending_effects(&mut self) -> ScarabResult<()> {
-        let _ = self.pending_effects.drain_filter(|effect| {
+    /// Attaches `child` to `parent` so it moves along with it, keeping its current position
+    /// relative to it. Returns whether both exist to be attached
+    pub fn attach(&mut self, child: EntityId, parent: EntityId) -> bool {
+        let parent_pos = match self.entity_registry.get_by_id(parent) {
+            Some(parent_entity) if parent != child => *parent_entity.get_box().pos(),
+            _ => return false,
+        };
+        match self.entity_registry.get_by_id_mut(child) {
+            Some(child_entity) => {
+                let offset = *child_entity.get_box().pos() - parent_pos;
+                child_entity
+                    .inner_entity_mut()
+                    .set_attachment(Some(Attachment {
+                        parent,
+                        offset: [offset.x, offset.y],
+                    }));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Detaches `child` from whatever it's attached to, returning what that was
+    pub fn detach(&mut self, child: EntityId) -> Option<EntityId> {
+        let entity = self
+            .entity_registry
+            .get_by_id_mut(child)?
+            .inner_entity_mut();
+        let parent = entity.get_attachment().map(|a| a.parent);
+        entity.set_attachment(None);
+        parent
+    }
+
+    /// Picks up the throwable entity `target` (see [Entity::set_throwable]), attaching it on
+    /// top of `holder`. Returns whether it was grabbed
+    pub fn grab(&mut self, holder: EntityId, target: EntityId) -> bool {
+        let holder_box = match self.entity_registry.get_by_id(holder) {
+            Some(holder_entity) if holder != target => *holder_entity.get_box(),
+            _ => return false,
+        };
+        let target_entity = match self.entity_registry.get_by_id_mut(target) {
+            Some(e) => e,
+            None => return false,
+        };
+        let entity = target_entity.inner_entity_mut();
+        if entity.get_throw_impulse().is_none() || entity.get_attachment().is_some() {
+            return false;
+        }
+        entity.set_velocity([0.0, 0.0].into());
+        let size = *entity.get_box().size();
+        let pos = [
+            holder_box.center().x - size.w / 2.0,
+            holder_box.top_y() - size.h,
+        ];
+        entity.get_box_mut().set_pos(pos.into());
+        self.attach(target, holder)
+    }
+
+    /// Throws the throwable entity that `holder` is carrying along `aim`, detaching it and
+    /// launching it as a projectile with its throw impulse. Returns the entity thrown
+    pub fn throw(&mut self, holder: EntityId, aim: Vec2d) -> Option<EntityId> {
+        let length = f64::hypot(aim[0], aim[1]);
+        if length == 0.0 {
+            return None;
+        }
+        let (i, impulse) = self.entity_registry.iter().enumerate().find_map(|(i, e)| {
+            let entity = e.inner_entity();
+            let held = entity.get_attachment().map(|a| a.parent) == Some(holder);
+            held.then(|| entity.get_throw_impulse().map(|impulse| (i, impulse)))
+                .flatten()
+        })?;
+        let thrown = self.entity_registry.id_of(i)?;
+        let entity = self.entity_registry.get_one_mut(i)?.inner_entity_mut();
+        entity.set_attachment(None);
+        entity.set_projectile(true);
+        entity.apply_impulse([aim[0] / length * impulse, aim[1] / length * impulse]);
+        Some(thrown)
+    }
+
+    /// Moves attached entities to their parents, detaching them from despawned ones
+    fn update_attachments(&mut self) {
+        let positions: HashMap<EntityId, Point> = self
+            .entity_registry
+            .iter_with_ids()
+            .map(|(id, e)| (id, *e.get_box().pos()))
+            .collect();
+        for registered_entity in self.entity_registry.iter_mut() {
+            let entity = registered_entity.inner_entity_mut();
+            let attachment = match entity.get_attachment() {
+                Some(attachment) => *attachment,
+                None => continue,
+            };
+            match positions.get(&attachment.parent) {
+                Some(parent_pos) => entity
+                    .get_box_mut()
+                    .set_pos(*parent_pos + attachment.offset),
+                None => entity.set_attachment(None),
+            }
+        }
+    }
+
+    // TODO! Find a way to pin the return type of this to a specific type within the registry
+    /// Optionally returns a mutable reference to the scene's player
+    pub fn player_mut(&mut self) -> Option<&mut E::Player> {
+        self.entity_registry.player_mut()
+    }
+
+    fn handle_entity_collisions(&mut self) -> ScarabResult<()> {
+        if let Some(overlap_push) = self.overlap_push {
+            self.relax_entity_overlaps(overlap_push);
+            return Ok(());
+        }
+
+        // Earlier shifts can make or break later overlaps, so each pair is checked as it's reached
+        for (moved, fixed) in self.collision_pairs() {
+            let (fixed_box, fixed_mass) = match self.entity_registry.get_by_id(fixed) {
+                Some(fixed) => (*fixed.get_box(), fixed.inner_entity().get_mass()),
+                None => continue,
+            };
+            let moved_entity = match self.entity_registry.get_by_id_mut(moved) {
+                Some(moved) => moved,
+                None => continue,
+            };
+            let moved_mass = moved_entity.inner_entity().get_mass();
+            if moved_mass.is_none() && fixed_mass.is_none() {
+                moved_entity
+                    .get_box_mut()
+                    .shift_to_nonoverlapping(&fixed_box);
+                continue;
+            }
+
+            // The separation is split so the lighter entity moves more, and one without a mass
+            // doesn't move at all
+            let moved_pos = *moved_entity.get_box().pos();
+            let mut separated = *moved_entity.get_box();
+            separated.shift_to_nonoverlapping(&fixed_box);
+            let shift = *separated.pos() - moved_pos;
+            let inverse_mass = |mass: Option<Scalar>| mass.map(|m| 1.0 / m).unwrap_or(0.0);
+            let moved_share =
+                inverse_mass(moved_mass) / (inverse_mass(moved_mass) + inverse_mass(fixed_mass));
+            moved_entity
+                .get_box_mut()
+                .set_pos(moved_pos + [shift.x * moved_share, shift.y * moved_share]);
+            if let Some(fixed) = self.entity_registry.get_by_id_mut(fixed) {
+                let fixed_share = 1.0 - moved_share;
+                fixed
+                    .get_box_mut()
+                    .set_pos(*fixed_box.pos() - [shift.x * fixed_share, shift.y * fixed_share]);
+            }
+        }
+        Ok(())
+    }
+
+    fn relax_entity_overlaps(&mut self, overlap_push: OverlapPush) {
+        // The same pairs as without an overlap push, so attached entities still aren't pushed
+        // apart and platforms are always the fixed side
+        let pairs: Vec<(usize, usize)> = self
+            .collision_pairs()
+            .into_iter()
+            .filter_map(|(moved, fixed)| {
+                let registry = &self.entity_registry;
+                Some((registry.index_of(moved)?, registry.index_of(fixed)?))
+            })
+            .collect();
+        let riding = self.platform_riding.is_some();
+        let movers: Vec<(Option<Scalar>, bool)> = self
+            .entity_registry
+            .iter()
+            .map(|e| {
+                let entity = e.inner_entity();
+                (entity.get_mass(), riding && entity.carries_riders())
+            })
+            .collect();
+        // How much of a pair's push each one takes. Platforms don't give way to riders,
+        // and like without an overlap push, the lighter entity moves more while one without
+        // a mass doesn't move at all. Two without a mass share it evenly
+        let shares = |moved: usize, fixed: usize| -> (f64, f64) {
+            let ((moved_mass, moved_platform), (fixed_mass, fixed_platform)) =
+                (movers[moved], movers[fixed]);
+            if fixed_platform && !moved_platform {
+                return (1.0, 0.0);
+            }
+            if moved_mass.is_none() && fixed_mass.is_none() {
+                return (0.5, 0.5);
+            }
+            let inverse_mass = |mass: Option<Scalar>| mass.map(|m| 1.0 / m).unwrap_or(0.0);
+            let moved_share =
+                inverse_mass(moved_mass) / (inverse_mass(moved_mass) + inverse_mass(fixed_mass));
+            (moved_share, 1.0 - moved_share)
+        };
+
+        for _ in 0..overlap_push.iterations {
+            let boxes: Vec<PhysBox> = self.entity_registry.iter().map(|e| *e.get_box()).collect();
+
+            // Each overlapping pair is separated along the axis they overlap the least on.
+            // (entity, axis, direction, overlap) for both entities of every overlapping pair
+            let mut contacts = Vec::new();
+            for &(i, j) in &pairs {
+                let (a, b) = (&boxes[i], &boxes[j]);
+                if !a.has_overlap(b) {
+                    continue;
+                }
+                let overlap_x = a.right_x().min(b.right_x()) - a.left_x().max(b.left_x());
+                let overlap_y = a.bottom_y().min(b.bottom_y()) - a.top_y().max(b.top_y());
+                let (axis, overlap, a_ahead) = if overlap_x <= overlap_y {
+                    (0, overlap_x, a.center().x > b.center().x)
+                } else {
+                    (1, overlap_y, a.center().y > b.center().y)
+                };
+                let (a_dir, b_dir) = if a_ahead { (1, 0) } else { (0, 1) };
+                contacts.push((i, j, axis, a_dir, b_dir, overlap));
+            }
+            if contacts.is_empty() {
+                return;
+            }
+
+            // Entities pushed from both sides along an axis are pinned there, so a surrounded
+            // entity stays put while the ones around it make room
+            let mut pushed_towards = vec![[[false; 2]; 2]; boxes.len()];
+            for &(i, j, axis, a_dir, b_dir, _) in &contacts {
+                pushed_towards[i][axis][a_dir] = true;
+                pushed_towards[j][axis][b_dir] = true;
+            }
+            let pinned =
+                |e: usize, axis: usize| pushed_towards[e][axis][0] && pushed_towards[e][axis][1];
+
+            // The largest push in each direction along each axis for every entity, so that
+            // several neighbors pushing the same way don't fling it further than any one needs
+            let mut pushes = vec![[[0.0; 2]; 2]; boxes.len()];
+            for &(i, j, axis, a_dir, b_dir, overlap) in &contacts {
+                let (a_share, b_share) = match shares(i, j) {
+                    (a_share, b_share) if a_share == 0.0 || b_share == 0.0 => (a_share, b_share),
+                    shared => match (pinned(i, axis), pinned(j, axis)) {
+                        (true, false) => (0.0, 1.0),
+                        (false, true) => (1.0, 0.0),
+                        _ => shared,
+                    },
+                };
+                let push = overlap * overlap_push.strength;
+                let a_push: &mut f64 = &mut pushes[i][axis][a_dir];
+                *a_push = a_push.max(push * a_share);
+                let b_push: &mut f64 = &mut pushes[j][axis][b_dir];
+                *b_push = b_push.max(push * b_share);
+            }
+
+            for (e, registered_entity) in self.entity_registry.iter_mut().enumerate() {
+                let mut shift = [0.0; 2];
+                for (axis, axis_shift) in shift.iter_mut().enumerate() {
+                    if !pinned(e, axis) {
+                        *axis_shift = pushes[e][axis][1] - pushes[e][axis][0];
+                    }
+                }
+                if shift != [0.0; 2] {
+                    let layers = registered_entity.inner_entity().get_collision_layers();
+                    let physbox = registered_entity.get_box_mut();
+                    // Pushed up against a wall the entity stops there instead of going into it
+                    let pos = self
+                        .field
+                        .sweep_box_blocked_by(physbox, shift, |c| c.is_solid_to(layers))
+                        .map(|hit| hit.pos)
+                        .unwrap_or(*physbox.pos() + shift);
+                    physbox.set_pos(pos);
+                }
+            }
+        }
+    }
+
+    fn process_pending_effects(&mut self, dt: f64) -> ScarabResult<()> {
+        let ids: Vec<EntityId> = if self.emit_health_changes {
+            self.entity_registry
+                .iter_with_ids()
+                .map(|(id, _)| id)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let health_changes = &mut self.health_changes;
+        let shared_health = &mut self.shared_health;
+        let _ = self.pending_effects.drain_filter(|effect| {
+            let persistent = effect.effect.advance(dt);
+            let source = effect.source.and_then(|s| ids.get(s.index).copied());
             let keep_effect = self
                 .entity_registry
                 .iter_mut()
@@ -175,13 +1549,41 @@ where
                     if effect.source.map_or(true, |s| s.should_apply_effect(i))
                         && (*effect.target).can_target(e)
                     {
+                        let mut pool = e
+                            .inner_entity()
+                            .get_shared_health()
+                            .and_then(|id| shared_health.get_mut(&id));
+                        // A destroyed member can't be hit, even though the rest of its group lives on
+                        if pool.is_some() && e.get_health().is_dead() {
+                            return None;
+                        }
+                        // The effect damages the pool in place of the member's own health
+                        if let Some(pool) = pool.as_mut() {
+                            std::mem::swap(e.get_health_mut(), *pool);
+                        }
+                        let before = e.get_health().current();
                         let res = effect.effect.apply_effect(e).ok();
+                        let amount = e.get_health().current() - before;
+                        if let Some(pool) = pool {
+                            std::mem::swap(e.get_health_mut(), pool);
+                        }
+                        e.inner_entity_mut().react_to_health_change(amount);
+                        if amount != 0.0 {
+                            if let Some(&target) = ids.get(i) {
+                                health_changes.push(HealthChange {
+                                    target,
+                                    source,
+                                    amount,
+                                });
+                            }
+                        }
                         Some(res).flatten()
                     } else {
                         None
                     }
                 })
-                .any(|b| b);
+                // Not `any` so that the effect is applied to every target, not just up to the first
+                .fold(persistent, |keep, b| keep || b);
 
             effect.source.map(|s| {
                 self.entity_registry
@@ -199,9 +1601,194 @@ where
             // Drain filter *REMOVES* when true
             !keep_effect
         });
+        self.kill_depleted_pool_members();
 
         Ok(())
     }
+
+    /// Kills every member of a shared health pool that has run out
+    fn kill_depleted_pool_members(&mut self) {
+        for entity in self.entity_registry.iter_mut() {
+            let depleted = entity
+                .inner_entity()
+                .get_shared_health()
+                .and_then(|id| self.shared_health.get(&id))
+                .map(Health::is_dead)
+                .unwrap_or(false);
+            let health = entity.get_health_mut();
+            if depleted && !health.is_dead() {
+                let amount = health.current();
+                health.raw_damage(amount);
+                entity.inner_entity_mut().react_to_health_change(-amount);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Where an entity is placed when it's spawned (see [Scene::spawn_entity])
+pub enum SpawnPlacement {
+    /// Exactly where it is, even if that's inside a wall or another entity
+    Exact,
+    /// The nearest position within the radius where it doesn't overlap another entity or a
+    /// cell it collides with. Spawning fails if there isn't one
+    NearestClear(Scalar),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The order that pairs of solid entities are shifted apart in (see [Scene::collision_pairs])
+pub enum PairOrder {
+    /// By when the entities were registered, which is only repeatable if they're always
+    /// registered in the same order
+    #[default]
+    Registration,
+    /// By the entities' uuids, which they keep when saved and loaded, so the order is the same
+    /// however they're registered. For reproducible replays
+    Uuid,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Named regions of air cells where airborne entities float instead of falling
+/// (see [Scene::set_zero_gravity])
+pub struct ZeroGravity {
+    /// The names of the zero gravity regions (see [Cell::set_name](crate::gameobject::field::Cell::set_name))
+    pub regions: Vec<String>,
+    /// The fraction of their velocity that floating entities lose per second.
+    /// Must be non-negative, 0 drifts forever
+    pub drag: Scalar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// How entities stand on and ride the platforms that
+/// [carry riders](crate::gameobject::entity::Entity::set_carries_riders) (see [Scene::set_platform_riding])
+pub struct PlatformRiding {
+    /// How far an entity's feet can be from the top of a platform and still be standing on it.
+    /// Must be non-negative
+    pub tolerance: Scalar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// How overlapping entities are pushed apart by [Scene::set_overlap_push]
+pub struct OverlapPush {
+    /// The number of times per tick that overlaps are relaxed, more iterations let crowds
+    /// settle into a non-overlapping arrangement within a single tick
+    pub iterations: u32,
+    /// The fraction in (0, 1] of each overlap that's pushed apart per iteration.
+    /// Lower strengths spread the separation over more iterations (or ticks) for smoother motion
+    pub strength: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A change to an entity's health made by an effect
+pub struct HealthChange {
+    /// The entity whose health changed
+    pub target: EntityId,
+    /// The entity that caused the effect, if it had a source
+    pub source: Option<EntityId>,
+    /// How much the health changed by, negative for damage
+    pub amount: Scalar,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An entity interacting with an [Interactable](crate::gameobject::entity::Interactable)
+pub struct Interaction {
+    /// The entity that interacted, usually the player
+    pub actor: EntityId,
+    /// The entity interacted with
+    pub target: EntityId,
+    /// The interactable's action
+    pub action: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A prefab waiting in a [SpawnQueue] to be spawned
+pub struct QueuedSpawn {
+    /// The name of the prefab (see [Scene::register_prefab])
+    pub prefab: String,
+    /// Where the prefab's box is centered when it spawns
+    pub center: Point,
+}
+
+/// A shared handle to a scene's queue of prefabs to spawn (see [Scene::spawn_queue]).
+/// Clones push to the same queue
+pub type SpawnQueue = Shared<Vec<QueuedSpawn>>;
+
+/// A shared handle to a scene's named boolean flags (see [Scene::game_flags]).
+/// Clones read and write the same flags
+pub type GameFlags = Shared<HashMap<String, bool>>;
+
+impl GameFlags {
+    /// Whether the flag called `name` is set. Flags that were never set aren't
+    pub fn get(&self, name: &str) -> bool {
+        self.borrow().get(name).copied().unwrap_or(false)
+    }
+
+    /// Sets or clears the flag called `name`
+    pub fn set(&self, name: &str, value: bool) {
+        self.borrow_mut().insert(name.to_string(), value);
+    }
+
+    /// Flips the flag called `name`, returning its new value
+    pub fn toggle(&self, name: &str) -> bool {
+        let value = !self.get(name);
+        self.set(name, value);
+        value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An entity crossing into or out of a named region of air cells
+pub enum RegionEvent {
+    /// The entity started overlapping the region
+    Enter {
+        /// The entity that entered
+        entity: EntityId,
+        /// The name of the region
+        region: String,
+    },
+    /// The entity stopped overlapping the region
+    Exit {
+        /// The entity that exited
+        entity: EntityId,
+        /// The name of the region
+        region: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How often an entity is updated by [Scene::tick_entities_around]
+pub enum UpdateRate {
+    /// Updated on every tick
+    EveryTick,
+    /// Updated once every this many ticks
+    Every(u32),
+    /// Not updated at all
+    Frozen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A level of detail policy for updating entities less often the further they are from the camera
+pub struct UpdateLod {
+    /// Entities with their centers within this distance of the focus update every tick
+    pub near: Scalar,
+    /// Entities within this distance, but beyond `near`, update every `interval` ticks.
+    /// Those any further away are frozen
+    pub far: Scalar,
+    /// The number of ticks between updates for entities between `near` and `far`
+    pub interval: u32,
+}
+
+impl UpdateLod {
+    /// How often an entity `distance` from the focus updates
+    pub fn rate_at(&self, distance: Scalar) -> UpdateRate {
+        if distance <= self.near {
+            UpdateRate::EveryTick
+        } else if distance <= self.far {
+            UpdateRate::Every(self.interval)
+        } else {
+            UpdateRate::Frozen
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -214,3 +1801,1377 @@ pub struct GameTickArgs<'a, E> {
     /// The change in time for this update
     pub dt: f64,
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        effect::Effect,
+        error::{ScarabError, SpawnError},
+        gameobject::{
+            entity::{
+                loot::{DropEntry, DropTable},
+                registry::test::TestEntity,
+                spawn_table::StatOverrides,
+                Interactable,
+            },
+            field::{Cell, CellColorView, FieldColorView},
+        },
+        rendering::sprite::hit_effects::HitEffectsConfig,
+        types::{physbox::PhysBox, NO_SOLIDITY, SOLID},
+    };
+
+    pub(crate) fn test_scene() -> Scene<TestEntity, FieldColorView> {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let color_view = CellColorView { color: [1.0; 4] };
+        Scene::new(
+            field,
+            FieldColorView {
+                solid_view: color_view.clone(),
+                air_view: color_view.clone(),
+                default_view: color_view,
+            },
+        )
+    }
+
+    #[test]
+    fn level_entities_draw_in_id_order_every_frame() {
+        let mut scene = test_scene();
+        scene.set_y_sort(true);
+        let mut overhead = TestEntity::with_box([50.0, 10.0, 5.0, 5.0]);
+        overhead.0.set_z_layer(1);
+        let overhead = scene.register_entity(overhead).unwrap();
+        let first = scene
+            .register_entity(TestEntity::with_box([10.0, 40.0, 5.0, 5.0]))
+            .unwrap();
+        let second = scene
+            .register_entity(TestEntity::with_box([20.0, 40.0, 5.0, 5.0]))
+            .unwrap();
+        // Walks up the screen past the level pair
+        let mut walker = TestEntity::with_box([30.0, 62.0, 5.0, 5.0]);
+        walker.0.set_max_velocity(100.0).unwrap();
+        walker.0.set_velocity([0.0, -10.0].into());
+        let walker = scene.register_entity(walker).unwrap();
+
+        let mut orders = vec![];
+        for _ in 0..5 {
+            orders.push(scene.render_order());
+            scene.tick_entities(1.0).unwrap();
+        }
+        assert_eq!(
+            orders,
+            vec![
+                vec![first, second, walker, overhead],
+                vec![first, second, walker, overhead],
+                vec![first, second, walker, overhead],
+                vec![walker, first, second, overhead],
+                vec![walker, first, second, overhead],
+            ]
+        );
+
+        // Level with each other the walker is drawn after the ones before it
+        scene
+            .entity_registry
+            .get_by_id_mut(walker)
+            .unwrap()
+            .0
+            .get_box_mut()
+            .set_pos([30.0, 40.0].into());
+        assert_eq!(scene.render_order(), vec![first, second, walker, overhead]);
+    }
+
+    #[test]
+    fn register_entities_returns_ascending_ids() {
+        let mut scene = test_scene();
+
+        let ids = scene
+            .register_entities(
+                (0..3).map(|i| TestEntity::with_box([i as f64 * 10.0, 0.0, 5.0, 5.0])),
+            )
+            .unwrap();
+
+        assert_eq!(ids.len(), 3);
+        assert!(ids[0] < ids[1] && ids[1] < ids[2]);
+        assert_eq!(scene.entities().len(), 3);
+    }
+
+    #[test]
+    fn despawn_tagged_removes_only_tagged() {
+        let mut scene = test_scene();
+        let mut entities: Vec<_> = (0..4)
+            .map(|i| TestEntity::with_box([i as f64 * 10.0, 0.0, 5.0, 5.0]))
+            .collect();
+        entities[1].0.add_tag("wave-1");
+        entities[3].0.add_tag("wave-1");
+        entities[2].0.add_tag("wave-2");
+        let ids = scene.register_entities(entities).unwrap();
+
+        let removed = scene.despawn_tagged("wave-1");
+
+        assert_eq!(removed.len(), 2);
+        assert!(removed.iter().all(|e| e.0.has_tag("wave-1")));
+        let remaining: Vec<_> = scene.entities().iter_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(remaining, vec![ids[0], ids[2]]);
+    }
+
+    #[test]
+    fn despawn_entities_by_id() {
+        let mut scene = test_scene();
+        let ids = scene
+            .register_entities(
+                (0..3).map(|i| TestEntity::with_box([i as f64 * 10.0, 0.0, 5.0, 5.0])),
+            )
+            .unwrap();
+
+        let removed = scene.despawn_entities(&[ids[0], ids[2]]);
+
+        assert_eq!(removed.len(), 2);
+        assert_eq!(scene.entities().id_of(0), Some(ids[1]));
+        assert_eq!(scene.entities().len(), 1);
+    }
+
+    #[test]
+    fn despawn_dead_rolls_loot() {
+        let mut scene = test_scene();
+        let mut entities: Vec<_> = (0..3)
+            .map(|i| TestEntity::with_box([i as f64 * 10.0, 0.0, 4.0, 4.0]))
+            .collect();
+        entities[0].0.get_health_mut().raw_damage(100.0);
+        entities[0].0.set_drop_table(Some(DropTable {
+            entries: vec![DropEntry::new("coin", 1.0)],
+            no_drop_weight: 0.0,
+        }));
+        entities[2].0.get_health_mut().raw_damage(100.0);
+        let ids = scene.register_entities(entities).unwrap();
+
+        let dead = scene.despawn_dead(&mut StdRng::seed_from_u64(0));
+
+        assert_eq!(dead.len(), 2);
+        let loot = dead[0].1.as_ref().unwrap();
+        assert_eq!(loot.item, "coin");
+        assert_eq!(loot.pos, [2.0, 2.0].into());
+        assert!(dead[1].1.is_none());
+        assert_eq!(scene.entities().id_of(0), Some(ids[1]));
+    }
+
+    #[test]
+    fn dropped_loot_is_queued_to_spawn_as_pickups() {
+        let mut scene = test_scene();
+        scene.register_prefab("coin", || TestEntity::with_box([0.0, 0.0, 2.0, 2.0]));
+        let mut entity = TestEntity::with_box([40.0, 40.0, 4.0, 4.0]);
+        entity.0.get_health_mut().raw_damage(100.0);
+        let mut coins = DropEntry::new("coin", 1.0);
+        coins.quantity = (3, 3);
+        entity.0.set_drop_table(Some(DropTable {
+            entries: vec![coins],
+            no_drop_weight: 0.0,
+        }));
+        scene.register_entity(entity).unwrap();
+
+        scene.despawn_dead(&mut StdRng::seed_from_u64(0));
+        assert_eq!(scene.spawn_queue().len(), 3);
+        let coins = scene.spawn_queued();
+
+        assert_eq!(coins.len(), 3);
+        assert!(coins.iter().all(|&coin| {
+            scene.entities().get_by_id(coin).unwrap().get_box().center() == [42.0, 42.0].into()
+        }));
+        assert!(scene.take_failed_spawns().is_empty());
+    }
+
+    #[test]
+    fn entities_in_cone_hits_only_within_angle() {
+        let mut scene = test_scene();
+        // Centers at (60, 50) ahead, (50, 70) 90 degrees off-axis, (70, 58) about 22 degrees off-axis
+        // and (72, 50) just out of range
+        let ids = scene
+            .register_entities(vec![
+                TestEntity::with_box([58.0, 48.0, 4.0, 4.0]),
+                TestEntity::with_box([48.0, 68.0, 4.0, 4.0]),
+                TestEntity::with_box([68.0, 56.0, 4.0, 4.0]),
+                TestEntity::with_box([70.0, 48.0, 4.0, 4.0]),
+            ])
+            .unwrap();
+
+        let apex = [50.0, 50.0].into();
+        let half_angle = core::f64::consts::FRAC_PI_4;
+        let hit = scene.entities_in_cone(apex, [1.0, 0.0], half_angle, 21.8, |_, _| true);
+        assert_eq!(hit, vec![ids[0], ids[2]]);
+
+        // Narrower than the off-axis entity's angle, even though its box pokes into the cone
+        let hit = scene.entities_in_cone(apex, [2.0, 0.0], 0.3, 21.8, |_, _| true);
+        assert_eq!(hit, vec![ids[0]]);
+
+        let hit = scene.entities_in_cone(apex, [1.0, 0.0], half_angle, 21.8, |id, _| id != ids[0]);
+        assert_eq!(hit, vec![ids[2]]);
+    }
+
+    #[test]
+    fn distant_entities_freeze_until_camera_approaches() {
+        let mut scene = test_scene();
+        scene.set_update_lod(Some(UpdateLod {
+            near: 20.0,
+            far: 50.0,
+            interval: 2,
+        }));
+        let mut entity = TestEntity::with_box([60.0, 50.0, 4.0, 4.0]);
+        entity.0.set_max_velocity(100.0).unwrap();
+        entity.0.set_velocity([10.0, 0.0].into());
+        let id = scene.register_entity(entity).unwrap();
+        let x = |scene: &Scene<TestEntity, FieldColorView>| {
+            scene.entities().get_by_id(id).unwrap().0.get_box().pos().x
+        };
+
+        // Beyond the far threshold it doesn't move at all
+        for _ in 0..5 {
+            scene.tick_entities_around(0.1, [0.0, 52.0].into()).unwrap();
+        }
+        assert_eq!(x(&scene), 60.0);
+
+        // In the reduced range it only updates every other tick, but catches up
+        let mut xs = Vec::new();
+        for _ in 0..4 {
+            scene
+                .tick_entities_around(0.1, [32.0, 52.0].into())
+                .unwrap();
+            xs.push(x(&scene));
+        }
+        assert!(xs
+            .iter()
+            .zip([60.0, 62.0, 62.0, 64.0])
+            .all(|(x, expected)| (x - expected).abs() < 1e-9));
+
+        // Near the camera it updates every tick
+        for _ in 0..3 {
+            scene
+                .tick_entities_around(0.1, [62.0, 52.0].into())
+                .unwrap();
+        }
+        assert!((x(&scene) - 67.0).abs() < 1e-9);
+
+        // Missed time isn't kept for entities that are gone
+        scene
+            .tick_entities_around(0.1, [32.0, 52.0].into())
+            .unwrap();
+        assert!(scene.lod_elapsed.contains_key(&id));
+        scene.despawn_entities(&[id]);
+        assert!(scene.lod_elapsed.is_empty());
+    }
+
+    #[test]
+    fn aim_assist_nudges_towards_target_by_bias() {
+        let mut scene = test_scene();
+        let target = scene
+            .register_entity(TestEntity::with_box([78.0, 48.0, 4.0, 4.0]))
+            .unwrap();
+        let mut assist = AimAssist::new(0.5, 50.0, 0.25, 0.0);
+        let origin = [50.0, 50.0].into();
+
+        // 0.2 radians below the target, turned a quarter of the way towards it
+        let aim = [2.0 * 0.2f64.cos(), 2.0 * 0.2f64.sin()];
+        let assisted = scene.assist_aim(&mut assist, origin, aim, |_, _| true);
+        assert!((f64::atan2(assisted[1], assisted[0]) - 0.15).abs() < 1e-9);
+        assert!((f64::hypot(assisted[0], assisted[1]) - 2.0).abs() < 1e-9);
+        assert_eq!(assist.sticky_target(), Some(target));
+
+        // Nothing to aim at
+        let away = [-1.0, 0.0];
+        assert_eq!(
+            scene.assist_aim(&mut assist, origin, away, |_, _| true),
+            away
+        );
+        assert_eq!(assist.sticky_target(), None);
+    }
+
+    #[derive(Debug)]
+    struct TestDamage(Scalar);
+
+    impl Effect<TestEntity> for TestDamage {
+        fn apply_effect(&mut self, target: &mut TestEntity) -> ScarabResult<bool> {
+            target.get_health_mut().raw_damage(self.0);
+            Ok(false)
+        }
+
+        fn update_src(&mut self, _src: &mut TestEntity) -> ScarabResult<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    /// Damage that respects invulnerability, like a game's attacks would
+    struct TestAttack(Scalar);
+
+    impl Effect<TestEntity> for TestAttack {
+        fn apply_effect(&mut self, target: &mut TestEntity) -> ScarabResult<bool> {
+            target.0.take_damage(self.0);
+            Ok(false)
+        }
+
+        fn update_src(&mut self, _src: &mut TestEntity) -> ScarabResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn invulnerable_target_is_still_targeted_but_takes_no_damage() {
+        let mut scene = test_scene();
+        let attacker = scene
+            .register_entity(TestEntity::with_box([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let mut statue = TestEntity::with_box([30.0, 10.0, 5.0, 5.0]);
+        statue.0.set_invulnerable(true);
+        let statue = scene.register_entity(statue).unwrap();
+        // Nearer, but can't be targeted even though it can be hurt
+        let mut decoy = TestEntity::with_box([20.0, 20.0, 5.0, 5.0]);
+        decoy.0.set_targetable(false);
+        let decoy = scene.register_entity(decoy).unwrap();
+
+        assert_eq!(
+            scene.nearest_target(attacker, 50.0, |_, _| true),
+            Some(statue)
+        );
+        let health = |scene: &Scene<TestEntity, FieldColorView>, id| {
+            scene
+                .entities()
+                .get_by_id(id)
+                .unwrap()
+                .get_health()
+                .current()
+        };
+        let before = health(&scene, statue);
+        scene.add_pending_effect(PendingEffect {
+            source: None,
+            target: Box::new(PhysBox::new([25.0, 5.0, 20.0, 30.0]).unwrap()),
+            effect: Box::new(TestAttack(5.0)),
+        });
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(health(&scene, statue), before);
+
+        // The flags are independent, the untargetable decoy can still be hurt
+        let decoy_before = health(&scene, decoy);
+        scene.add_pending_effect(PendingEffect {
+            source: None,
+            target: Box::new(PhysBox::new([18.0, 18.0, 10.0, 10.0]).unwrap()),
+            effect: Box::new(TestAttack(5.0)),
+        });
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(health(&scene, decoy), decoy_before - 5.0);
+
+        scene
+            .entity_registry
+            .get_by_id_mut(statue)
+            .unwrap()
+            .0
+            .set_invulnerable(false);
+        scene.add_pending_effect(PendingEffect {
+            source: None,
+            target: Box::new(PhysBox::new([25.0, 5.0, 20.0, 30.0]).unwrap()),
+            effect: Box::new(TestAttack(5.0)),
+        });
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(health(&scene, statue), before - 5.0);
+    }
+
+    #[test]
+    fn effect_damage_emits_health_changes_from_source() {
+        let mut scene = test_scene();
+        scene.set_health_change_events(true);
+        let attacker = scene
+            .register_entity(TestEntity::with_box([10.0, 10.0, 4.0, 4.0]))
+            .unwrap();
+        let target = scene
+            .register_entity(TestEntity::with_box([20.0, 10.0, 4.0, 4.0]))
+            .unwrap();
+        let attacker_index = scene.entities().index_of(attacker).unwrap();
+        scene.add_pending_effect(PendingEffect {
+            source: Some((attacker_index, false).into()),
+            target: Box::new(PhysBox::new([0.0, 0.0, 50.0, 50.0]).unwrap()),
+            effect: Box::new(TestDamage(3.0)),
+        });
+        scene.add_pending_effect(PendingEffect {
+            source: None,
+            target: Box::new(PhysBox::new([15.0, 0.0, 50.0, 50.0]).unwrap()),
+            effect: Box::new(TestDamage(1.0)),
+        });
+
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(
+            scene.take_health_changes(),
+            vec![
+                HealthChange {
+                    target,
+                    source: Some(attacker),
+                    amount: -3.0
+                },
+                HealthChange {
+                    target,
+                    source: None,
+                    amount: -1.0
+                },
+            ]
+        );
+        assert!(scene.take_health_changes().is_empty());
+    }
+
+    #[test]
+    fn spawning_into_an_occupied_spot_moves_to_the_nearest_clear_one() {
+        let mut scene = test_scene();
+        scene.field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([50.0, 0.0, 10.0, 100.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([60.0, 0.0, 40.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+        let spawn = |scene: &mut Scene<_, _>| {
+            let entity = TestEntity::with_box([52.0, 40.0, 10.0, 10.0]);
+            let id = scene
+                .spawn_entity(entity, SpawnPlacement::NearestClear(20.0))
+                .unwrap();
+            *scene.entities().get_by_id(id).unwrap().get_box().pos()
+        };
+
+        // Out of the wall on the closer side
+        assert_eq!(spawn(&mut scene), [60.0, 40.0].into());
+        // That side is taken now, so the other side of the wall is closest
+        assert_eq!(spawn(&mut scene), [40.0, 40.0].into());
+
+        let exact = TestEntity::with_box([52.0, 40.0, 10.0, 10.0]);
+        let id = scene.spawn_entity(exact, SpawnPlacement::Exact).unwrap();
+        assert_eq!(
+            *scene.entities().get_by_id(id).unwrap().get_box().pos(),
+            [52.0, 40.0].into()
+        );
+    }
+
+    #[test]
+    fn crowded_spawn_fails_without_registering() {
+        let mut scene = test_scene();
+        let crowd = (0..10).flat_map(|x| {
+            (0..10)
+                .map(move |y| TestEntity::with_box([x as f64 * 10.0, y as f64 * 10.0, 10.0, 10.0]))
+        });
+        scene.register_entities(crowd).unwrap();
+
+        let entity = TestEntity::with_box([45.0, 45.0, 5.0, 5.0]);
+        let err = scene
+            .spawn_entity(entity, SpawnPlacement::NearestClear(30.0))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ScarabError::PhysicsError(PhysicsError::NoClearPosition(_, radius)) if radius == 30.0
+        ));
+        assert_eq!(scene.entities().len(), 100);
+    }
+
+    fn hit_effects_scene() -> (Scene<TestEntity, FieldColorView>, EntityId) {
+        let mut scene = test_scene();
+        let mut entity = TestEntity::with_box([10.0, 10.0, 5.0, 5.0]);
+        entity.0.set_hit_effects(Some(HitEffectsConfig {
+            flash_duration: 0.2,
+            flash_color: [1.0; 4],
+            dissolve_duration: 0.3,
+            dissolve_scale: 0.0,
+        }));
+        let id = scene.register_entity(entity).unwrap();
+        (scene, id)
+    }
+
+    fn hit_with(scene: &mut Scene<TestEntity, FieldColorView>, damage: Scalar) {
+        scene.add_pending_effect(PendingEffect {
+            source: None,
+            target: Box::new(PhysBox::new([0.0, 0.0, 50.0, 50.0]).unwrap()),
+            effect: Box::new(TestDamage(damage)),
+        });
+    }
+
+    #[test]
+    fn hit_flashes_for_the_configured_duration() {
+        let (mut scene, id) = hit_effects_scene();
+        let flash = |scene: &Scene<TestEntity, FieldColorView>| {
+            let entity = scene.entities().get_by_id(id).unwrap();
+            entity.0.hit_effects().unwrap().style().flash
+        };
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(flash(&scene), None);
+
+        hit_with(&mut scene, 1.0);
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(flash(&scene), Some([1.0; 4]));
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(flash(&scene), Some([1.0; 4]));
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(flash(&scene), None);
+    }
+
+    #[test]
+    fn death_dissolves_fully_before_despawning() {
+        let (mut scene, id) = hit_effects_scene();
+        let mut rng = StdRng::seed_from_u64(0);
+        hit_with(&mut scene, 10.0);
+        scene.tick_entities(0.1).unwrap();
+
+        let mut alphas = Vec::new();
+        while scene.despawn_dead(&mut rng).is_empty() {
+            let entity = scene.entities().get_by_id(id).unwrap();
+            alphas.push(entity.0.hit_effects().unwrap().style().alpha);
+            scene.tick_entities(0.1).unwrap();
+            assert!(alphas.len() < 10, "never despawned");
+        }
+        assert!(scene.entities().get_by_id(id).is_none());
+        assert_eq!(alphas.len(), 3);
+        assert_eq!(alphas[0], 1.0);
+        assert!(alphas.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn damage_to_any_member_reduces_shared_pool_and_depleting_it_kills_all() {
+        let mut scene = test_scene();
+        let pool = scene.add_shared_health(Health::new(10.0));
+        let members: Vec<_> = (0..3)
+            .map(|i| {
+                let mut entity = TestEntity::with_box([i as f64 * 20.0, 0.0, 5.0, 5.0]);
+                entity.0.set_shared_health(Some(pool));
+                entity
+            })
+            .collect();
+        let ids = scene.register_entities(members).unwrap();
+        let loner = scene
+            .register_entity(TestEntity::with_box([80.0, 0.0, 5.0, 5.0]))
+            .unwrap();
+        let hit = |scene: &mut Scene<_, _>, x: f64, damage: Scalar| {
+            scene.add_pending_effect(PendingEffect {
+                source: None,
+                target: Box::new(PhysBox::new([x, 0.0, 5.0, 5.0]).unwrap()),
+                effect: Box::new(TestDamage(damage)),
+            });
+            scene.tick_entities(0.1).unwrap();
+        };
+
+        hit(&mut scene, 0.0, 3.0);
+        hit(&mut scene, 40.0, 2.0);
+        assert_eq!(scene.shared_health(pool).unwrap().current(), 5.0);
+        for &id in &ids {
+            let member = scene.entities().get_by_id(id).unwrap();
+            assert_eq!(member.get_health().current(), 10.0);
+        }
+
+        // A destroyed member can't be hit, but the others survive on the pool
+        scene
+            .entity_registry
+            .get_by_id_mut(ids[1])
+            .unwrap()
+            .get_health_mut()
+            .raw_damage(10.0);
+        hit(&mut scene, 20.0, 4.0);
+        assert_eq!(scene.shared_health(pool).unwrap().current(), 5.0);
+        assert_eq!(scene.despawn_dead(&mut StdRng::seed_from_u64(0)).len(), 1);
+
+        hit(&mut scene, 0.0, 5.0);
+        assert!(scene.shared_health(pool).unwrap().is_dead());
+        let dead = scene.despawn_dead(&mut StdRng::seed_from_u64(0));
+        assert_eq!(dead.len(), 2);
+        let remaining: Vec<_> = scene.entities().iter_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(remaining, vec![loner]);
+    }
+
+    #[test]
+    fn despawning_exits_the_regions_the_entity_was_in() {
+        let mut cells = vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 0.0, 50.0, 100.0]).unwrap()),
+        ];
+        cells[0].set_name(Some("a".to_string()));
+        cells[1].set_name(Some("b".to_string()));
+        let mut scene = test_scene();
+        scene.field = Field::new(cells).unwrap();
+        scene.set_region_events(true);
+        let stays = scene
+            .register_entity(TestEntity::with_box([10.0, 40.0, 4.0, 4.0]))
+            .unwrap();
+        // Straddling both regions
+        let leaves = scene
+            .register_entity(TestEntity::with_box([48.0, 40.0, 4.0, 4.0]))
+            .unwrap();
+        scene.tick_entities(0.1).unwrap();
+        scene.take_region_events();
+
+        scene.despawn_entities(&[leaves]);
+        assert_eq!(
+            scene.take_region_events(),
+            vec![
+                RegionEvent::Exit {
+                    entity: leaves,
+                    region: "a".to_string()
+                },
+                RegionEvent::Exit {
+                    entity: leaves,
+                    region: "b".to_string()
+                }
+            ]
+        );
+        assert_eq!(scene.entity_regions.keys().collect::<Vec<_>>(), [&stays]);
+
+        scene.tick_entities(0.1).unwrap();
+        assert!(scene.take_region_events().is_empty());
+    }
+
+    #[test]
+    fn moving_between_air_cells_exits_then_enters() {
+        let mut cells = vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 0.0, 50.0, 100.0]).unwrap()),
+        ];
+        cells[0].set_name(Some("a".to_string()));
+        cells[1].set_name(Some("b".to_string()));
+        let mut scene = test_scene();
+        scene.field = Field::new(cells).unwrap();
+        scene.set_region_events(true);
+
+        let mut entity = TestEntity::with_box([30.0, 40.0, 4.0, 4.0]);
+        entity.0.set_max_velocity(200.0).unwrap();
+        entity.0.set_velocity([150.0, 0.0].into());
+        let id = scene.register_entity(entity).unwrap();
+
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(
+            scene.take_region_events(),
+            vec![RegionEvent::Enter {
+                entity: id,
+                region: "a".to_string()
+            }]
+        );
+
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(
+            scene.take_region_events(),
+            vec![
+                RegionEvent::Exit {
+                    entity: id,
+                    region: "a".to_string()
+                },
+                RegionEvent::Enter {
+                    entity: id,
+                    region: "b".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_cluster_separates_around_surrounded_entity() {
+        let mut scene = test_scene();
+        scene
+            .set_overlap_push(Some(OverlapPush {
+                iterations: 4,
+                strength: 1.0,
+            }))
+            .unwrap();
+        let center = scene
+            .register_entity(TestEntity::with_box([48.0, 48.0, 4.0, 4.0]))
+            .unwrap();
+        for [x, y] in [[45.0, 48.0], [51.0, 48.0], [48.0, 45.0], [48.0, 51.0]] {
+            scene
+                .register_entity(TestEntity::with_box([x, y, 4.0, 4.0]))
+                .unwrap();
+        }
+
+        scene.tick_entities(0.1).unwrap();
+
+        let boxes: Vec<PhysBox> = scene.entities().iter().map(|e| *e.get_box()).collect();
+        for (i, a) in boxes.iter().enumerate() {
+            for b in boxes.iter().skip(i + 1) {
+                assert!(!a.has_overlap(b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+        // Surrounded on every side, so it stays put instead of being ejected
+        assert_eq!(
+            *scene.entities().get_by_id(center).unwrap().get_box().pos(),
+            [48.0, 48.0].into()
+        );
+        assert_eq!(
+            scene.set_overlap_push(Some(OverlapPush {
+                iterations: 0,
+                strength: 1.0
+            })),
+            Err(PhysicsError::OverlapPush)
+        );
+    }
+
+    #[test]
+    fn overlap_push_follows_masses_attachments_platforms_and_walls() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([100.0, 0.0, 10.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+        let color_view = CellColorView { color: [1.0; 4] };
+        let mut scene = Scene::new(
+            field,
+            FieldColorView {
+                solid_view: color_view.clone(),
+                air_view: color_view.clone(),
+                default_view: color_view,
+            },
+        );
+        scene
+            .set_overlap_push(Some(OverlapPush {
+                iterations: 1,
+                strength: 1.0,
+            }))
+            .unwrap();
+        scene
+            .set_platform_riding(Some(PlatformRiding { tolerance: 0.5 }))
+            .unwrap();
+        let mut register = |physbox: [f64; 4], mass: Option<f64>| {
+            let mut entity = TestEntity::with_box(physbox);
+            entity.0.set_mass(mass).unwrap();
+            scene.register_entity(entity).unwrap()
+        };
+        let immovable = register([20.0, 20.0, 6.0, 6.0], None);
+        let pushed = register([23.0, 20.0, 6.0, 6.0], Some(1.0));
+        let parent = register([20.0, 60.0, 6.0, 6.0], None);
+        let child = register([22.0, 60.0, 6.0, 6.0], None);
+        let platform = register([50.0, 20.0, 20.0, 4.0], None);
+        let rider = register([55.0, 17.0, 4.0, 4.0], None);
+        let by_wall = register([93.0, 60.0, 6.0, 6.0], None);
+        let beside = register([90.0, 60.0, 6.0, 6.0], None);
+        assert!(scene.attach(child, parent));
+        scene
+            .entity_registry
+            .get_by_id_mut(platform)
+            .unwrap()
+            .0
+            .set_carries_riders(true);
+
+        scene.tick_entities(0.1).unwrap();
+        let pos = |id| -> [f64; 2] {
+            let pos = *scene.entities().get_by_id(id).unwrap().get_box().pos();
+            [pos.x, pos.y]
+        };
+
+        // A massless entity doesn't budge for one with a mass
+        assert_eq!(pos(immovable), [20.0, 20.0]);
+        assert_eq!(pos(pushed), [26.0, 20.0]);
+        // Attached entities are left overlapping
+        assert_eq!(pos(parent), [20.0, 60.0]);
+        assert_eq!(pos(child), [22.0, 60.0]);
+        // The platform stays fixed while the rider is pushed out on top of it
+        assert_eq!(pos(platform), [50.0, 20.0]);
+        assert_eq!(pos(rider), [55.0, 16.0]);
+        // Pushed towards the wall it stops against it instead of going in
+        assert_eq!(pos(by_wall), [94.0, 60.0]);
+        assert_eq!(pos(beside), [88.5, 60.0]);
+    }
+
+    #[test]
+    fn uuid_pair_order_is_independent_of_registration_order() {
+        let boxes = [
+            [40.0, 40.0, 10.0, 10.0],
+            [45.0, 42.0, 10.0, 10.0],
+            [42.0, 47.0, 10.0, 10.0],
+            [48.0, 48.0, 10.0, 10.0],
+        ];
+        let saved: Vec<Vec<u8>> = boxes
+            .iter()
+            .map(|b| rmp_serde::to_vec(&TestEntity::with_box(*b)).unwrap())
+            .collect();
+
+        // Each run loads the same entities, registering them in a different order
+        let run = |order: [usize; 4]| {
+            let mut scene = test_scene();
+            scene.set_pair_order(PairOrder::Uuid);
+            for i in order {
+                let entity: TestEntity = rmp_serde::from_slice(&saved[i]).unwrap();
+                scene.register_entity(entity).unwrap();
+            }
+            let uuid_of = |id| scene.entities().get_by_id(id).unwrap().uuid();
+            let pairs: Vec<_> = scene
+                .collision_pairs()
+                .into_iter()
+                .map(|(moved, fixed)| (uuid_of(moved), uuid_of(fixed)))
+                .collect();
+            scene.tick_entities(0.1).unwrap();
+            let mut resolved: Vec<_> = scene
+                .entities()
+                .iter()
+                .map(|e| (e.uuid(), *e.get_box()))
+                .collect();
+            resolved.sort_by_key(|&(uuid, _)| uuid);
+            (pairs, resolved)
+        };
+
+        let (pairs, resolved) = run([0, 1, 2, 3]);
+        assert_eq!(pairs.len(), 6);
+        assert!(pairs
+            .windows(2)
+            .all(|w| (w[0].1, w[0].0) < (w[1].1, w[1].0)));
+        for order in [[0, 1, 2, 3], [3, 2, 1, 0], [2, 0, 3, 1]] {
+            assert_eq!(run(order), (pairs.clone(), resolved.clone()));
+        }
+    }
+
+    #[test]
+    fn interacting_near_a_chest_opens_the_nearest_one() {
+        let interactable = |physbox, action: &str| {
+            let mut entity = TestEntity::with_box(physbox);
+            entity
+                .0
+                .set_interactable(Some(Interactable {
+                    range: 3.0,
+                    action: action.to_string(),
+                }))
+                .unwrap();
+            entity
+        };
+        let mut scene = test_scene();
+        let player = scene
+            .register_entity(TestEntity::with_box([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let chest = scene
+            .register_entity(interactable([17.0, 10.0, 5.0, 5.0], "open_chest"))
+            .unwrap();
+        // Also in range, but further away
+        scene
+            .register_entity(interactable([10.0, 17.5, 5.0, 5.0], "open_door"))
+            .unwrap();
+
+        scene
+            .entity_registry
+            .get_by_id_mut(player)
+            .unwrap()
+            .0
+            .request_interaction();
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(
+            scene.take_interactions(),
+            vec![Interaction {
+                actor: player,
+                target: chest,
+                action: "open_chest".to_string()
+            }]
+        );
+        // The request is used up
+        scene.tick_entities(0.1).unwrap();
+        assert!(scene.take_interactions().is_empty());
+
+        let far_away = scene.entity_registry.get_by_id_mut(player).unwrap();
+        far_away.0.get_box_mut().set_pos([60.0, 60.0].into());
+        far_away.0.request_interaction();
+        scene.tick_entities(0.1).unwrap();
+        assert!(scene.take_interactions().is_empty());
+        assert_eq!(scene.nearest_interactable(player), None);
+    }
+
+    #[test]
+    fn crates_are_pushed_grabbed_and_thrown() {
+        let mut scene = test_scene();
+        let mut player = TestEntity::with_box([10.0, 50.0, 5.0, 5.0]);
+        player.0.set_max_velocity(10.0).unwrap();
+        player.0.set_velocity([10.0, 0.0].into());
+        let player = scene.register_entity(player).unwrap();
+        let mut crate_entity = TestEntity::with_box([16.0, 50.0, 5.0, 5.0]);
+        crate_entity.0.set_mass(Some(2.0)).unwrap();
+        crate_entity.0.set_throwable(Some(20.0)).unwrap();
+        let crate_id = scene.register_entity(crate_entity).unwrap();
+        let pos_of = |scene: &Scene<TestEntity, FieldColorView>, id| {
+            *scene.entities().get_by_id(id).unwrap().get_box().pos()
+        };
+
+        // Walking 5 to the right into the crate pushes it the 4 they'd overlap by
+        scene.tick_entities(0.5).unwrap();
+        assert_eq!(pos_of(&scene, player), [15.0, 50.0].into());
+        assert_eq!(pos_of(&scene, crate_id), [20.0, 50.0].into());
+
+        // Grabbed, it sits on top of the player and follows it around
+        assert!(scene.grab(player, crate_id));
+        assert_eq!(pos_of(&scene, crate_id), [15.0, 45.0].into());
+        scene.tick_entities(0.5).unwrap();
+        assert_eq!(pos_of(&scene, player), [20.0, 50.0].into());
+        assert_eq!(pos_of(&scene, crate_id), [20.0, 45.0].into());
+        let attachment = *scene
+            .entities()
+            .get_by_id(crate_id)
+            .unwrap()
+            .0
+            .get_attachment()
+            .unwrap();
+        assert_eq!(attachment.parent, player);
+
+        scene
+            .entity_registry
+            .get_by_id_mut(player)
+            .unwrap()
+            .0
+            .set_velocity([0.0, 0.0].into());
+        assert_eq!(scene.throw(player, [3.0, -4.0]), Some(crate_id));
+        let thrown = &scene.entities().get_by_id(crate_id).unwrap().0;
+        assert!(thrown.get_attachment().is_none());
+        assert!(thrown.is_projectile());
+        // The impulse of 20 along the aim, divided by the mass of 2
+        assert_eq!(thrown.get_velocity(), [6.0, -8.0].into());
+        scene.tick_entities(0.5).unwrap();
+        assert_eq!(pos_of(&scene, crate_id), [23.0, 41.0].into());
+        assert_eq!(scene.throw(player, [1.0, 0.0]), None);
+    }
+
+    #[test]
+    fn scene_collects_projectile_wall_impacts_as_it_ticks() {
+        let wall = PhysBox::new([100.0, 0.0, 10.0, 100.0]).unwrap();
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap()),
+            Cell::new(SOLID, wall),
+        ])
+        .unwrap();
+        let mut scene = test_scene();
+        scene.field = field;
+        let mut projectile = TestEntity::with_box([80.0, 50.0, 4.0, 4.0]);
+        projectile.0.set_max_velocity(1200.0).unwrap();
+        projectile.0.set_velocity([1200.0, 0.0].into());
+        projectile.0.set_projectile(true);
+        let projectile = scene.register_entity(projectile).unwrap();
+
+        scene.tick_entities(1.0 / 60.0).unwrap();
+
+        let impacts = scene.take_wall_impacts();
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].0, projectile);
+        assert_eq!(impacts[0].1.point, [100.0, 52.0].into());
+        assert_eq!(impacts[0].1.normal, [-1.0, 0.0]);
+        assert!(scene.take_wall_impacts().is_empty());
+    }
+
+    #[test]
+    fn unknown_queued_prefab_is_skipped_without_failing_the_tick() {
+        let mut scene = test_scene();
+        scene.register_prefab("spark", || TestEntity::with_box([0.0, 0.0, 2.0, 2.0]));
+        let queue = scene.spawn_queue();
+        for prefab in ["sprak", "spark", "spark"] {
+            queue.push(QueuedSpawn {
+                prefab: prefab.to_string(),
+                center: [50.0, 50.0].into(),
+            });
+        }
+
+        scene.tick_entities(0.1).unwrap();
+        assert!(queue.is_empty());
+        assert_eq!(scene.entities().len(), 2);
+        let failed = scene.take_failed_spawns();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0.prefab, "sprak");
+        assert!(matches!(&failed[0].1, ScarabError::UnknownPrefab(name) if name == "sprak"));
+        assert!(scene.take_failed_spawns().is_empty());
+    }
+
+    #[test]
+    fn spawn_definitions_from_file_spawn_variants_with_overridden_stats() {
+        let mut definitions = SpawnDefinitions::new();
+        definitions.insert(
+            "armored_grunt",
+            SpawnDefinition {
+                prefab: "grunt".to_string(),
+                stats: StatOverrides {
+                    max_health: Some(250.0),
+                    max_velocity: Some(5.0),
+                    tags: vec!["armored".to_string()],
+                    ..StatOverrides::default()
+                },
+                behavior: Some("patrol".to_string()),
+                animation: Some("grunt".to_string()),
+            },
+        );
+        let path = std::env::temp_dir().join(format!(
+            "scarab_spawn_definitions_{}.msgpack",
+            std::process::id()
+        ));
+        std::fs::write(&path, rmp_serde::to_vec(&definitions).unwrap()).unwrap();
+
+        let mut scene = test_scene();
+        scene.register_prefab("grunt", || {
+            let mut grunt = TestEntity::with_box([0.0, 0.0, 4.0, 6.0]);
+            grunt.0.set_max_velocity(20.0).unwrap();
+            grunt
+        });
+        let loaded = SpawnDefinitions::load(&path, rmp_serde::from_read).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, definitions);
+        scene
+            .register_spawn_definitions(loaded, &["patrol", "wander"], &["grunt"])
+            .unwrap();
+
+        let id = scene
+            .spawn_prefab("armored_grunt", [50.0, 50.0].into())
+            .unwrap();
+        let grunt = &scene.entities().get_by_id(id).unwrap().0;
+        assert_eq!(grunt.get_health().max(), 250.0);
+        assert_eq!(grunt.get_health().current(), 250.0);
+        assert_eq!(grunt.get_max_velocity(), 5.0);
+        assert!(grunt.has_tag("armored"));
+        assert_eq!(grunt.get_box().center(), [50.0, 50.0].into());
+        let definition = scene.get_spawn_definition("armored_grunt").unwrap();
+        assert_eq!(definition.behavior.as_deref(), Some("patrol"));
+        // The base prefab is left as it was
+        let base = scene.spawn_prefab("grunt", [20.0, 20.0].into()).unwrap();
+        assert_eq!(
+            scene
+                .entities()
+                .get_by_id(base)
+                .unwrap()
+                .0
+                .get_max_velocity(),
+            20.0
+        );
+
+        let mut flying = SpawnDefinitions::new();
+        flying.insert(
+            "bat",
+            SpawnDefinition {
+                prefab: "grunt".to_string(),
+                stats: StatOverrides::default(),
+                behavior: Some("fly".to_string()),
+                animation: None,
+            },
+        );
+        let err = scene
+            .register_spawn_definitions(flying, &["patrol", "wander"], &["grunt"])
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            ScarabError::SpawnError(SpawnError::UnknownBehavior(name, key, _))
+                if name == "bat" && key == "fly"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Spawn definition bat has the unknown behavior fly, expected one of [patrol, wander]"
+        );
+        assert!(scene.get_spawn_definition("bat").is_none());
+    }
+
+    #[test]
+    fn rider_stays_on_reversing_platform_and_detaches_when_it_jumps() {
+        let mut scene = test_scene();
+        scene
+            .set_platform_riding(Some(PlatformRiding { tolerance: 0.5 }))
+            .unwrap();
+        let mut platform = TestEntity::with_box([20.0, 60.0, 30.0, 5.0]);
+        platform.0.set_carries_riders(true);
+        platform.0.set_max_velocity(100.0).unwrap();
+        let platform = scene.register_entity(platform).unwrap();
+        let mut rider = TestEntity::with_box([25.0, 50.0, 5.0, 10.0]);
+        rider.0.set_gravity(Some(50.0));
+        let rider = scene.register_entity(rider).unwrap();
+        let boxes = |scene: &Scene<TestEntity, FieldColorView>| {
+            let get = |id| *scene.entities().get_by_id(id).unwrap().get_box();
+            (get(platform), get(rider))
+        };
+        let set_platform_velocity = |scene: &mut Scene<TestEntity, FieldColorView>, v: [f64; 2]| {
+            let platform = scene.entity_registry.get_by_id_mut(platform).unwrap();
+            platform.0.set_velocity(v.into());
+        };
+
+        scene.tick_entities(0.1).unwrap();
+        set_platform_velocity(&mut scene, [10.0, -10.0]);
+        for tick in 0..20 {
+            if tick == 10 {
+                // Reverses mid-travel, dropping away from under the rider
+                set_platform_velocity(&mut scene, [-10.0, 10.0]);
+            }
+            scene.tick_entities(0.1).unwrap();
+            let (p, r) = boxes(&scene);
+            let riding = &scene.entities().get_by_id(rider).unwrap().0;
+            assert_eq!(riding.get_standing_on(), Some(platform));
+            assert!(!riding.is_airborne());
+            assert!((r.bottom_y() - p.top_y()).abs() < 1e-9);
+            assert!((r.left_x() - p.left_x() - 5.0).abs() < 1e-9);
+        }
+
+        let jumper = scene.entity_registry.get_by_id_mut(rider).unwrap();
+        jumper.0.launch(20.0);
+        scene.tick_entities(0.1).unwrap();
+        let (p, r) = boxes(&scene);
+        let jumper = &scene.entities().get_by_id(rider).unwrap().0;
+        assert_eq!(jumper.get_standing_on(), None);
+        assert!(jumper.is_airborne());
+        assert!(r.bottom_y() < p.top_y());
+
+        // Falls back down onto the platform as it carries on moving
+        for _ in 0..20 {
+            scene.tick_entities(0.1).unwrap();
+        }
+        let (p, r) = boxes(&scene);
+        let landed = &scene.entities().get_by_id(rider).unwrap().0;
+        assert_eq!(landed.get_standing_on(), Some(platform));
+        assert!((r.bottom_y() - p.top_y()).abs() < 1e-9);
+        assert_eq!(
+            scene.set_platform_riding(Some(PlatformRiding { tolerance: -1.0 })),
+            Err(PhysicsError::RidingTolerance)
+        );
+    }
+
+    #[test]
+    fn falls_again_once_world_gravity_is_back_on() {
+        let mut scene = test_scene();
+        let mut entity = TestEntity::with_box([10.0, 10.0, 4.0, 4.0]);
+        entity.0.set_gravity(Some(100.0));
+        entity.0.launch(0.0);
+        let id = scene.register_entity(entity).unwrap();
+        let state = |scene: &Scene<TestEntity, FieldColorView>| {
+            let e = &scene.entities().get_by_id(id).unwrap().0;
+            (e.get_velocity().y, e.is_floating())
+        };
+
+        scene.set_world_gravity(false);
+        scene.tick_entities(0.1).unwrap();
+        assert_eq!(state(&scene), (0.0, true));
+
+        scene.set_world_gravity(true);
+        scene.tick_entities(0.1).unwrap();
+        let (speed, floating) = state(&scene);
+        assert!(!floating);
+        assert!((speed - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn falling_through_zero_gravity_region_floats_until_it_leaves() {
+        let mut zero_g = Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 40.0, 100.0, 20.0]).unwrap());
+        zero_g.set_name(Some("zero_g".to_string()));
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 100.0, 40.0]).unwrap()),
+            zero_g,
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 60.0, 100.0, 40.0]).unwrap()),
+        ])
+        .unwrap();
+        let color_view = CellColorView { color: [1.0; 4] };
+        let mut scene = Scene::new(
+            field,
+            FieldColorView {
+                solid_view: color_view.clone(),
+                air_view: color_view.clone(),
+                default_view: color_view,
+            },
+        );
+        scene
+            .set_zero_gravity(Some(ZeroGravity {
+                regions: vec!["zero_g".to_string()],
+                drag: 0.5,
+            }))
+            .unwrap();
+        let mut entity = TestEntity::with_box([10.0, 30.0, 4.0, 4.0]);
+        entity.0.set_gravity(Some(100.0));
+        entity.0.launch(0.0);
+        let id = scene.register_entity(entity).unwrap();
+        // (center y, vertical speed, floating)
+        let state = |scene: &Scene<TestEntity, FieldColorView>| {
+            let e = &scene.entities().get_by_id(id).unwrap().0;
+            (e.get_box().center().y, e.get_velocity().y, e.is_floating())
+        };
+
+        let mut path = vec![state(&scene)];
+        while path.last().unwrap().0 < 70.0 {
+            scene.tick_entities(0.02).unwrap();
+            path.push(state(&scene));
+        }
+
+        for w in path.windows(2) {
+            let ((_, before, _), (center, after, floating)) = (w[0], w[1]);
+            // Floating is decided by where the center was at the start of the tick
+            assert_eq!(floating, (40.0..60.0).contains(&(center - after * 0.02)));
+            if floating {
+                assert!(after < before);
+            } else {
+                assert!((after - before - 2.0).abs() < 1e-9);
+            }
+        }
+        // It floated for a while, then left and carried on falling
+        assert!(path.iter().any(|&(_, _, floating)| floating));
+        assert!(!path.last().unwrap().2);
+        assert_eq!(
+            scene.set_zero_gravity(Some(ZeroGravity {
+                regions: Vec::new(),
+                drag: -1.0
+            })),
+            Err(PhysicsError::Drag)
+        );
+    }
+
+    #[test]
+    fn clicking_a_cell_picks_it() {
+        let mut scene = test_scene();
+        scene.field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 0.0, 50.0, 100.0]).unwrap()),
+        ])
+        .unwrap();
+        // Twice the size of the camera, so each pixel is 2 points
+        let camera = Camera::new(
+            PhysBox::new([20.0, 0.0, 100.0, 50.0]).unwrap(),
+            [200.0, 100.0],
+        );
+
+        // (30, 10) in the world
+        let left = scene.cell_at_screen([20.0, 20.0].into(), &camera).unwrap();
+        assert_eq!(
+            scene
+                .get_field()
+                .cell_at_pos([0.0, 0.0].into())
+                .unwrap()
+                .id(),
+            left
+        );
+        // (70, 10) in the world
+        let right = scene.cell_at_screen([100.0, 20.0].into(), &camera).unwrap();
+        assert_eq!(
+            scene
+                .get_field()
+                .cell_at_pos([99.0, 0.0].into())
+                .unwrap()
+                .id(),
+            right
+        );
+        assert_ne!(left, right);
+
+        // (110, 10) is past the edge of the field
+        assert_eq!(scene.cell_at_screen([180.0, 20.0].into(), &camera), None);
+    }
+
+    #[test]
+    fn clicking_near_a_small_entity_picks_it() {
+        let mut scene = test_scene();
+        // Twice the size of the camera, so each pixel is 2 points
+        let camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [200.0, 100.0],
+        );
+        let tiny = scene
+            .register_entity(TestEntity::with_box([10.0, 10.0, 1.0, 1.0]))
+            .unwrap();
+
+        // (12, 10.5) in the world, a pixel to the right of the box
+        let click = [24.0, 21.0].into();
+        assert_eq!(scene.pick_at(click, &camera, 0.0), None);
+        assert_eq!(scene.pick_at(click, &camera, 1.5), None);
+        assert_eq!(scene.pick_at(click, &camera, 3.0), Some(tiny));
+        assert_eq!(scene.pick_at([21.0, 21.0].into(), &camera, 0.0), Some(tiny));
+
+        // Both are within reach of (12.4, 10.5), the tiny one's center is closer
+        scene
+            .register_entity(TestEntity::with_box([14.0, 10.0, 1.0, 1.0]))
+            .unwrap();
+        assert_eq!(scene.pick_at([24.8, 21.0].into(), &camera, 4.0), Some(tiny));
+
+        // (11.5, 20.5) is exactly between the two, so the one on top wins
+        let mut upper = TestEntity::with_box([12.0, 20.0, 1.0, 1.0]);
+        upper.0.set_z_layer(1);
+        let upper = scene.register_entity(upper).unwrap();
+        let lower = scene
+            .register_entity(TestEntity::with_box([10.0, 20.0, 1.0, 1.0]))
+            .unwrap();
+        let between = [23.0, 41.0].into();
+        assert_eq!(scene.pick_at(between, &camera, 2.0), Some(upper));
+        scene
+            .entity_registry
+            .get_by_id_mut(upper)
+            .unwrap()
+            .0
+            .set_z_layer(0);
+        // On the same layer the newer one is drawn on top
+        assert_eq!(scene.pick_at(between, &camera, 2.0), Some(lower));
+    }
+
+    #[test]
+    fn cells_outside_visibility_radius_are_darkened() {
+        let mut scene = test_scene();
+        scene.field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 20.0, 20.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([80.0, 0.0, 20.0, 20.0]).unwrap()),
+        ])
+        .unwrap();
+        let fog = FogOfWar {
+            darkness: 0.75,
+            falloff: 10.0,
+            tile_size: 4.0,
+        };
+        scene.set_fog_of_war(Some(fog));
+        let mut entity = TestEntity::with_box([18.0, 8.0, 4.0, 4.0]);
+        entity.0.set_visibility_radius(Some(20.0)).unwrap();
+        scene.register_entity(entity).unwrap();
+        assert_eq!(
+            TestEntity::with_box([0.0, 0.0, 1.0, 1.0])
+                .0
+                .set_visibility_radius(Some(0.0)),
+            Err(PhysicsError::VisibilityRadius)
+        );
+
+        let lights = scene.visibility_lights();
+        let brightness = |pos: [f64; 2]| {
+            let cell = scene.get_field().cell_at_pos(pos.into()).unwrap();
+            fog.brightness_at(cell.get_box().center(), &lights)
+        };
+        assert_eq!(brightness([5.0, 5.0]), 1.0);
+        assert_eq!(brightness([85.0, 5.0]), 0.25);
+    }
+
+    #[test]
+    fn lock_on_picks_nearest_and_cycles_until_target_lost() {
+        let mut scene = test_scene();
+        let camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+            [100.0, 100.0],
+        );
+        let player = scene
+            .register_entity(TestEntity::with_box([8.0, 48.0, 4.0, 4.0]))
+            .unwrap();
+        let far = scene
+            .register_entity(TestEntity::with_box([48.0, 48.0, 4.0, 4.0]))
+            .unwrap();
+        let near = scene
+            .register_entity(TestEntity::with_box([28.0, 48.0, 4.0, 4.0]))
+            .unwrap();
+        // Out of range
+        scene
+            .register_entity(TestEntity::with_box([88.0, 48.0, 4.0, 4.0]))
+            .unwrap();
+        let mut lock_on = LockOn::new(60.0, Some(1.0));
+
+        // Nothing in the facing cone
+        let facing_away = [-1.0, 0.0];
+        let lock = scene.lock_on(&mut lock_on, player, facing_away, &camera, |_, _| true);
+        assert_eq!(lock, None);
+
+        let facing = [1.0, 0.0];
+        let lock = scene.lock_on(&mut lock_on, player, facing, &camera, |_, _| true);
+        assert_eq!(lock, Some(near));
+        assert_eq!(
+            scene.cycle_lock_on(&mut lock_on, player, facing, &camera, |_, _| true),
+            Some(far)
+        );
+        assert_eq!(
+            scene.cycle_lock_on(&mut lock_on, player, facing, &camera, |_, _| true),
+            Some(near)
+        );
+        assert_eq!(scene.lock_on_direction(&lock_on, player), Some([1.0, 0.0]));
+        assert_eq!(
+            scene.lock_on_focus(&lock_on, player),
+            Some([20.0, 50.0].into())
+        );
+
+        // The lock holds while the target is valid and breaks once it dies
+        assert_eq!(
+            scene.update_lock_on(&mut lock_on, player, &camera),
+            Some(near)
+        );
+        scene
+            .entity_registry
+            .get_by_id_mut(near)
+            .unwrap()
+            .get_health_mut()
+            .raw_damage(100.0);
+        assert_eq!(scene.update_lock_on(&mut lock_on, player, &camera), None);
+        assert_eq!(lock_on.target(), None);
+    }
+}
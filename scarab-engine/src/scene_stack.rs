@@ -0,0 +1,245 @@
+use piston::{Input, RenderArgs, UpdateArgs};
+use serde::{Deserialize, Serialize};
+
+use crate::ScarabResult;
+
+/// A scene that can be layered in a [SceneStack], i.e. gameplay, a pause menu or a loading screen.
+/// `C` is whatever the scenes need to render with, i.e. the graphics backend and textures
+pub trait StackedScene<C> {
+    /// Runs the scene's update
+    fn update(&mut self, args: &UpdateArgs) -> ScarabResult<()>;
+
+    /// Renders the scene
+    fn render(&mut self, args: &RenderArgs, render_with: &mut C) -> ScarabResult<()>;
+
+    /// Handles an input event. Does nothing by default
+    fn input_event(&mut self, _input: &Input) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// What a scene in a [SceneStack] still does while another scene is on top of it.
+/// The top scene always updates, renders and receives input
+pub struct LayerFlags {
+    /// Whether the scene keeps updating, i.e. a world that carries on behind a HUD
+    pub updates_when_not_top: bool,
+    /// Whether the scene keeps rendering, i.e. paused gameplay showing behind a menu
+    pub renders_when_not_top: bool,
+    /// Whether the scene still receives input events after the scenes above it
+    pub receives_input_when_not_top: bool,
+}
+
+impl Default for LayerFlags {
+    /// Frozen and unresponsive, but still drawn underneath
+    fn default() -> Self {
+        Self {
+            updates_when_not_top: false,
+            renders_when_not_top: true,
+            receives_input_when_not_top: false,
+        }
+    }
+}
+
+/// Layered scenes where the top one is active and those below it only update, render and
+/// receive input as their [LayerFlags] allow. An [App](crate::App) runs the stack by
+/// forwarding its update, render and input events to it
+pub struct SceneStack<C> {
+    layers: Vec<(Box<dyn StackedScene<C>>, LayerFlags)>,
+}
+
+impl<C> Default for SceneStack<C> {
+    fn default() -> Self {
+        Self { layers: Vec::new() }
+    }
+}
+
+impl<C> SceneStack<C> {
+    /// An empty stack
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Puts `scene` on top of the stack
+    pub fn push(&mut self, scene: Box<dyn StackedScene<C>>, flags: LayerFlags) {
+        self.layers.push((scene, flags));
+    }
+
+    /// Removes the top scene, returning it
+    pub fn pop(&mut self) -> Option<Box<dyn StackedScene<C>>> {
+        self.layers.pop().map(|(scene, _)| scene)
+    }
+
+    /// The number of scenes in the stack
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Whether there are no scenes in the stack
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// The flags of the scene at `index`, counting up from the bottom of the stack
+    pub fn flags(&self, index: usize) -> Option<&LayerFlags> {
+        self.layers.get(index).map(|(_, flags)| flags)
+    }
+
+    /// Changes the flags of the scene at `index`, counting up from the bottom of the stack
+    pub fn flags_mut(&mut self, index: usize) -> Option<&mut LayerFlags> {
+        self.layers.get_mut(index).map(|(_, flags)| flags)
+    }
+
+    /// Updates the top scene and the scenes below it that update when they're not on top,
+    /// from the bottom up
+    pub fn update(&mut self, args: &UpdateArgs) -> ScarabResult<()> {
+        let top = self.layers.len().saturating_sub(1);
+        for (i, (scene, flags)) in self.layers.iter_mut().enumerate() {
+            if i == top || flags.updates_when_not_top {
+                scene.update(args)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the top scene and the scenes below it that render when they're not on top,
+    /// from the bottom up so that the top scene is drawn over the rest
+    pub fn render(&mut self, args: &RenderArgs, render_with: &mut C) -> ScarabResult<()> {
+        let top = self.layers.len().saturating_sub(1);
+        for (i, (scene, flags)) in self.layers.iter_mut().enumerate() {
+            if i == top || flags.renders_when_not_top {
+                scene.render(args, render_with)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gives `input` to the top scene, then to the scenes below it that receive input when
+    /// they're not on top, from the top down
+    pub fn input_event(&mut self, input: &Input) {
+        let top = self.layers.len().saturating_sub(1);
+        for (i, (scene, flags)) in self.layers.iter_mut().enumerate().rev() {
+            if i == top || flags.receives_input_when_not_top {
+                scene.input_event(input);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use piston::{Button, ButtonArgs, ButtonState, Key};
+    use shapes::Point;
+
+    use super::*;
+    use crate::{
+        gameobject::{entity::registry::test::TestEntity, field::FieldColorView},
+        scene::{test::test_scene, Scene},
+        types::physbox::HasBox,
+    };
+
+    /// The names of the scenes in the order they were drawn
+    type DrawLog = Vec<&'static str>;
+
+    struct Gameplay {
+        scene: Rc<RefCell<Scene<TestEntity, FieldColorView>>>,
+        inputs: Rc<RefCell<usize>>,
+    }
+
+    impl StackedScene<DrawLog> for Gameplay {
+        fn update(&mut self, args: &UpdateArgs) -> ScarabResult<()> {
+            self.scene.borrow_mut().tick_entities(args.dt)
+        }
+
+        fn render(&mut self, _args: &RenderArgs, drawn: &mut DrawLog) -> ScarabResult<()> {
+            drawn.push("gameplay");
+            Ok(())
+        }
+
+        fn input_event(&mut self, _input: &Input) {
+            *self.inputs.borrow_mut() += 1;
+        }
+    }
+
+    struct Menu {
+        updates: Rc<RefCell<usize>>,
+    }
+
+    impl StackedScene<DrawLog> for Menu {
+        fn update(&mut self, _args: &UpdateArgs) -> ScarabResult<()> {
+            *self.updates.borrow_mut() += 1;
+            Ok(())
+        }
+
+        fn render(&mut self, _args: &RenderArgs, drawn: &mut DrawLog) -> ScarabResult<()> {
+            drawn.push("menu");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn gameplay_under_menu_keeps_drawing_while_frozen() {
+        let mut entity = TestEntity::with_box([10.0, 10.0, 5.0, 5.0]);
+        entity.0.set_max_velocity(100.0).unwrap();
+        entity.0.set_velocity([10.0, 0.0].into());
+        let scene = Rc::new(RefCell::new(test_scene()));
+        let id = scene.borrow_mut().register_entity(entity).unwrap();
+        let inputs = Rc::new(RefCell::new(0));
+        let menu_updates = Rc::new(RefCell::new(0));
+
+        let mut stack = SceneStack::new();
+        stack.push(
+            Box::new(Gameplay {
+                scene: scene.clone(),
+                inputs: inputs.clone(),
+            }),
+            LayerFlags::default(),
+        );
+        let pos = || -> Point {
+            *scene
+                .borrow()
+                .entities()
+                .get_by_id(id)
+                .unwrap()
+                .get_box()
+                .pos()
+        };
+        let args = RenderArgs {
+            ext_dt: 0.0,
+            window_size: [10.0, 10.0],
+            draw_size: [10, 10],
+        };
+        let input = Input::Button(ButtonArgs {
+            state: ButtonState::Press,
+            button: Button::Keyboard(Key::Escape),
+            scancode: None,
+        });
+
+        // On its own the gameplay is on top
+        stack.update(&UpdateArgs { dt: 0.5 }).unwrap();
+        assert_eq!(pos(), [15.0, 10.0].into());
+
+        stack.push(
+            Box::new(Menu {
+                updates: menu_updates.clone(),
+            }),
+            LayerFlags::default(),
+        );
+        let mut drawn = DrawLog::new();
+        for _ in 0..2 {
+            stack.update(&UpdateArgs { dt: 0.5 }).unwrap();
+            stack.render(&args, &mut drawn).unwrap();
+            stack.input_event(&input);
+        }
+        assert_eq!(pos(), [15.0, 10.0].into());
+        assert_eq!(drawn, vec!["gameplay", "menu", "gameplay", "menu"]);
+        assert_eq!(*menu_updates.borrow(), 2);
+        assert_eq!(*inputs.borrow(), 0);
+
+        stack.pop();
+        stack.update(&UpdateArgs { dt: 0.5 }).unwrap();
+        stack.input_event(&input);
+        assert_eq!(pos(), [20.0, 10.0].into());
+        assert_eq!(*inputs.borrow(), 1);
+    }
+}
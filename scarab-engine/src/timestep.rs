@@ -0,0 +1,179 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::{PhysicsError, PhysicsResult};
+
+fn validate_step(step: f64) -> PhysicsResult<f64> {
+    if step > 0.0 && step.is_finite() {
+        Ok(step)
+    } else {
+        Err(PhysicsError::TimestepStep)
+    }
+}
+
+/// Loading a step of 0 would otherwise hang [FixedTimestep::advance]
+fn deserialize_step<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    validate_step(f64::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Accumulates real elapsed time and splits it into fixed-size simulation steps.
+///
+/// The time left over after consuming all of the whole steps is exposed as [FixedTimestep::alpha]
+/// so that rendering can interpolate between the last two simulated states.
+pub struct FixedTimestep {
+    /// The length of a single fixed step in seconds
+    #[serde(deserialize_with = "deserialize_step")]
+    step: f64,
+    /// The elapsed time (in seconds) that hasn't been consumed by a step yet
+    #[serde(skip)]
+    accumulator: f64,
+    /// The most steps that can be waiting to be simulated at once, if limited
+    #[serde(default)]
+    max_steps: Option<usize>,
+    /// The time remaining that the simulation is frozen for
+    #[serde(skip)]
+    hitstop: f64,
+}
+
+impl FixedTimestep {
+    /// Creates a timestep that runs fixed steps of `step` seconds.
+    /// `step` must be greater than 0 and finite
+    pub fn new(step: f64) -> PhysicsResult<Self> {
+        Ok(Self {
+            step: validate_step(step)?,
+            accumulator: 0.0,
+            max_steps: None,
+            hitstop: 0.0,
+        })
+    }
+
+    /// Creates a timestep that runs `ups` steps per second. `ups` must be greater than 0
+    pub fn from_ups(ups: u64) -> PhysicsResult<Self> {
+        Self::new(1.0 / ups as f64)
+    }
+
+    /// The length of a single fixed step in seconds
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    /// Limits the number of steps that can be waiting to be simulated at once. Time beyond the
+    /// limit is dropped, so after a long freeze the simulation slows down for a moment instead
+    /// of running so many catch-up steps that it falls even further behind. `None` never drops time
+    pub fn set_max_steps(&mut self, max_steps: Option<usize>) {
+        self.max_steps = max_steps;
+    }
+
+    /// The most steps that can be waiting to be simulated at once, if limited
+    pub fn max_steps(&self) -> Option<usize> {
+        self.max_steps
+    }
+
+    /// Freezes the simulation for `duration` seconds (i.e. for a heavy hit to land with more
+    /// weight), dropping that much of the time added instead of simulating it.
+    /// A longer hitstop already running isn't shortened
+    pub fn start_hitstop(&mut self, duration: f64) {
+        self.hitstop = self.hitstop.max(duration);
+    }
+
+    /// The time remaining that the simulation is frozen for
+    pub fn hitstop_remaining(&self) -> f64 {
+        self.hitstop
+    }
+
+    /// Adds `dt` seconds of real elapsed time to be simulated, less any of it spent in hitstop
+    pub fn add_time(&mut self, dt: f64) {
+        let stopped = dt.min(self.hitstop);
+        self.hitstop -= stopped;
+        self.accumulator += dt - stopped;
+        if let Some(max_steps) = self.max_steps {
+            self.accumulator = self.accumulator.min(max_steps as f64 * self.step);
+        }
+    }
+
+    /// If at least one whole step of time has accumulated, removes it and returns true
+    pub fn consume_step(&mut self) -> bool {
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Adds `dt` seconds of elapsed time and consumes as many whole steps as possible.
+    /// Returns the number of steps that should be simulated
+    pub fn advance(&mut self, dt: f64) -> usize {
+        self.add_time(dt);
+        let mut steps = 0;
+        while self.consume_step() {
+            steps += 1;
+        }
+        steps
+    }
+
+    /// The fraction of a step that has elapsed since the last consumed step, in the range [0, 1)
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.step
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EPSILON: f64 = 0.000_000_000_1;
+
+    #[test]
+    fn one_and_a_half_steps_leaves_half_alpha() {
+        let mut timestep = FixedTimestep::new(1.0 / 60.0).unwrap();
+
+        timestep.add_time(1.5 / 60.0);
+        assert!(timestep.consume_step());
+        assert!(!timestep.consume_step());
+        assert!((timestep.alpha() - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn advance_counts_whole_steps() {
+        let mut timestep = FixedTimestep::new(0.1).unwrap();
+
+        assert_eq!(timestep.advance(0.05), 0);
+        assert!((timestep.alpha() - 0.5).abs() < EPSILON);
+
+        assert_eq!(timestep.advance(0.26), 3);
+        assert!((timestep.alpha() - 0.1).abs() < EPSILON);
+    }
+
+    #[test]
+    fn step_must_be_positive_even_when_loaded() {
+        assert_eq!(FixedTimestep::new(0.0), Err(PhysicsError::TimestepStep));
+        assert_eq!(FixedTimestep::new(-0.1), Err(PhysicsError::TimestepStep));
+        assert_eq!(
+            FixedTimestep::new(f64::NAN),
+            Err(PhysicsError::TimestepStep)
+        );
+        assert_eq!(FixedTimestep::from_ups(0), Err(PhysicsError::TimestepStep));
+
+        let saved = rmp_serde::to_vec(&FixedTimestep::new(0.1).unwrap()).unwrap();
+        assert!(rmp_serde::from_slice::<FixedTimestep>(&saved).is_ok());
+        let mut zero_step = FixedTimestep::new(0.1).unwrap();
+        zero_step.step = 0.0;
+        let saved = rmp_serde::to_vec(&zero_step).unwrap();
+        assert!(rmp_serde::from_slice::<FixedTimestep>(&saved).is_err());
+    }
+
+    #[test]
+    fn freeze_runs_at_most_max_steps() {
+        let mut timestep = FixedTimestep::from_ups(60).unwrap();
+        timestep.set_max_steps(Some(5));
+
+        // A 30 second freeze would otherwise be 1800 steps
+        assert_eq!(timestep.advance(30.0), 5);
+        assert!(timestep.alpha().abs() < EPSILON);
+
+        // Back to normal afterwards
+        assert_eq!(timestep.advance(1.5 / 60.0), 1);
+        assert!((timestep.alpha() - 0.5).abs() < EPSILON);
+    }
+}
@@ -3,17 +3,46 @@ use core::ops::{Add, BitAnd, BitOr, Mul, Not, Sub};
 use graphics::types::{Scalar, Vec2d};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use shapes::Point;
+use shapes::{Point, Size};
 pub use uuid::Uuid;
 
 /// Stuff for rectangular physics items
 pub mod physbox;
+/// Handles to values shared with the scene from outside of it
+pub mod shared;
 
 lazy_static! {
     /// Pre-calculate the square root of 2
     pub static ref ROOT_2: f64 = f64::sqrt(2.0);
 }
 
+/// The default tolerance used by the engine when comparing floating-point positions
+pub const DEFAULT_EPSILON: Scalar = 1e-9;
+
+/// A trait for comparing floating-point game values where exact equality is unreliable
+pub trait ApproxEq {
+    /// Whether every component of `self` and `other` differ by at most `epsilon`
+    fn approx_eq(&self, other: &Self, epsilon: Scalar) -> bool;
+}
+
+impl ApproxEq for Scalar {
+    fn approx_eq(&self, other: &Self, epsilon: Scalar) -> bool {
+        (self - other).abs() <= epsilon
+    }
+}
+
+impl ApproxEq for Point {
+    fn approx_eq(&self, other: &Self, epsilon: Scalar) -> bool {
+        self.x.approx_eq(&other.x, epsilon) && self.y.approx_eq(&other.y, epsilon)
+    }
+}
+
+impl ApproxEq for Size {
+    fn approx_eq(&self, other: &Self, epsilon: Scalar) -> bool {
+        self.w.approx_eq(&other.w, epsilon) && self.h.approx_eq(&other.h, epsilon)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 /// Represents the edges of a rectangular game object
 pub enum BoxEdge {
@@ -205,6 +234,12 @@ impl From<Point> for Velocity {
     }
 }
 
+impl ApproxEq for Velocity {
+    fn approx_eq(&self, other: &Self, epsilon: Scalar) -> bool {
+        self.x.approx_eq(&other.x, epsilon) && self.y.approx_eq(&other.y, epsilon)
+    }
+}
+
 /// A trait for a gameobject that has a unique identifier
 pub trait HasUuid {
     /// The object's unique identifier
@@ -342,6 +377,11 @@ impl Solidity {
     pub fn has_solidity(&self) -> bool {
         self != &NO_SOLIDITY
     }
+
+    /// Returns true if some edges can be passed and others can't (i.e. one-way platforms)
+    pub fn is_one_way(&self) -> bool {
+        self.has_solidity() && self != &SOLID
+    }
 }
 
 impl BitAnd<Solidity> for Solidity {
@@ -365,6 +405,75 @@ impl Not for Solidity {
     }
 }
 
+/// A set of up to 32 collision layers (i.e. players, enemies, projectiles).
+/// Cells are only solid to entities on one of the layers the cell is [solid to](crate::gameobject::field::Cell::solid_to),
+/// so a cell can block some classes of entity and not others.
+/// Games name their own layers with [CollisionLayers::layer].
+///
+/// Cells and entities default to [ALL_LAYERS], so a [SOLID] cell is solid to every entity and
+/// a [NO_SOLIDITY] cell is solid to none, the same as without layers
+/// ```
+/// use scarab_engine::types::{CollisionLayers, ALL_LAYERS};
+/// const PLAYER_SOLID: CollisionLayers = CollisionLayers::layer(0);
+/// const PROJECTILE_SOLID: CollisionLayers = CollisionLayers::layer(2);
+///
+/// let player_walls = PLAYER_SOLID;
+/// assert!(player_walls.intersects(PLAYER_SOLID | PROJECTILE_SOLID));
+/// assert!(!player_walls.intersects(PROJECTILE_SOLID));
+/// assert!(ALL_LAYERS.contains(PLAYER_SOLID | PROJECTILE_SOLID));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CollisionLayers(pub u32);
+
+/// Every collision layer
+pub const ALL_LAYERS: CollisionLayers = CollisionLayers(u32::MAX);
+/// No collision layers
+pub const NO_LAYERS: CollisionLayers = CollisionLayers(0);
+
+impl CollisionLayers {
+    /// The single layer numbered `n`. Must be less than 32
+    pub const fn layer(n: u32) -> Self {
+        Self(1 << n)
+    }
+
+    /// Whether any layer is in both `self` and `other`
+    pub fn intersects(&self, other: CollisionLayers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Whether every layer in `other` is also in `self`
+    pub fn contains(&self, other: CollisionLayers) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        ALL_LAYERS
+    }
+}
+
+impl BitAnd<CollisionLayers> for CollisionLayers {
+    type Output = CollisionLayers;
+    fn bitand(self, rhs: CollisionLayers) -> Self::Output {
+        CollisionLayers(self.0 & rhs.0)
+    }
+}
+
+impl BitOr<CollisionLayers> for CollisionLayers {
+    type Output = CollisionLayers;
+    fn bitor(self, rhs: CollisionLayers) -> Self::Output {
+        CollisionLayers(self.0 | rhs.0)
+    }
+}
+
+impl Not for CollisionLayers {
+    type Output = CollisionLayers;
+    fn not(self) -> Self::Output {
+        CollisionLayers(!self.0)
+    }
+}
+
 /// A trait for gameobjects that have a solidity component
 pub trait HasSolidity {
     /// The game object's solidity component
@@ -377,6 +486,17 @@ impl HasSolidity for Solidity {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// What happens to the current health when the maximum health changes
+pub enum MaxHealthPolicy {
+    /// The current health stays the same (limited by the new maximum)
+    KeepCurrent,
+    /// The current health stays the same fraction of the maximum
+    KeepFraction,
+    /// The current health is set to the new maximum
+    HealToFull,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 /// The health of a game object
 pub struct Health {
@@ -398,6 +518,11 @@ impl Health {
         self.curr -= amt;
     }
 
+    /// Whether the health has run out
+    pub fn is_dead(&self) -> bool {
+        self.curr <= 0.0
+    }
+
     /// The current health value
     pub fn current(&self) -> Scalar {
         self.curr
@@ -412,8 +537,24 @@ impl Health {
     pub fn fraction(&self) -> Scalar {
         self.curr / self.max
     }
+
+    /// Changes the maximum health (i.e. from a buff or leveling up), updating the current
+    /// health according to `policy`
+    pub fn set_max(&mut self, max: Scalar, policy: MaxHealthPolicy) {
+        self.curr = match policy {
+            MaxHealthPolicy::KeepCurrent => self.curr.min(max),
+            MaxHealthPolicy::KeepFraction if self.max > 0.0 => self.fraction() * max,
+            MaxHealthPolicy::KeepFraction | MaxHealthPolicy::HealToFull => max,
+        };
+        self.max = max;
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+/// A handle to a health pool shared by a group of entities, owned by the scene
+/// (see [Scene::add_shared_health](crate::scene::Scene::add_shared_health))
+pub struct SharedHealthId(pub(crate) usize);
+
 /// A trait for gameobjects that have a health component
 pub trait HasHealth {
     /// A reference to the game object's internal health
@@ -432,3 +573,34 @@ impl HasHealth for Health {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn max_health_policies() {
+        let half_health = || {
+            let mut health = Health::new(100.0);
+            health.raw_damage(50.0);
+            health
+        };
+
+        let expected = [
+            (MaxHealthPolicy::KeepCurrent, 50.0),
+            (MaxHealthPolicy::KeepFraction, 75.0),
+            (MaxHealthPolicy::HealToFull, 150.0),
+        ];
+        for (policy, current) in expected {
+            let mut health = half_health();
+            health.set_max(150.0, policy);
+            assert_eq!(health.max(), 150.0);
+            assert_eq!(health.current(), current, "{:?}", policy);
+        }
+
+        // Lowering the max never leaves more than the max
+        let mut health = half_health();
+        health.set_max(40.0, MaxHealthPolicy::KeepCurrent);
+        assert_eq!(health.current(), 40.0);
+    }
+}
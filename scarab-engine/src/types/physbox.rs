@@ -2,7 +2,7 @@ use graphics::types::{Scalar, Vec2d};
 use serde::{Deserialize, Serialize};
 use shapes::{Point, Size};
 
-use super::{Axis, BoxEdge};
+use super::{ApproxEq, Axis, BoxEdge};
 use crate::{PhysicsError, PhysicsResult};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -78,6 +78,42 @@ impl PhysBox {
         self.pos.x + self.size.w
     }
 
+    /// The top left corner, the same as [PhysBox::pos]
+    pub fn top_left(&self) -> Point {
+        self.pos
+    }
+
+    /// The top right corner
+    pub fn top_right(&self) -> Point {
+        [self.right_x(), self.top_y()].into()
+    }
+
+    /// The bottom left corner
+    pub fn bottom_left(&self) -> Point {
+        [self.left_x(), self.bottom_y()].into()
+    }
+
+    /// The bottom right corner
+    pub fn bottom_right(&self) -> Point {
+        [self.right_x(), self.bottom_y()].into()
+    }
+
+    /// The center of the box
+    pub fn center(&self) -> Point {
+        self.pos + [self.size.w / 2.0, self.size.h / 2.0]
+    }
+
+    /// The midpoint of the given edge
+    pub fn edge_midpoint(&self, edge: BoxEdge) -> Point {
+        let center = self.center();
+        match edge {
+            BoxEdge::Top => [center.x, self.top_y()].into(),
+            BoxEdge::Left => [self.left_x(), center.y].into(),
+            BoxEdge::Bottom => [center.x, self.bottom_y()].into(),
+            BoxEdge::Right => [self.right_x(), center.y].into(),
+        }
+    }
+
     /// Translates self so that the y value of the top edge is 'val'
     pub fn set_top_y(&mut self, val: Scalar) {
         self.pos.y = val;
@@ -90,7 +126,7 @@ impl PhysBox {
 
     /// Translates self so that the y value of the bottom edge is 'val'
     pub fn set_bottom_y(&mut self, val: Scalar) {
-        self.pos.y = val - self.size.w;
+        self.pos.y = val - self.size.h;
     }
 
     /// Translates self so that the x value of the right edge is 'val'
@@ -98,6 +134,17 @@ impl PhysBox {
         self.pos.x = val - self.size.w;
     }
 
+    /// A copy of self with each edge moved inwards by `amount`.
+    /// The inset is limited so that the result always keeps a positive size
+    pub fn inset(&self, amount: Scalar) -> Self {
+        let dx = amount.min(self.size.w / 4.0);
+        let dy = amount.min(self.size.h / 4.0);
+        Self {
+            pos: self.pos + [dx, dy],
+            size: [self.size.w - 2.0 * dx, self.size.h - 2.0 * dy].into(),
+        }
+    }
+
     /// Computes the area of self
     pub fn area(&self) -> Scalar {
         self.size.w * self.size.h
@@ -235,6 +282,40 @@ impl PhysBox {
             && (other.pos.y < this_bottom_right.y)
     }
 
+    /// The shortest distance between the edges of `self` and `other`, 0 if they touch or overlap
+    /// ```
+    /// use scarab_engine::types::physbox::PhysBox;
+    ///
+    /// let box1 = PhysBox::new([0.0, 0.0, 5.0, 5.0].into()).unwrap();
+    /// let box2 = PhysBox::new([8.0, 9.0, 5.0, 5.0].into()).unwrap();
+    ///
+    /// assert_eq!(box1.gap_to(&box2), 5.0);
+    /// ```
+    pub fn gap_to(&self, other: &Self) -> Scalar {
+        let dx = (other.left_x() - self.right_x())
+            .max(self.left_x() - other.right_x())
+            .max(0.0);
+        let dy = (other.top_y() - self.bottom_y())
+            .max(self.top_y() - other.bottom_y())
+            .max(0.0);
+        f64::hypot(dx, dy)
+    }
+
+    /// The shortest distance from `pos` to the box, 0 if it's on or inside of the box
+    /// ```
+    /// use scarab_engine::types::physbox::PhysBox;
+    ///
+    /// let physbox = PhysBox::new([0.0, 0.0, 5.0, 5.0].into()).unwrap();
+    ///
+    /// assert_eq!(physbox.distance_to_pos([8.0, 9.0].into()), 5.0);
+    /// assert_eq!(physbox.distance_to_pos([2.0, 3.0].into()), 0.0);
+    /// ```
+    pub fn distance_to_pos(&self, pos: Point) -> Scalar {
+        let dx = (pos.x - self.right_x()).max(self.left_x() - pos.x).max(0.0);
+        let dy = (pos.y - self.bottom_y()).max(self.top_y() - pos.y).max(0.0);
+        f64::hypot(dx, dy)
+    }
+
     /// Is `self` fully contained within `other`
     /// Uses fully inclusive logic so that a.is_fully_contained_by(&a) is true
     /// i.e. in set notation `a.is_fully_contained_by(&b)` means that $a \subset b$
@@ -296,6 +377,12 @@ impl PhysBox {
     }
 }
 
+impl ApproxEq for PhysBox {
+    fn approx_eq(&self, other: &Self, epsilon: Scalar) -> bool {
+        self.pos.approx_eq(&other.pos, epsilon) && self.size.approx_eq(&other.size, epsilon)
+    }
+}
+
 impl HasBox for PhysBox {
     fn get_box(&self) -> &PhysBox {
         self
@@ -469,6 +556,26 @@ mod test {
         assert!(!physbox1_1.has_overlap(&physbox1_0));
     }
 
+    #[test]
+    fn approx_eq_within_epsilon() {
+        let physbox1 = PhysBox::new([1.0, 2.0, 3.0, 4.0]).unwrap();
+        let physbox2 = PhysBox::new([1.0 + 1e-4, 2.0 - 1e-4, 3.0, 4.0 + 1e-4]).unwrap();
+
+        assert!(physbox1.approx_eq(&physbox2, 1e-3));
+        assert!(physbox2.approx_eq(&physbox1, 1e-3));
+        assert_ne!(physbox1, physbox2);
+    }
+
+    #[test]
+    fn approx_eq_outside_epsilon() {
+        let physbox1 = PhysBox::new([1.0, 2.0, 3.0, 4.0]).unwrap();
+        let moved = PhysBox::new([1.01, 2.0, 3.0, 4.0]).unwrap();
+        let resized = PhysBox::new([1.0, 2.0, 3.0, 4.01]).unwrap();
+
+        assert!(!physbox1.approx_eq(&moved, 1e-3));
+        assert!(!physbox1.approx_eq(&resized, 1e-3));
+    }
+
     #[test]
     fn box_contains_itself() {
         let physbox = PhysBox::new([1.0, 50.0, 20.0, 20.0]).unwrap();
@@ -508,4 +615,60 @@ mod test {
         assert!(physbox3.is_fully_contained_by(&physbox1));
         assert!(physbox4.is_fully_contained_by(&physbox1));
     }
+
+    #[test]
+    fn set_bottom_y_uses_height() {
+        let mut physbox = PhysBox::new([0.0, 0.0, 6.0, 4.0]).unwrap();
+        physbox.set_bottom_y(20.0);
+
+        assert_eq!(physbox.pos(), &[0.0, 16.0].into());
+        assert_eq!(physbox.bottom_y(), 20.0);
+    }
+
+    #[test]
+    fn set_edge_moves_each_edge_of_non_square_box_onto_value() {
+        for edge in [BoxEdge::Top, BoxEdge::Left, BoxEdge::Bottom, BoxEdge::Right] {
+            let mut physbox = PhysBox::new([0.0, 0.0, 6.0, 4.0]).unwrap();
+            physbox.set_edge(20.0, edge);
+
+            assert_eq!(physbox.get_edge(edge), 20.0, "{:?}", edge);
+            assert_eq!(physbox.size(), &[6.0, 4.0].into());
+        }
+
+        // The floor's top edge is where a box resting on it ends
+        let floor = PhysBox::new([0.0, 50.0, 100.0, 10.0]).unwrap();
+        let mut physbox = PhysBox::new([10.0, 0.0, 6.0, 4.0]).unwrap();
+        physbox.set_touching_opposite_edge(&floor, BoxEdge::Bottom);
+        assert_eq!(physbox.pos(), &[10.0, 46.0].into());
+    }
+
+    #[test]
+    fn inset_keeps_positive_size() {
+        let physbox = PhysBox::new([0.0, 0.0, 4.0, 8.0]).unwrap();
+
+        assert_eq!(
+            physbox.inset(1.0),
+            PhysBox::new([1.0, 1.0, 2.0, 6.0]).unwrap()
+        );
+        assert_eq!(
+            physbox.inset(10.0),
+            PhysBox::new([1.0, 2.0, 2.0, 4.0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn corners_and_midpoints_are_y_down() {
+        let physbox = PhysBox::new([2.0, 4.0, 6.0, 10.0]).unwrap();
+
+        assert_eq!(physbox.top_left(), [2.0, 4.0].into());
+        assert_eq!(physbox.top_right(), [8.0, 4.0].into());
+        assert_eq!(physbox.bottom_left(), [2.0, 14.0].into());
+        assert_eq!(physbox.bottom_right(), [8.0, 14.0].into());
+        assert_eq!(physbox.center(), [5.0, 9.0].into());
+
+        assert_eq!(physbox.edge_midpoint(BoxEdge::Top), [5.0, 4.0].into());
+        assert_eq!(physbox.edge_midpoint(BoxEdge::Left), [2.0, 9.0].into());
+        assert_eq!(physbox.edge_midpoint(BoxEdge::Bottom), [5.0, 14.0].into());
+        assert_eq!(physbox.edge_midpoint(BoxEdge::Right), [8.0, 9.0].into());
+    }
 }
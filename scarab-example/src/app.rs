@@ -1,4 +1,8 @@
-use std::{fmt::Debug, fs::File, io::Write};
+use std::{
+    fmt::Debug,
+    fs::{self, File},
+    io::Write,
+};
 
 use glutin_window::GlutinWindow as Window;
 use opengl_graphics::{GlGraphics, OpenGL};
@@ -6,6 +10,7 @@ use piston::input::{RenderArgs, UpdateArgs};
 use piston::window::WindowSettings;
 use piston::{CloseArgs, EventSettings, Events, Input, ResizeArgs};
 use scarab_engine::{
+    build_window,
     gameobject::{
         entity::registry::{RegisteredDebugEntity, RegisteredEntity},
         field::Field,
@@ -16,8 +21,9 @@ use scarab_engine::{
         registry::{TextureList, TextureRegistry},
         Camera, View,
     },
+    save::{self, SaveFormat},
     scene::Scene,
-    App, ScarabError, ScarabResult,
+    AntiAliasing, App, ScarabError, ScarabResult,
 };
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -26,6 +32,8 @@ use crate::{debug::DebugOptions, external_serde::EventSettingsDef};
 
 /// A semver-like version of the AppData's save format
 static SAVE_VERSION: &'static str = "0.1.0";
+/// How the AppData is compressed when it's saved
+const SAVE_FORMAT: SaveFormat = SaveFormat::Gzip;
 
 pub struct ExampleApp<E, V, I, J, D> {
     gl: GlGraphics, // OpenGL drawing backend.
@@ -80,14 +88,15 @@ where
     D: DeserializeOwned,
 {
     pub fn load_from_save(opengl: OpenGL, save_name: String) -> ScarabResult<Self> {
-        let window: Window = WindowSettings::new("scarab-example", [300, 400])
-            .graphics_api(opengl)
-            .exit_on_esc(true)
-            .build()
-            .unwrap(); // TODO: don't panic here
-
-        let file = File::open(&save_name).map_err(|e| ScarabError::RawString(format!("{:}", e)))?;
-        let app_data: AppData<E, V, I, J, D> = rmp_serde::from_read(file)
+        let window: Window = build_window(
+            WindowSettings::new("scarab-example", [300, 400])
+                .graphics_api(opengl)
+                .exit_on_esc(true),
+            AntiAliasing::PIXEL_ART,
+        )?;
+
+        let saved = fs::read(&save_name).map_err(|e| ScarabError::RawString(format!("{:}", e)))?;
+        let app_data: AppData<E, V, I, J, D> = rmp_serde::from_slice(&save::decompress(&saved)?)
             .map_err(|e| ScarabError::RawString(format!("Could not parse file: {:?}", e)))?;
 
         // Lazy version checking requires exact match.
@@ -153,6 +162,13 @@ where
             .scene
             .tick_entities(args.dt)
             .map_err(|e| println!("Ticking entities failed with error: {e:}"));
+
+        for interaction in self.data.scene.take_interactions() {
+            println!(
+                "{:?} interacted with {:?}: {}",
+                interaction.actor, interaction.target, interaction.action
+            );
+        }
     }
 
     fn resize(&mut self, args: &ResizeArgs) {
@@ -166,8 +182,12 @@ where
             .serialize(&mut rmp_serde::Serializer::new(&mut buf))
             .unwrap();
 
-        let _ = f
-            .write_all(&buf)
+        let _ = SAVE_FORMAT
+            .compress(&buf)
+            .and_then(|compressed| {
+                f.write_all(&compressed)
+                    .map_err(|e| ScarabError::RawString(format!("{:}", e)))
+            })
             .map_err(|e| println!("Saving app state failed with error: {e}"));
     }
 
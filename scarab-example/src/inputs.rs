@@ -14,13 +14,25 @@ use crate::{debug::DebugOptions, entities::Player};
 pub enum GameInputActions {
     SetPlayerMovement(Velocity),
     Attack,
+    DropThrough,
+    Dash,
+    Interact,
     Nop,
 }
 
+/// How long the player ignores one-way platforms after dropping through one
+const DROP_THROUGH_SECONDS: f64 = 0.25;
+
 #[derive(Serialize, Deserialize)]
 pub struct GameInputs<'a> {
     pub move_binding: Option<UnitAxis2dBinding>,
     pub attack_binding: Option<ButtonBinding>,
+    /// Drops through one-way platforms when pressed while moving down
+    pub drop_through_binding: Option<ButtonBinding>,
+    /// Dashes in the direction the player is moving
+    pub dash_binding: Option<ButtonBinding>,
+    /// Interacts with the nearest door, chest or NPC in range
+    pub interact_binding: Option<ButtonBinding>,
     phantom: PhantomData<&'a u8>,
 }
 
@@ -35,12 +47,30 @@ impl<'a> InputRegistry for GameInputs<'a> {
     ) -> ScarabResult<()> {
         match action {
             GameInputActions::SetPlayerMovement(vel) => {
-                target
-                    .entity
-                    .set_velocity(vel * target.entity.get_max_velocity());
+                if target.entity.can_move() && !target.entity.is_dashing() {
+                    target
+                        .entity
+                        .set_velocity(vel * target.entity.get_max_velocity());
+                }
             }
             GameInputActions::Attack => {
-                target.attack();
+                if target.entity.can_act() {
+                    target.attack();
+                }
+            }
+            GameInputActions::DropThrough => {
+                if target.entity.get_velocity().y > 0.0 {
+                    target.entity.drop_through_one_way(DROP_THROUGH_SECONDS);
+                }
+            }
+            GameInputActions::Dash => {
+                let velocity = target.entity.get_velocity();
+                target.entity.try_dash([velocity.x, velocity.y]);
+            }
+            GameInputActions::Interact => {
+                if target.entity.can_act() {
+                    target.entity.request_interaction();
+                }
             }
             GameInputActions::Nop => {}
         }
@@ -68,6 +98,42 @@ impl<'a> InputRegistry for GameInputs<'a> {
                     })
                     .flatten()
             })
+            .or_else(|| {
+                self.drop_through_binding
+                    .as_mut()
+                    .and_then(|binding| binding.maybe_to_action(input))
+                    .and_then(|state| {
+                        if state {
+                            Some(GameInputActions::DropThrough)
+                        } else {
+                            None
+                        }
+                    })
+            })
+            .or_else(|| {
+                self.dash_binding
+                    .as_mut()
+                    .and_then(|binding| binding.maybe_to_action(input))
+                    .and_then(|state| {
+                        if state {
+                            Some(GameInputActions::Dash)
+                        } else {
+                            None
+                        }
+                    })
+            })
+            .or_else(|| {
+                self.interact_binding
+                    .as_mut()
+                    .and_then(|binding| binding.maybe_to_action(input))
+                    .and_then(|state| {
+                        if state {
+                            Some(GameInputActions::Interact)
+                        } else {
+                            None
+                        }
+                    })
+            })
     }
 }
 
@@ -76,6 +142,9 @@ impl<'a> GameInputs<'a> {
         Self {
             move_binding: None,
             attack_binding: None,
+            drop_through_binding: None,
+            dash_binding: None,
+            interact_binding: None,
             phantom: PhantomData::default(),
         }
     }
@@ -4,8 +4,9 @@ use debug::{DebugOptions, FieldDebug};
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::{ButtonState, EventSettings, Key, Window, WindowSettings};
 use scarab_engine::{
+    build_window,
     gameobject::{
-        entity::Entity,
+        entity::{dash::Dash, Entity},
         field::{Cell, CellColorView, Field, FieldColorView},
     },
     input::{ButtonBinding, LogicalDpad, SingleButton, VirtualDpad},
@@ -20,7 +21,7 @@ use scarab_engine::{
         physbox::{HasBoxMut, PhysBox},
         Axis, NO_SOLIDITY, SOLID,
     },
-    App, GlutinWindow, LogicalSize, ScarabResult,
+    AntiAliasing, App, GlutinWindow, LogicalSize, ScarabResult,
 };
 
 use self::{
@@ -40,11 +41,12 @@ const MS_PER_FRAME: f64 = 1000.0 / 15.0;
 fn main() -> ScarabResult<()> {
     let camera_size = [640, 360];
     let opengl = OpenGL::V3_2;
-    let window: GlutinWindow = WindowSettings::new("scarab-example", camera_size)
-        .graphics_api(opengl)
-        .exit_on_esc(true)
-        .build()
-        .unwrap(); // TODO! log a more readable message before panicking
+    let window: GlutinWindow = build_window(
+        WindowSettings::new("scarab-example", camera_size)
+            .graphics_api(opengl)
+            .exit_on_esc(true),
+        AntiAliasing::PIXEL_ART,
+    )?;
     let gl = GlGraphics::new(opengl);
     window
         .ctx
@@ -140,6 +142,7 @@ fn main() -> ScarabResult<()> {
     b.set_pos([310.0, 170.0].into());
     b.set_size([20.0, 20.0].into())?;
     p.set_max_velocity(75.0)?;
+    p.set_dash(Some(Dash::new(60.0, 0.15, 0.75)?));
     let player = Player::new(p, 2.0, 1.0);
 
     // Create the enemy setting its position, size and max speed
@@ -195,6 +198,18 @@ fn main() -> ScarabResult<()> {
         ButtonState::Press,
         SingleButton::Mouse(piston::MouseButton::Left),
     ));
+    game_input_registry.drop_through_binding = Some(ButtonBinding::new(
+        ButtonState::Press,
+        SingleButton::Keyboard(Key::Space),
+    ));
+    game_input_registry.dash_binding = Some(ButtonBinding::new(
+        ButtonState::Press,
+        SingleButton::Keyboard(Key::LShift),
+    ));
+    game_input_registry.interact_binding = Some(ButtonBinding::new(
+        ButtonState::Press,
+        SingleButton::Keyboard(Key::E),
+    ));
 
     let mut app_input_registry = AppInputs::default();
     app_input_registry.toggle_debug_entity_collision_boxes = Some(ButtonBinding::new(